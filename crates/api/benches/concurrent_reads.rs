@@ -0,0 +1,170 @@
+//! Concurrent read throughput of a SQLite queue served through a [`BackendAdapter`].
+//!
+//! Every reader fetches tasks by id or lists a page of tasks. For comparison,
+//! the same reads are measured on the bare storage, cloned per read like the
+//! adapter does, and behind an `Arc<tokio::sync::RwLock<_>>`, the way the
+//! adapter used to share it, fetching under the write lock. Run with
+//! `cargo bench -p apalis-board-api --bench concurrent_reads`.
+
+use std::{
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use apalis_board_api::{
+    backend::{BackendAdapter, DynBackend},
+    framework::QueueOptions,
+};
+use apalis_core::{
+    backend::{FetchById, Filter, ListTasks},
+    task::task_id::TaskId,
+};
+use apalis_sqlite::{SqlitePool, SqliteStorage};
+use serde_json::json;
+use tokio::sync::RwLock;
+
+/// Tasks pushed before measuring.
+const TASKS: usize = 200;
+/// Reads per measurement, spread over the readers.
+const READS: usize = 4_000;
+
+#[derive(Clone, Copy, Debug)]
+enum Read {
+    FetchById,
+    ListTasks,
+}
+
+#[tokio::main]
+async fn main() {
+    let path =
+        std::env::temp_dir().join(format!("apalis-board-bench-{}.sqlite", std::process::id()));
+    let pool = SqlitePool::connect(&format!("sqlite://{}?mode=rwc", path.display()))
+        .await
+        .expect("could not open the database");
+    SqliteStorage::setup(&pool).await.unwrap();
+    let storage = SqliteStorage::<String, _, _>::new_in_queue(&pool, "bench");
+    let cloned = storage.clone();
+    let locked = Arc::new(RwLock::new(storage.clone()));
+    let queue: DynBackend = Arc::new(BackendAdapter::new(storage, QueueOptions::new()));
+
+    for i in 0..TASKS {
+        queue.push_task(json!(format!("task-{i}"))).await.unwrap();
+    }
+    let filter = Filter {
+        status: None,
+        page: 1,
+        page_size: Some(TASKS as u32),
+    };
+    let ids: Arc<[String]> = queue
+        .list_tasks(&filter)
+        .await
+        .unwrap()
+        .iter()
+        .filter_map(|task| task["parts"]["task_id"].as_str().map(str::to_owned))
+        .collect();
+    assert_eq!(ids.len(), TASKS);
+
+    let page = Filter {
+        status: None,
+        page: 1,
+        page_size: Some(10),
+    };
+    for read in [Read::FetchById, Read::ListTasks] {
+        println!("{read:?}");
+        for readers in [1, 4, 16, 64] {
+            let shared = {
+                let (queue, ids, page) = (queue.clone(), ids.clone(), page.clone());
+                measure(readers, move |i| {
+                    let (queue, ids, page) = (queue.clone(), ids.clone(), page.clone());
+                    async move {
+                        match read {
+                            Read::FetchById => {
+                                let task = queue.fetch_task(&ids[i % ids.len()]).await.unwrap();
+                                assert!(task.is_some());
+                            }
+                            Read::ListTasks => {
+                                queue.list_tasks(&page).await.unwrap();
+                            }
+                        }
+                    }
+                })
+                .await
+            };
+            let bare = {
+                let (cloned, ids, page) = (cloned.clone(), ids.clone(), page.clone());
+                measure(readers, move |i| {
+                    let (mut storage, ids, page) = (cloned.clone(), ids.clone(), page.clone());
+                    async move {
+                        match read {
+                            Read::FetchById => {
+                                let id: TaskId<_> = ids[i % ids.len()].parse().unwrap();
+                                let task = storage.fetch_by_id(&id).await.unwrap();
+                                assert!(task.is_some());
+                            }
+                            Read::ListTasks => {
+                                storage.list_tasks(&page).await.unwrap();
+                            }
+                        }
+                    }
+                })
+                .await
+            };
+            let behind_lock = {
+                let (locked, ids, page) = (locked.clone(), ids.clone(), page.clone());
+                measure(readers, move |i| {
+                    let (locked, ids, page) = (locked.clone(), ids.clone(), page.clone());
+                    async move {
+                        match read {
+                            Read::FetchById => {
+                                let id: TaskId<_> = ids[i % ids.len()].parse().unwrap();
+                                let task = locked.write().await.fetch_by_id(&id).await.unwrap();
+                                assert!(task.is_some());
+                            }
+                            Read::ListTasks => {
+                                locked.read().await.list_tasks(&page).await.unwrap();
+                            }
+                        }
+                    }
+                })
+                .await
+            };
+            let rate = |elapsed: Duration| READS as f64 / elapsed.as_secs_f64();
+            println!(
+                "  {readers:>3} readers: {:>8.0} reads/s, bare {:>8.0}, behind a RwLock {:>8.0}",
+                rate(shared),
+                rate(bare),
+                rate(behind_lock)
+            );
+        }
+    }
+
+    pool.close().await;
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{suffix}", path.display()));
+    }
+}
+
+/// The time `readers` concurrent readers take to perform [`READS`] reads,
+/// `read(i)` performing the `i`th one.
+async fn measure<F, Fut>(readers: usize, read: F) -> Duration
+where
+    F: Fn(usize) -> Fut + Clone + Send + 'static,
+    Fut: Future<Output = ()> + Send,
+{
+    let start = Instant::now();
+    let handles: Vec<_> = (0..readers)
+        .map(|reader| {
+            let read = read.clone();
+            tokio::spawn(async move {
+                for i in (reader..READS).step_by(readers) {
+                    read(i).await;
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.await.unwrap();
+    }
+    start.elapsed()
+}
@@ -0,0 +1,74 @@
+//! Precompress the embedded frontend, see `ServeUI`.
+
+fn main() {
+    #[cfg(feature = "ui")]
+    ui::compress();
+}
+
+#[cfg(feature = "ui")]
+mod ui {
+    use std::{
+        env, fs,
+        io::Write,
+        path::{Path, PathBuf},
+    };
+
+    /// The extensions worth compressing, images and fonts already are.
+    const COMPRESSIBLE: &[&str] = &["html", "js", "mjs", "css", "wasm", "svg", "json", "ico"];
+
+    /// Write a `.gz` and a `.br` variant of every compressible file of `dist`
+    /// to `$OUT_DIR/ui`, when it is smaller than the file.
+    pub(crate) fn compress() {
+        let dist = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join("dist");
+        let out = PathBuf::from(env::var("OUT_DIR").unwrap()).join("ui");
+        println!("cargo:rerun-if-changed={}", dist.display());
+        let _ = fs::remove_dir_all(&out);
+        fs::create_dir_all(&out).unwrap();
+        walk(&dist, &dist, &out);
+    }
+
+    fn walk(dir: &Path, dist: &Path, out: &Path) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                walk(&path, dist, out);
+                continue;
+            }
+            let compressible = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| COMPRESSIBLE.contains(&e));
+            if !compressible {
+                continue;
+            }
+            let contents = fs::read(&path).unwrap();
+            let target = out.join(path.strip_prefix(dist).unwrap());
+            fs::create_dir_all(target.parent().unwrap()).unwrap();
+            write_smaller(&target, "gz", &gzip(&contents), contents.len());
+            write_smaller(&target, "br", &brotli(&contents), contents.len());
+        }
+    }
+
+    fn write_smaller(target: &Path, ext: &str, compressed: &[u8], len: usize) {
+        if compressed.len() < len {
+            let mut name = target.as_os_str().to_owned();
+            name.push(format!(".{ext}"));
+            fs::write(name, compressed).unwrap();
+        }
+    }
+
+    fn gzip(contents: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        encoder.write_all(contents).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn brotli(contents: &[u8]) -> Vec<u8> {
+        let mut compressed = Vec::new();
+        {
+            let mut encoder = brotli::CompressorWriter::new(&mut compressed, 4096, 9, 22);
+            encoder.write_all(contents).unwrap();
+        }
+        compressed
+    }
+}
@@ -0,0 +1,382 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    fs::{File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    net::IpAddr,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, RwLock},
+};
+
+use apalis_board_types::{ApiError, AuditAction, AuditQuery, AuditRecord, RequestOrigin, User};
+use futures::future::{BoxFuture, FutureExt, ready};
+
+use crate::{now, proxy::TrustedProxies};
+
+/// Keeps the [`AuditRecord`]s of the changes made through the board.
+pub trait AuditSink: Send + Sync + 'static {
+    /// Save a record.
+    fn record(&self, record: AuditRecord) -> BoxFuture<'_, Result<(), ApiError>>;
+
+    /// The records passing `query`, newest first.
+    fn query<'a>(
+        &'a self,
+        query: &'a AuditQuery,
+    ) -> BoxFuture<'a, Result<Vec<AuditRecord>, ApiError>>;
+}
+
+/// Keeps the latest records in memory, they are lost on restart.
+#[derive(Debug)]
+pub struct MemoryAuditLog {
+    records: RwLock<VecDeque<AuditRecord>>,
+    capacity: usize,
+}
+
+impl MemoryAuditLog {
+    /// The number of records kept by [`MemoryAuditLog::new`].
+    pub const DEFAULT_CAPACITY: usize = 10_000;
+
+    /// Keep the latest [`DEFAULT_CAPACITY`](Self::DEFAULT_CAPACITY) records.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_capacity(Self::DEFAULT_CAPACITY)
+    }
+
+    /// Keep the latest `capacity` records.
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            records: RwLock::new(VecDeque::new()),
+            capacity,
+        }
+    }
+}
+
+impl Default for MemoryAuditLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AuditSink for MemoryAuditLog {
+    fn record(&self, record: AuditRecord) -> BoxFuture<'_, Result<(), ApiError>> {
+        let mut records = self.records.write().expect("audit log poisoned");
+        if records.len() >= self.capacity {
+            records.pop_front();
+        }
+        records.push_back(record);
+        ready(Ok(())).boxed()
+    }
+
+    fn query<'a>(
+        &'a self,
+        query: &'a AuditQuery,
+    ) -> BoxFuture<'a, Result<Vec<AuditRecord>, ApiError>> {
+        let records = self.records.read().expect("audit log poisoned");
+        let found = newest(records.iter().cloned(), query);
+        ready(Ok(found)).boxed()
+    }
+}
+
+/// Appends records to a file, one JSON object per line.
+///
+/// The file is written and read on the blocking thread pool. Queries read it
+/// backwards from the end and stop once they found enough records.
+#[derive(Debug)]
+pub struct FileAuditLog {
+    path: PathBuf,
+    file: Arc<Mutex<File>>,
+}
+
+impl FileAuditLog {
+    /// How many bytes of the file are read at once by a query.
+    const BLOCK: u64 = 64 * 1024;
+
+    /// Append to `path`, which is created if missing.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self {
+            path,
+            file: Arc::new(Mutex::new(file)),
+        })
+    }
+
+    /// The records of the file at `path` passing `query`, newest first.
+    fn tail(path: &Path, query: &AuditQuery) -> io::Result<Vec<AuditRecord>> {
+        let mut file = File::open(path)?;
+        let mut end = file.metadata()?.len();
+        let mut found = Vec::new();
+        // The start of the oldest line read so far, which may go on in the previous block
+        let mut partial = Vec::new();
+        while end > 0 && found.len() < query.limit() {
+            let start = end.saturating_sub(Self::BLOCK);
+            let mut block = Vec::new();
+            file.seek(SeekFrom::Start(start))?;
+            (&mut file).take(end - start).read_to_end(&mut block)?;
+            block.append(&mut partial);
+            end = start;
+
+            let mut lines = block.split(|b| *b == b'\n');
+            if start > 0 {
+                partial = lines.next().unwrap_or_default().to_vec();
+            }
+            for line in lines.rev() {
+                // Skip lines torn by a crash instead of hiding the whole log
+                let Ok(record) = serde_json::from_slice::<AuditRecord>(line) else {
+                    continue;
+                };
+                if query.matches(&record) && found.len() < query.limit() {
+                    found.push(record);
+                }
+            }
+        }
+        Ok(found)
+    }
+}
+
+impl AuditSink for FileAuditLog {
+    fn record(&self, record: AuditRecord) -> BoxFuture<'_, Result<(), ApiError>> {
+        let file = self.file.clone();
+        let write = move || -> io::Result<()> {
+            let mut line = serde_json::to_vec(&record)?;
+            line.push(b'\n');
+            let mut file = file.lock().expect("audit log poisoned");
+            file.write_all(&line)?;
+            file.flush()
+        };
+        crate::blocking(write)
+            .map(|res| {
+                res.map_err(|e| {
+                    ApiError::BackendError(format!("Could not write the audit log: {e}"))
+                })
+            })
+            .boxed()
+    }
+
+    fn query<'a>(
+        &'a self,
+        query: &'a AuditQuery,
+    ) -> BoxFuture<'a, Result<Vec<AuditRecord>, ApiError>> {
+        let (path, query) = (self.path.clone(), query.clone());
+        crate::blocking(move || Self::tail(&path, &query))
+            .map(|res| {
+                res.map_err(|e| {
+                    ApiError::BackendError(format!("Could not read the audit log: {e}"))
+                })
+            })
+            .boxed()
+    }
+}
+
+/// The records of `records`, oldest first, passing `query`, newest first.
+fn newest(
+    records: impl DoubleEndedIterator<Item = AuditRecord>,
+    query: &AuditQuery,
+) -> Vec<AuditRecord> {
+    records
+        .rev()
+        .filter(|r| query.matches(r))
+        .take(query.limit())
+        .collect()
+}
+
+/// Records changes made through the board, see [`ApiBuilder::audit`](crate::framework::ApiBuilder::audit).
+#[derive(Clone)]
+pub struct Audit(Arc<dyn AuditSink>);
+
+impl fmt::Debug for Audit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Audit").finish_non_exhaustive()
+    }
+}
+
+impl Default for Audit {
+    fn default() -> Self {
+        Self::new(MemoryAuditLog::new())
+    }
+}
+
+impl Audit {
+    /// Keep the records in `sink`.
+    pub fn new(sink: impl AuditSink) -> Self {
+        Self(Arc::new(sink))
+    }
+
+    /// The records passing `query`, newest first.
+    pub async fn query(&self, query: &AuditQuery) -> Result<Vec<AuditRecord>, ApiError> {
+        self.0.query(query).await
+    }
+
+    /// Save `record`, along with the error of `outcome` if the change failed.
+    ///
+    /// The change already happened, so a failing sink is only logged.
+    pub async fn record<T>(&self, mut record: AuditRecord, outcome: &Result<T, ApiError>) {
+        record.error = outcome.as_ref().err().map(ToString::to_string);
+        if let Err(e) = self.0.record(record).await {
+            tracing::warn!(error = %e, "Could not record a change to the audit log");
+        }
+    }
+}
+
+/// A record of `action`, performed by `user` now.
+#[must_use]
+pub fn entry(user: Option<&User>, action: AuditAction, origin: RequestOrigin) -> AuditRecord {
+    let (actor_id, actor) = user.map_or_else(
+        || {
+            let anonymous = User::anonymous();
+            (anonymous.id, anonymous.username)
+        },
+        |u| (u.id.clone(), u.username.clone()),
+    );
+    AuditRecord {
+        actor,
+        actor_id,
+        action,
+        queue: None,
+        task_ids: Vec::new(),
+        target: None,
+        timestamp: now(),
+        origin,
+        error: None,
+    }
+}
+
+/// The origin of a request from the address of the peer and its `User-Agent`.
+///
+/// `X-Forwarded-For` is only followed when the peer is one of `proxies`.
+#[must_use]
+pub fn origin(
+    proxies: &TrustedProxies,
+    peer: Option<IpAddr>,
+    forwarded_for: Option<&str>,
+    user_agent: Option<&str>,
+) -> RequestOrigin {
+    RequestOrigin {
+        address: proxies
+            .client(peer, forwarded_for)
+            .map(|addr| addr.to_string()),
+        user_agent: user_agent.map(str::to_owned),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block_on<T>(fut: impl std::future::Future<Output = T>) -> T {
+        futures::executor::block_on(fut)
+    }
+
+    fn push(actor: &str, queue: &str) -> AuditRecord {
+        AuditRecord {
+            queue: Some(queue.to_owned()),
+            ..entry(
+                Some(&User::new(actor)),
+                AuditAction::Push,
+                RequestOrigin::default(),
+            )
+        }
+    }
+
+    #[test]
+    fn test_memory_log_filters_newest_first() {
+        let audit = Audit::new(MemoryAuditLog::with_capacity(3));
+        for (actor, queue) in [("a", "emails"), ("b", "payments"), ("c", "payments")] {
+            block_on(audit.record(push(actor, queue), &Ok(())));
+        }
+        let failed: Result<(), _> = Err(ApiError::Validation("bad".to_owned()));
+        block_on(audit.record(push("d", "payments"), &failed));
+
+        let all = block_on(audit.query(&AuditQuery::default())).unwrap();
+        let actors: Vec<_> = all.iter().map(|r| r.actor.as_str()).collect();
+        assert_eq!(actors, ["d", "c", "b"]);
+        assert_eq!(all[0].error.as_deref(), Some("Validation error: bad"));
+
+        let query = AuditQuery {
+            actor: Some("b".to_owned()),
+            queue: Some("payments".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(block_on(audit.query(&query)).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_file_log_survives_reopening() {
+        let path =
+            std::env::temp_dir().join(format!("apalis-board-audit-{}.jsonl", std::process::id()));
+        let audit = Audit::new(FileAuditLog::open(&path).unwrap());
+        audit.record(push("ops", "emails"), &Ok(())).await;
+
+        let reopened = Audit::new(FileAuditLog::open(&path).unwrap());
+        reopened.record(push("ci", "emails"), &Ok(())).await;
+        let records = reopened.query(&AuditQuery::default()).await.unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].actor, "ci");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_file_log_is_read_from_the_end() {
+        let path = std::env::temp_dir().join(format!(
+            "apalis-board-audit-tail-{}.jsonl",
+            std::process::id()
+        ));
+        let audit = Audit::new(FileAuditLog::open(&path).unwrap());
+        // Enough records to span several blocks, with a torn line in between
+        for i in 0..2_000 {
+            let queue = if i % 2 == 0 { "emails" } else { "payments" };
+            audit
+                .record(push(&format!("ops-{i}"), queue), &Ok(()))
+                .await;
+        }
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"{\"actor\":\n")
+            .unwrap();
+        audit.record(push("ci", "emails"), &Ok(())).await;
+
+        let query = AuditQuery {
+            queue: Some("payments".to_owned()),
+            limit: Some(3),
+            ..Default::default()
+        };
+        let records = audit.query(&query).await.unwrap();
+        let actors: Vec<_> = records.iter().map(|r| r.actor.as_str()).collect();
+        assert_eq!(actors, ["ops-1999", "ops-1997", "ops-1995"]);
+
+        let query = AuditQuery {
+            limit: Some(usize::MAX),
+            ..Default::default()
+        };
+        let records = audit.query(&query).await.unwrap();
+        assert_eq!(records.len(), 2_001);
+        assert_eq!(records[0].actor, "ci");
+        assert_eq!(records[2_000].actor, "ops-0");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_entries_tell_tokens_from_users() {
+        let mut token = User::new("alice");
+        token.id = "token:01JABCDEFGHJKMNPQRSTVWXYZ0".to_owned();
+        let record = entry(Some(&token), AuditAction::Push, RequestOrigin::default());
+        assert_eq!(record.actor, "alice");
+        assert_eq!(record.actor_id, "token:01JABCDEFGHJKMNPQRSTVWXYZ0");
+        assert_ne!(record.actor_id, push("alice", "emails").actor_id);
+    }
+
+    #[test]
+    fn test_origin_only_trusts_forwarded_address_from_proxies() {
+        let peer = "10.0.0.1".parse().ok();
+        let forwarded = Some("203.0.113.7");
+        let proxied = origin(&TrustedProxies::new(peer), peer, forwarded, Some("curl/8"));
+        assert_eq!(proxied.address.as_deref(), Some("203.0.113.7"));
+        assert_eq!(proxied.user_agent.as_deref(), Some("curl/8"));
+
+        let direct = origin(&TrustedProxies::default(), peer, forwarded, None);
+        assert_eq!(direct.address.as_deref(), Some("10.0.0.1"));
+    }
+}
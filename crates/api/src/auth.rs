@@ -0,0 +1,433 @@
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use apalis_board_types::{ApiError, Grant, Permission, User};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use futures::future::{BoxFuture, FutureExt};
+
+/// The query parameter accepted in place of the `Authorization` header on `/events`.
+///
+/// Browsers cannot set headers on an `EventSource`, so `/events` is usually
+/// authenticated with `?authorization=Bearer%20<token>`. Other routes ignore
+/// it, so credentials don't end up in the access logs of every request.
+pub const AUTHORIZATION_PARAM: &str = "authorization";
+
+/// The parts of a request that may carry credentials.
+#[derive(Debug, Clone, Default)]
+pub struct Credentials {
+    /// The `Authorization` header, or the [`AUTHORIZATION_PARAM`] query parameter.
+    pub authorization: Option<String>,
+    /// The `Cookie` header.
+    pub cookie: Option<String>,
+}
+
+/// The query string of a request to `path` that may carry credentials, see
+/// [`AUTHORIZATION_PARAM`].
+#[cfg(any(feature = "axum", feature = "actix"))]
+pub(crate) fn credentials_query<'a>(path: &str, query: Option<&'a str>) -> Option<&'a str> {
+    query.filter(|_| path.trim_end_matches('/').ends_with("/events"))
+}
+
+impl Credentials {
+    /// Collect the credentials from a request's headers and query string.
+    ///
+    /// Only pass the query string of routes that can't send headers.
+    #[must_use]
+    pub fn new(authorization: Option<&str>, cookie: Option<&str>, query: Option<&str>) -> Self {
+        let authorization = authorization.map(ToOwned::to_owned).or_else(|| {
+            query?
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(key, _)| *key == AUTHORIZATION_PARAM)
+                .map(|(_, value)| percent_decode(value))
+        });
+        Self {
+            authorization,
+            cookie: cookie.map(ToOwned::to_owned),
+        }
+    }
+
+    /// The token of a `Bearer` authorization.
+    #[must_use]
+    pub fn bearer(&self) -> Option<&str> {
+        let (scheme, token) = self.authorization.as_deref()?.split_once(' ')?;
+        scheme
+            .eq_ignore_ascii_case("bearer")
+            .then_some(token.trim())
+    }
+
+    /// The username and password of a `Basic` authorization.
+    #[must_use]
+    pub fn basic(&self) -> Option<(String, String)> {
+        let (scheme, encoded) = self.authorization.as_deref()?.split_once(' ')?;
+        if !scheme.eq_ignore_ascii_case("basic") {
+            return None;
+        }
+        let decoded = String::from_utf8(STANDARD.decode(encoded.trim()).ok()?).ok()?;
+        let (username, password) = decoded.split_once(':')?;
+        Some((username.to_owned(), password.to_owned()))
+    }
+
+    /// The value of a cookie.
+    #[must_use]
+    pub fn cookie(&self, name: &str) -> Option<&str> {
+        self.cookie
+            .as_deref()?
+            .split(';')
+            .filter_map(|pair| pair.trim().split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+    }
+}
+
+/// Decides who a request belongs to.
+///
+/// Implement this to plug in session cookies, JWTs or any other scheme, and
+/// pass it to [`ApiBuilder::authenticator`](crate::framework::ApiBuilder::authenticator).
+pub trait Authenticator: Send + Sync + 'static {
+    /// Resolve the user, or fail with [`ApiError::Unauthorized`].
+    fn authenticate<'a>(
+        &'a self,
+        credentials: &'a Credentials,
+    ) -> BoxFuture<'a, Result<User, ApiError>>;
+
+    /// The `WWW-Authenticate` challenge sent with unauthorized responses.
+    fn challenge(&self) -> Option<String> {
+        None
+    }
+}
+
+/// A cloneable handle to an [`Authenticator`].
+#[derive(Clone)]
+pub struct Auth(Arc<dyn Authenticator>);
+
+impl Auth {
+    /// Wrap an authenticator.
+    pub fn new(authenticator: impl Authenticator) -> Self {
+        Self(Arc::new(authenticator))
+    }
+
+    /// Authenticate a request.
+    pub async fn authenticate(&self, credentials: &Credentials) -> Result<User, ApiError> {
+        self.0.authenticate(credentials).await
+    }
+
+    /// The `WWW-Authenticate` challenge of the authenticator.
+    #[must_use]
+    pub fn challenge(&self) -> Option<String> {
+        self.0.challenge()
+    }
+}
+
+impl Auth {
+    /// Fall back to `other` when this authenticator rejects the credentials.
+    #[must_use]
+    pub fn or(self, other: Self) -> Self {
+        Self(Arc::new(Either(self, other)))
+    }
+}
+
+/// Tries the first authenticator, then the second.
+struct Either(Auth, Auth);
+
+impl Authenticator for Either {
+    fn authenticate<'a>(
+        &'a self,
+        credentials: &'a Credentials,
+    ) -> BoxFuture<'a, Result<User, ApiError>> {
+        async move {
+            match self.0.authenticate(credentials).await {
+                Err(ApiError::Unauthorized(_)) => self.1.authenticate(credentials).await,
+                res => res,
+            }
+        }
+        .boxed()
+    }
+
+    fn challenge(&self) -> Option<String> {
+        self.0.challenge().or_else(|| self.1.challenge())
+    }
+}
+
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Auth").finish_non_exhaustive()
+    }
+}
+
+/// Authenticate requests with static `Bearer` tokens.
+#[derive(Clone, Default)]
+pub struct BearerAuth {
+    tokens: HashMap<String, User>,
+}
+
+impl BearerAuth {
+    /// Create an authenticator that accepts no tokens.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept `token` as `user`.
+    #[must_use]
+    pub fn token(mut self, token: impl Into<String>, user: User) -> Self {
+        self.tokens.insert(token.into(), user);
+        self
+    }
+}
+
+impl fmt::Debug for BearerAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let users = self
+            .tokens
+            .values()
+            .map(|u| &u.username)
+            .collect::<Vec<_>>();
+        f.debug_struct("BearerAuth").field("users", &users).finish()
+    }
+}
+
+impl Authenticator for BearerAuth {
+    fn authenticate<'a>(
+        &'a self,
+        credentials: &'a Credentials,
+    ) -> BoxFuture<'a, Result<User, ApiError>> {
+        let user = credentials
+            .bearer()
+            .and_then(|token| {
+                self.tokens
+                    .iter()
+                    .find(|(known, _)| constant_time_eq(known.as_bytes(), token.as_bytes()))
+            })
+            .map(|(_, user)| user.clone())
+            .ok_or_else(|| ApiError::Unauthorized("Invalid or missing bearer token".to_owned()));
+        futures::future::ready(user).boxed()
+    }
+
+    fn challenge(&self) -> Option<String> {
+        Some("Bearer".to_owned())
+    }
+}
+
+/// Authenticate requests with HTTP `Basic` usernames and passwords.
+#[derive(Clone, Default)]
+pub struct BasicAuth {
+    /// The password and grants of every username.
+    users: HashMap<String, (String, Vec<Grant>)>,
+    realm: Option<String>,
+}
+
+impl BasicAuth {
+    /// Create an authenticator that accepts no users.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept `username` with `password`, granting it `grants`.
+    ///
+    /// A user without grants is authenticated but may do nothing.
+    #[must_use]
+    pub fn user(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+        grants: impl IntoIterator<Item = Grant>,
+    ) -> Self {
+        self.users.insert(
+            username.into(),
+            (password.into(), grants.into_iter().collect()),
+        );
+        self
+    }
+
+    /// Ask browsers to prompt for credentials in `realm`.
+    ///
+    /// Without a realm the board's login screen is used instead.
+    #[must_use]
+    pub fn realm(mut self, realm: impl Into<String>) -> Self {
+        self.realm = Some(realm.into());
+        self
+    }
+}
+
+impl fmt::Debug for BasicAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BasicAuth")
+            .field("users", &self.users.keys().collect::<Vec<_>>())
+            .field("realm", &self.realm)
+            .finish()
+    }
+}
+
+impl Authenticator for BasicAuth {
+    fn authenticate<'a>(
+        &'a self,
+        credentials: &'a Credentials,
+    ) -> BoxFuture<'a, Result<User, ApiError>> {
+        let user = credentials
+            .basic()
+            .and_then(|(username, password)| {
+                let (expected, grants) = self.users.get(&username)?;
+                constant_time_eq(expected.as_bytes(), password.as_bytes()).then(|| User {
+                    grants: grants.clone(),
+                    ..User::new(username)
+                })
+            })
+            .ok_or_else(|| ApiError::Unauthorized("Invalid username or password".to_owned()));
+        futures::future::ready(user).boxed()
+    }
+
+    fn challenge(&self) -> Option<String> {
+        self.realm
+            .as_ref()
+            .map(|realm| format!("Basic realm=\"{realm}\", charset=\"UTF-8\""))
+    }
+}
+
+/// The queue name standing for every queue, including those registered later.
+///
+/// Only grants on `*` cover it. The root routes mixing the items of every
+/// queue, like `/tasks` or `/overview`, require it.
+pub const EVERY_QUEUE: &str = "*";
+
+/// Whether `user` may read `queue`, to filter the root routes listing queues.
+#[cfg(any(feature = "axum", feature = "actix"))]
+pub(crate) fn can_read(user: Option<&User>, queue: &str) -> bool {
+    authorize(user, Permission::Read, Some(queue)).is_ok()
+}
+
+/// Check that `user` may perform `permission` on `queue`, see [`EVERY_QUEUE`].
+///
+/// Requests without a user come from boards without an authenticator and are allowed.
+pub fn authorize(
+    user: Option<&User>,
+    permission: Permission,
+    queue: Option<&str>,
+) -> Result<(), ApiError> {
+    match user {
+        Some(user) if !user.can(permission, queue) => Err(ApiError::Forbidden(format!(
+            "`{}` lacks the `{}` permission{}",
+            user.username,
+            permission.as_str(),
+            queue.map_or_else(String::new, |q| format!(" on `{q}`"))
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Compare secrets without leaking where they differ.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (escaped, bytes[i]) {
+            (Some(byte), _) => {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+            (None, b'+') => out.push(b' '),
+            (None, byte) => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use apalis_board_types::Role;
+
+    fn block_on<T>(fut: BoxFuture<'_, T>) -> T {
+        futures::executor::block_on(fut)
+    }
+
+    #[test]
+    fn test_bearer_from_header_and_query() {
+        let auth = BearerAuth::new().token("s3cret", User::new("ci").grant(Role::Viewer, "*"));
+
+        let header = Credentials::new(Some("Bearer s3cret"), None, None);
+        assert_eq!(block_on(auth.authenticate(&header)).unwrap().username, "ci");
+
+        let query = Credentials::new(None, None, Some("x=1&authorization=Bearer%20s3cret"));
+        assert_eq!(block_on(auth.authenticate(&query)).unwrap().username, "ci");
+
+        let wrong = Credentials::new(Some("Bearer nope"), None, None);
+        assert!(matches!(
+            block_on(auth.authenticate(&wrong)),
+            Err(ApiError::Unauthorized(_))
+        ));
+    }
+
+    #[cfg(any(feature = "axum", feature = "actix"))]
+    #[test]
+    fn test_query_credentials_only_on_events() {
+        let query = Some("authorization=Bearer%20s3cret");
+        assert_eq!(credentials_query("/api/v1/events", query), query);
+        assert_eq!(credentials_query("/events/", query), query);
+        assert_eq!(credentials_query("/api/v1/tasks", query), None);
+        assert_eq!(
+            credentials_query("/api/v1/queues/events/tasks", query),
+            None
+        );
+    }
+
+    #[test]
+    fn test_basic() {
+        let auth = BasicAuth::new().user("admin", "hunter2", [Grant::new(Role::Admin, "*")]);
+        // admin:hunter2
+        let ok = Credentials::new(Some("Basic YWRtaW46aHVudGVyMg=="), None, None);
+        assert_eq!(
+            block_on(auth.authenticate(&ok)).unwrap(),
+            User::new("admin").grant(Role::Admin, "*")
+        );
+
+        // admin:hunter3
+        let wrong = Credentials::new(Some("Basic YWRtaW46aHVudGVyMw=="), None, None);
+        assert!(block_on(auth.authenticate(&wrong)).is_err());
+        assert!(auth.challenge().is_none());
+    }
+
+    #[test]
+    fn test_roles_are_scoped_to_queues() {
+        let user = User::new("ops")
+            .grant(Role::Viewer, "*")
+            .grant(Role::Operator, "emails-*");
+        assert!(authorize(Some(&user), Permission::Read, None).is_ok());
+        assert!(authorize(Some(&user), Permission::Read, Some("sms")).is_ok());
+        assert!(authorize(Some(&user), Permission::Push, Some("emails-eu")).is_ok());
+        assert!(matches!(
+            authorize(Some(&user), Permission::Push, Some("sms")),
+            Err(ApiError::Forbidden(_))
+        ));
+        assert!(authorize(Some(&user), Permission::Purge, Some("emails-eu")).is_err());
+
+        assert!(authorize(Some(&user), Permission::Read, Some(EVERY_QUEUE)).is_ok());
+        assert!(authorize(Some(&user), Permission::Push, Some(EVERY_QUEUE)).is_err());
+        assert!(authorize(Some(&User::new("nobody")), Permission::Read, Some("sms")).is_err());
+        assert!(authorize(Some(&User::new("nobody")), Permission::Read, None).is_err());
+        assert!(authorize(Some(&User::anonymous()), Permission::Pause, Some("sms")).is_ok());
+        assert!(authorize(None, Permission::Purge, None).is_ok());
+        assert!(!Grant::new(Role::Admin, "a*c*e").matches("abcd"));
+        assert!(Grant::new(Role::Admin, "a*c*e").matches("abcde"));
+        assert!(!Grant::new(Role::Admin, "emails").matches("emails-eu"));
+    }
+
+    #[test]
+    fn test_cookie() {
+        let credentials = Credentials::new(None, Some("theme=dark; session=abc"), None);
+        assert_eq!(credentials.cookie("session"), Some("abc"));
+        assert_eq!(credentials.cookie("missing"), None);
+    }
+}
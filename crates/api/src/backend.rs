@@ -0,0 +1,430 @@
+use std::{fmt, str::FromStr, sync::Arc};
+
+use apalis_board_types::ApiError;
+use apalis_core::{
+    backend::{
+        Backend, BackendExt, FetchById, Filter, ListAllTasks, ListQueues, ListTasks, ListWorkers,
+        Metrics, QueueInfo, RunningWorker, Statistic, TaskSink, codec::Codec,
+    },
+    task::status::Status,
+};
+use futures::{FutureExt, future::BoxFuture};
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
+
+use crate::framework::{QueueOptions, Validator};
+
+/// A queue of a backend as seen by the board, with JSON in and out.
+///
+/// The trait is object safe, so queues of any backend can be kept as a
+/// [`DynBackend`] and registered at runtime with
+/// [`Registry::register`](crate::registry::Registry::register). Apalis
+/// backends are adapted by [`BackendAdapter`].
+///
+/// The `list_queues`, `list_all_*` and `overview` methods describe the whole
+/// backend rather than the queue, they serve the root routes.
+pub trait BoardBackend: Send + Sync + 'static {
+    /// The name of the backend, e.g. `SqliteStorage`.
+    fn name(&self) -> &str;
+
+    /// The name of the queue.
+    fn queue(&self) -> &str;
+
+    /// The JSON schema of the queue's arguments, if known.
+    fn schema(&self) -> Option<&Value> {
+        None
+    }
+
+    /// Push a task with the given arguments, returning its id when known.
+    ///
+    /// Arguments that don't fit the queue are rejected with [`ApiError::Validation`].
+    fn push_task(&self, args: Value) -> BoxFuture<'_, Result<Option<String>, ApiError>>;
+
+    /// The tasks of the queue passing `filter`.
+    fn list_tasks<'a>(&'a self, filter: &'a Filter) -> BoxFuture<'a, Result<Vec<Value>, ApiError>>;
+
+    /// A task of the queue by its id.
+    fn fetch_task<'a>(&'a self, task_id: &'a str)
+    -> BoxFuture<'a, Result<Option<Value>, ApiError>>;
+
+    /// Run a failed or killed task again, returning the id of the new task when known.
+    ///
+    /// Apalis backends can't reset a task, so by default its arguments are
+    /// pushed as a new task and the original is left alone for its history.
+    /// Tasks in any other state are refused with [`ApiError::Validation`].
+    fn retry_task<'a>(
+        &'a self,
+        task_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<String>, ApiError>> {
+        async move {
+            let task = self
+                .fetch_task(task_id)
+                .await?
+                .ok_or_else(|| ApiError::NotFound(format!("Task {task_id} not found")))?;
+            let status = task["parts"]["status"].as_str().map(Status::from_str);
+            if !matches!(status, Some(Ok(Status::Failed | Status::Killed))) {
+                return Err(ApiError::Validation(format!(
+                    "Task {task_id} has not failed, only failed and killed tasks can be retried"
+                )));
+            }
+            self.push_task(task["args"].clone()).await
+        }
+        .boxed()
+    }
+
+    /// The statistics of the queue.
+    fn stats(&self) -> BoxFuture<'_, Result<Vec<Statistic>, ApiError>>;
+
+    /// The workers of the queue.
+    fn workers(&self) -> BoxFuture<'_, Result<Vec<RunningWorker>, ApiError>>;
+
+    /// Every queue of the backend.
+    fn list_queues(&self) -> BoxFuture<'_, Result<Vec<QueueInfo>, ApiError>>;
+
+    /// The tasks of every queue of the backend passing `filter`.
+    fn list_all_tasks<'a>(
+        &'a self,
+        filter: &'a Filter,
+    ) -> BoxFuture<'a, Result<Vec<Value>, ApiError>>;
+
+    /// The workers of every queue of the backend.
+    fn list_all_workers(&self) -> BoxFuture<'_, Result<Vec<RunningWorker>, ApiError>>;
+
+    /// The statistics of the whole backend.
+    fn overview(&self) -> BoxFuture<'_, Result<Vec<Statistic>, ApiError>>;
+}
+
+/// A shared, type-erased [`BoardBackend`].
+pub type DynBackend = Arc<dyn BoardBackend>;
+
+/// Adapts an apalis backend pushing `T`s to a [`BoardBackend`].
+///
+/// Reads share the backend, fetching and pushing a task work on a clone of it,
+/// so requests never wait on each other. Backends backed by a connection pool,
+/// like the SQL ones, are cheap to clone.
+pub struct BackendAdapter<B, T> {
+    backend: B,
+    name: String,
+    queue: String,
+    schema: Option<Value>,
+    validator: Validator<T>,
+}
+
+impl<B: BackendExt, T> BackendAdapter<B, T> {
+    /// Serve the queue of `backend`, set up with `options`.
+    pub fn new(backend: B, options: QueueOptions<T>) -> Self {
+        Self {
+            name: crate::backend_name::<B>(),
+            queue: backend.get_queue().to_string(),
+            backend,
+            schema: options.schema,
+            validator: options.validator,
+        }
+    }
+
+    /// Name the backend `name` instead of after its type.
+    ///
+    /// Backends of the same type are told apart by their name, so two
+    /// databases of the same kind need distinct ones to both serve the root
+    /// routes.
+    #[must_use]
+    pub fn named(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+}
+
+impl<B, T> fmt::Debug for BackendAdapter<B, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BackendAdapter")
+            .field("name", &self.name)
+            .field("queue", &self.queue)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<B, T> BoardBackend for BackendAdapter<B, T>
+where
+    B: Metrics
+        + ListWorkers
+        + ListAllTasks
+        + ListQueues
+        + BackendExt
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+    B: ListTasks<T> + FetchById<T> + TaskSink<T>,
+    B::Context: Serialize + Send,
+    B::IdType: Serialize + FromStr + Send,
+    <B::IdType as FromStr>::Err: std::error::Error,
+    B::Compact: Serialize,
+    B::Error: std::error::Error,
+    <<B as BackendExt>::Codec as Codec<<B as Backend>::Args>>::Error: std::error::Error,
+    B::Codec: Codec<T>,
+    <B::Codec as Codec<T>>::Error: std::error::Error,
+    T: Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn queue(&self) -> &str {
+        &self.queue
+    }
+
+    fn schema(&self) -> Option<&Value> {
+        self.schema.as_ref()
+    }
+
+    fn push_task(&self, args: Value) -> BoxFuture<'_, Result<Option<String>, ApiError>> {
+        async move {
+            let args: T =
+                serde_json::from_value(args).map_err(|e| ApiError::Validation(e.to_string()))?;
+            self.validator.check(&args)?;
+            crate::push_task(args, &mut self.backend.clone()).await
+        }
+        .boxed()
+    }
+
+    fn list_tasks<'a>(&'a self, filter: &'a Filter) -> BoxFuture<'a, Result<Vec<Value>, ApiError>> {
+        async move {
+            let tasks =
+                crate::get_tasks::<B, T, <B::Codec as Codec<T>>::Compact>(&self.backend, filter)
+                    .await?;
+            to_json(&tasks)
+        }
+        .boxed()
+    }
+
+    fn fetch_task<'a>(
+        &'a self,
+        task_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<Value>, ApiError>> {
+        async move {
+            crate::get_task_by_id::<B, T>(task_id, &mut self.backend.clone())
+                .await?
+                .map(|task| to_value(&task))
+                .transpose()
+        }
+        .boxed()
+    }
+
+    fn stats(&self) -> BoxFuture<'_, Result<Vec<Statistic>, ApiError>> {
+        crate::stats_by_queue(&self.backend).boxed()
+    }
+
+    fn workers(&self) -> BoxFuture<'_, Result<Vec<RunningWorker>, ApiError>> {
+        crate::get_workers(&self.backend).boxed()
+    }
+
+    fn list_queues(&self) -> BoxFuture<'_, Result<Vec<QueueInfo>, ApiError>> {
+        crate::fetch_queues(&self.backend).boxed()
+    }
+
+    fn list_all_tasks<'a>(
+        &'a self,
+        filter: &'a Filter,
+    ) -> BoxFuture<'a, Result<Vec<Value>, ApiError>> {
+        async move { to_json(&crate::get_all_tasks(&self.backend, filter).await?) }.boxed()
+    }
+
+    fn list_all_workers(&self) -> BoxFuture<'_, Result<Vec<RunningWorker>, ApiError>> {
+        crate::get_all_workers(&self.backend).boxed()
+    }
+
+    fn overview(&self) -> BoxFuture<'_, Result<Vec<Statistic>, ApiError>> {
+        crate::overview(&self.backend).boxed()
+    }
+}
+
+fn to_value<I: Serialize>(item: &I) -> Result<Value, ApiError> {
+    serde_json::to_value(item).map_err(|e| ApiError::CodecError(e.to_string()))
+}
+
+fn to_json<I: Serialize>(items: &[I]) -> Result<Vec<Value>, ApiError> {
+    items.iter().map(to_value).collect()
+}
+
+/// A queue answering with fixed data, for tests.
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub(crate) struct StubBackend {
+    pub(crate) name: String,
+    pub(crate) queue: String,
+    pub(crate) schema: Option<Value>,
+    pub(crate) overview: Vec<Statistic>,
+    /// The tasks of every queue, newest first.
+    pub(crate) tasks: Vec<Value>,
+    /// Fail every statistics and workers query.
+    pub(crate) broken: bool,
+}
+
+#[cfg(test)]
+impl StubBackend {
+    pub(crate) fn new(name: &str, queue: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            queue: queue.to_owned(),
+            ..Self::default()
+        }
+    }
+}
+
+#[cfg(test)]
+impl BoardBackend for StubBackend {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn queue(&self) -> &str {
+        &self.queue
+    }
+
+    fn schema(&self) -> Option<&Value> {
+        self.schema.as_ref()
+    }
+
+    fn push_task(&self, _: Value) -> BoxFuture<'_, Result<Option<String>, ApiError>> {
+        futures::future::ok(Some(format!("{}-task", self.queue))).boxed()
+    }
+
+    fn list_tasks<'a>(&'a self, _: &'a Filter) -> BoxFuture<'a, Result<Vec<Value>, ApiError>> {
+        futures::future::ok(Vec::new()).boxed()
+    }
+
+    fn fetch_task<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Option<Value>, ApiError>> {
+        let task = self.tasks.iter().find(|t| t["parts"]["task_id"] == id);
+        futures::future::ok(task.cloned()).boxed()
+    }
+
+    fn stats(&self) -> BoxFuture<'_, Result<Vec<Statistic>, ApiError>> {
+        if self.broken {
+            return futures::future::err(ApiError::BackendError("broken".to_owned())).boxed();
+        }
+        futures::future::ok(self.overview.clone()).boxed()
+    }
+
+    fn workers(&self) -> BoxFuture<'_, Result<Vec<RunningWorker>, ApiError>> {
+        self.list_all_workers()
+    }
+
+    fn list_queues(&self) -> BoxFuture<'_, Result<Vec<QueueInfo>, ApiError>> {
+        let info = QueueInfo {
+            name: self.queue.clone(),
+            stats: Vec::new(),
+            workers: Vec::new(),
+            activity: Vec::new(),
+        };
+        futures::future::ok(vec![info]).boxed()
+    }
+
+    fn list_all_tasks<'a>(
+        &'a self,
+        filter: &'a Filter,
+    ) -> BoxFuture<'a, Result<Vec<Value>, ApiError>> {
+        let page = self
+            .tasks
+            .iter()
+            .skip(filter.offset() as usize)
+            .take(filter.limit() as usize)
+            .cloned()
+            .collect();
+        futures::future::ok(page).boxed()
+    }
+
+    fn list_all_workers(&self) -> BoxFuture<'_, Result<Vec<RunningWorker>, ApiError>> {
+        if self.broken {
+            return futures::future::err(ApiError::BackendError("broken".to_owned())).boxed();
+        }
+        let worker = RunningWorker {
+            id: format!("{}-worker", self.queue),
+            queue: self.queue.clone(),
+            backend: "storage".to_owned(),
+            started_at: 0,
+            last_heartbeat: 0,
+            layers: String::new(),
+        };
+        futures::future::ok(vec![worker]).boxed()
+    }
+
+    fn overview(&self) -> BoxFuture<'_, Result<Vec<Statistic>, ApiError>> {
+        self.stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use apalis_sqlite::{PoolOptions, SqliteStorage};
+    use serde_json::json;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_adapter_serves_sqlite() {
+        // A single connection, every connection to `:memory:` opens a new database
+        let pool = PoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        SqliteStorage::setup(&pool).await.unwrap();
+        let storage = SqliteStorage::<String, _, _>::new_in_queue(&pool, "emails");
+        let options = QueueOptions::new().validate(|to: &String| match to.contains('@') {
+            true => Ok(()),
+            false => Err("not an address".to_owned()),
+        });
+        let queue = BackendAdapter::new(storage, options);
+        assert_eq!(queue.name(), "SqliteStorage");
+        assert_eq!(queue.queue(), "emails");
+
+        let id = queue.push_task(json!("ada@example.com")).await.unwrap();
+        let id = id.expect("SQLite ids are ULIDs");
+        assert!(queue.fetch_task(&id).await.unwrap().is_some());
+        assert!(matches!(
+            queue.push_task(json!("ada")).await,
+            Err(ApiError::Validation(_))
+        ));
+        assert!(matches!(
+            queue.push_task(json!(42)).await,
+            Err(ApiError::Validation(_))
+        ));
+
+        let filter = Filter {
+            status: None,
+            page: 1,
+            page_size: Some(10),
+        };
+        let tasks = queue.list_tasks(&filter).await.unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0]["args"], "ada@example.com");
+        let id = tasks[0]["parts"]["task_id"].as_str().unwrap();
+        let task = queue.fetch_task(id).await.unwrap().unwrap();
+        assert_eq!(task["args"], "ada@example.com");
+        assert!(matches!(
+            queue.fetch_task("not-an-id").await,
+            Err(ApiError::Validation(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_only_failed_tasks_are_retried() {
+        let task = |id: &str, status: &str| json!({ "args": "ada@example.com", "parts": { "task_id": id, "status": status } });
+        let queue = StubBackend {
+            tasks: vec![task("failed", "Failed"), task("done", "Done")],
+            ..StubBackend::new("SqliteStorage", "emails")
+        };
+        assert_eq!(
+            queue.retry_task("failed").await.unwrap().as_deref(),
+            Some("emails-task")
+        );
+        assert!(matches!(
+            queue.retry_task("done").await,
+            Err(ApiError::Validation(_))
+        ));
+        assert!(matches!(
+            queue.retry_task("missing").await,
+            Err(ApiError::NotFound(_))
+        ));
+    }
+}
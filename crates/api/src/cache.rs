@@ -0,0 +1,281 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use apalis_board_types::ApiError;
+use apalis_core::backend::Statistic;
+use futures::{
+    FutureExt,
+    future::{BoxFuture, Shared},
+};
+
+/// The header telling whether a response was served from the [`StatsCache`].
+pub const CACHE_HEADER: &str = "x-cache";
+
+type Fetch = Shared<BoxFuture<'static, Result<Vec<Statistic>, ApiError>>>;
+
+struct Entry {
+    /// When the fetch succeeded, none while it is in flight.
+    done: Option<Instant>,
+    ttl: Duration,
+    fetch: Fetch,
+}
+
+impl Entry {
+    /// Whether the entry may still be served at `now`.
+    fn fresh(&self, now: Instant) -> bool {
+        self.done
+            .is_none_or(|done| now.duration_since(done) < self.ttl)
+    }
+}
+
+/// Whether a response was served from the [`StatsCache`], reported in [`CACHE_HEADER`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CacheStatus {
+    /// Another request fetched the statistics, or is still fetching them.
+    Hit,
+    /// The statistics were fetched for this request.
+    Miss,
+}
+
+impl CacheStatus {
+    /// The value of [`CACHE_HEADER`].
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Hit => "HIT",
+            Self::Miss => "MISS",
+        }
+    }
+}
+
+/// Keeps the responses of `/overview` and `/queues/{queue}/stats` for a short while.
+///
+/// Both run aggregate queries on the backend, and every open board polls them.
+/// Each route gets its own time to live, counted from when the statistics
+/// were fetched, and is not cached unless one is set. Concurrent requests for
+/// the same statistics share a single fetch, and failed fetches are not kept.
+#[derive(Clone, Default)]
+pub struct StatsCache {
+    overview: Option<Duration>,
+    stats: Option<Duration>,
+    entries: Arc<Mutex<HashMap<String, Entry>>>,
+}
+
+impl fmt::Debug for StatsCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StatsCache")
+            .field("overview", &self.overview)
+            .field("stats", &self.stats)
+            .finish_non_exhaustive()
+    }
+}
+
+impl StatsCache {
+    /// Cache nothing yet.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep the responses of `/overview` for `ttl`.
+    #[must_use]
+    pub fn overview(mut self, ttl: Duration) -> Self {
+        self.overview = Some(ttl);
+        self
+    }
+
+    /// Keep the responses of `/queues/{queue}/stats` for `ttl`.
+    #[must_use]
+    pub fn stats(mut self, ttl: Duration) -> Self {
+        self.stats = Some(ttl);
+        self
+    }
+
+    /// The overview of `backend`, every backend when it is empty.
+    pub(crate) async fn get_overview(
+        &self,
+        backend: &str,
+        fetch: impl FnOnce() -> BoxFuture<'static, Result<Vec<Statistic>, ApiError>>,
+    ) -> (Result<Vec<Statistic>, ApiError>, Option<CacheStatus>) {
+        self.get(self.overview, format!("overview:{backend}"), fetch)
+            .await
+    }
+
+    /// The statistics of `queue`.
+    pub(crate) async fn get_stats(
+        &self,
+        queue: &str,
+        fetch: impl FnOnce() -> BoxFuture<'static, Result<Vec<Statistic>, ApiError>>,
+    ) -> (Result<Vec<Statistic>, ApiError>, Option<CacheStatus>) {
+        self.get(self.stats, format!("stats:{queue}"), fetch).await
+    }
+
+    async fn get(
+        &self,
+        ttl: Option<Duration>,
+        key: String,
+        fetch: impl FnOnce() -> BoxFuture<'static, Result<Vec<Statistic>, ApiError>>,
+    ) -> (Result<Vec<Statistic>, ApiError>, Option<CacheStatus>) {
+        let Some(ttl) = ttl else {
+            return (fetch().await, None);
+        };
+        let (shared, status) = {
+            let mut entries = self.entries.lock().expect("stats cache poisoned");
+            let now = Instant::now();
+            entries.retain(|_, e| e.fresh(now));
+            if let Some(entry) = entries.get(&key) {
+                (entry.fetch.clone(), CacheStatus::Hit)
+            } else {
+                let shared = fetch().shared();
+                let entry = Entry {
+                    done: None,
+                    ttl,
+                    fetch: shared.clone(),
+                };
+                entries.insert(key.clone(), entry);
+                (shared, CacheStatus::Miss)
+            }
+        };
+        let res = shared.clone().await;
+        let mut entries = self.entries.lock().expect("stats cache poisoned");
+        match entries.get_mut(&key) {
+            Some(entry) if Shared::ptr_eq(&entry.fetch, &shared) => {
+                if res.is_err() {
+                    entries.remove(&key);
+                } else if entry.done.is_none() {
+                    entry.done = Some(Instant::now());
+                }
+            }
+            _ => {}
+        }
+        (res, Some(status))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures::channel::oneshot;
+
+    use apalis_core::backend::StatType;
+
+    use super::*;
+
+    fn counting(
+        calls: &Arc<AtomicUsize>,
+        res: Result<(), ApiError>,
+    ) -> impl FnOnce() -> BoxFuture<'static, Result<Vec<Statistic>, ApiError>> {
+        let (release, released) = oneshot::channel();
+        let _ = release.send(());
+        pending(calls, res, released)
+    }
+
+    /// A fetch that only completes once `released` is.
+    fn pending(
+        calls: &Arc<AtomicUsize>,
+        res: Result<(), ApiError>,
+        released: oneshot::Receiver<()>,
+    ) -> impl FnOnce() -> BoxFuture<'static, Result<Vec<Statistic>, ApiError>> {
+        let calls = calls.clone();
+        move || {
+            async move {
+                let n = calls.fetch_add(1, Ordering::SeqCst) + 1;
+                let _ = released.await;
+                res.map(|()| {
+                    vec![Statistic {
+                        title: "RUNNING_JOBS".to_owned(),
+                        stat_type: StatType::Number,
+                        value: n.to_string(),
+                        priority: None,
+                    }]
+                })
+            }
+            .boxed()
+        }
+    }
+
+    #[test]
+    fn test_concurrent_requests_share_a_fetch() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let cache = StatsCache::new().stats(Duration::from_secs(60));
+        let (release, released) = oneshot::channel();
+        let ((a, hit_a), (b, hit_b), ()) = futures::executor::block_on(async {
+            futures::join!(
+                cache.get_stats("emails", pending(&calls, Ok(()), released)),
+                cache.get_stats("emails", counting(&calls, Ok(()))),
+                async { release.send(()).unwrap() }
+            )
+        });
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(a.unwrap()[0].value, b.unwrap()[0].value);
+        assert_eq!(
+            (hit_a, hit_b),
+            (Some(CacheStatus::Miss), Some(CacheStatus::Hit))
+        );
+
+        // Other queues and the uncached overview are fetched
+        let (res, _) =
+            futures::executor::block_on(cache.get_stats("sms", counting(&calls, Ok(()))));
+        assert!(res.is_ok());
+        let (_, status) =
+            futures::executor::block_on(cache.get_overview("", counting(&calls, Ok(()))));
+        assert_eq!(status, None);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_failures_and_stale_entries_are_fetched_again() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let cache = StatsCache::new()
+            .overview(Duration::from_secs(60))
+            .stats(Duration::ZERO);
+        let failed = Err(ApiError::BackendError("busy".to_owned()));
+        let (res, _) =
+            futures::executor::block_on(cache.get_overview("", counting(&calls, failed)));
+        assert!(res.is_err());
+        let (res, status) =
+            futures::executor::block_on(cache.get_overview("", counting(&calls, Ok(()))));
+        assert_eq!(res.unwrap()[0].value, "2");
+        assert_eq!(status, Some(CacheStatus::Miss));
+
+        for _ in 0..2 {
+            let (_, status) =
+                futures::executor::block_on(cache.get_stats("emails", counting(&calls, Ok(()))));
+            assert_eq!(status, Some(CacheStatus::Miss));
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn test_entries_live_from_when_the_fetch_completed() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let ttl = Duration::from_millis(100);
+        let cache = StatsCache::new().stats(ttl);
+        let (release, released) = oneshot::channel();
+        let ((_, first), (_, during)) = futures::executor::block_on(async {
+            futures::join!(
+                cache.get_stats("emails", pending(&calls, Ok(()), released)),
+                async {
+                    // Outlive the time to live while the first fetch is in flight
+                    std::thread::sleep(ttl * 2);
+                    let during = cache.get_stats("emails", counting(&calls, Ok(())));
+                    futures::join!(during, async { release.send(()).unwrap() }).0
+                }
+            )
+        });
+        assert_eq!(
+            (first, during),
+            (Some(CacheStatus::Miss), Some(CacheStatus::Hit))
+        );
+
+        let (_, after) =
+            futures::executor::block_on(cache.get_stats("emails", counting(&calls, Ok(()))));
+        assert_eq!(after, Some(CacheStatus::Hit));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
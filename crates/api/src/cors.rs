@@ -0,0 +1,203 @@
+/// Lets a board hosted on another origin, e.g. a CDN, call the API.
+///
+/// Only the listed origins are answered with the CORS headers, so browsers
+/// refuse the responses on every other site. Requests from the listed origins
+/// also pass the [`Csrf`](crate::csrf::Csrf) checks.
+#[derive(Clone, Debug)]
+pub struct Cors {
+    origins: Vec<String>,
+    credentials: bool,
+    methods: Vec<String>,
+    headers: Vec<String>,
+    expose: Vec<String>,
+    max_age: u64,
+}
+
+/// A `(name, value)` header pair.
+pub(crate) type Header = (&'static str, String);
+
+impl Default for Cors {
+    fn default() -> Self {
+        Self {
+            origins: Vec::new(),
+            credentials: false,
+            methods: ["GET", "POST", "PUT", "DELETE"].map(str::to_owned).to_vec(),
+            headers: [
+                "Authorization",
+                "Content-Type",
+                "If-None-Match",
+                crate::csrf::CSRF_HEADER,
+            ]
+            .map(str::to_owned)
+            .to_vec(),
+            expose: ["WWW-Authenticate", "ETag", crate::cache::CACHE_HEADER]
+                .map(str::to_owned)
+                .to_vec(),
+            max_age: 600,
+        }
+    }
+}
+
+impl Cors {
+    /// Allow no origin yet, with the methods and headers the board uses.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow requests from `origin`, e.g. `https://board.example.com`.
+    #[must_use]
+    pub fn allow_origin(mut self, origin: impl Into<String>) -> Self {
+        self.origins
+            .push(origin.into().trim_end_matches('/').to_owned());
+        self
+    }
+
+    /// Let the browser send cookies and HTTP authentication along.
+    #[must_use]
+    pub fn allow_credentials(mut self) -> Self {
+        self.credentials = true;
+        self
+    }
+
+    /// Replace the allowed methods, `GET`, `POST`, `PUT` and `DELETE` by default.
+    #[must_use]
+    pub fn allow_methods<I, S>(mut self, methods: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.methods = methods.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Also allow the request header `name`.
+    #[must_use]
+    pub fn allow_header(mut self, name: impl Into<String>) -> Self {
+        self.headers.push(name.into());
+        self
+    }
+
+    /// Also let the board read the response header `name`.
+    #[must_use]
+    pub fn expose_header(mut self, name: impl Into<String>) -> Self {
+        self.expose.push(name.into());
+        self
+    }
+
+    /// How long browsers may cache a preflight response, 10 minutes by default.
+    #[must_use]
+    pub fn max_age(mut self, seconds: u64) -> Self {
+        self.max_age = seconds;
+        self
+    }
+
+    /// The allowed origins.
+    pub fn origins(&self) -> impl Iterator<Item = &str> {
+        self.origins.iter().map(String::as_str)
+    }
+
+    /// Whether `origin` may call the API.
+    #[must_use]
+    pub fn allows(&self, origin: &str) -> bool {
+        self.origins().any(|o| o == origin)
+    }
+
+    /// Whether a request is a CORS preflight, given its method, `Origin` and
+    /// `Access-Control-Request-Method`.
+    pub(crate) fn is_preflight(
+        method: &str,
+        origin: Option<&str>,
+        request_method: Option<&str>,
+    ) -> bool {
+        method == "OPTIONS" && origin.is_some() && request_method.is_some()
+    }
+
+    /// The headers answering a preflight from `origin`, none if it isn't allowed.
+    pub(crate) fn preflight(&self, origin: &str) -> Option<Vec<Header>> {
+        if !self.allows(origin) {
+            return None;
+        }
+        let mut headers = self.headers(Some(origin));
+        headers.push(("access-control-allow-methods", self.methods.join(", ")));
+        headers.push(("access-control-allow-headers", self.headers.join(", ")));
+        headers.push(("access-control-max-age", self.max_age.to_string()));
+        Some(headers)
+    }
+
+    /// The headers to add to a response to `origin`.
+    ///
+    /// Always includes `Vary: Origin`, as the response depends on it.
+    pub(crate) fn headers(&self, origin: Option<&str>) -> Vec<Header> {
+        let mut headers = vec![("vary", "Origin".to_owned())];
+        let Some(origin) = origin.filter(|o| self.allows(o)) else {
+            return headers;
+        };
+        headers.push(("access-control-allow-origin", origin.to_owned()));
+        if self.credentials {
+            headers.push(("access-control-allow-credentials", "true".to_owned()));
+        }
+        if !self.expose.is_empty() {
+            headers.push(("access-control-expose-headers", self.expose.join(", ")));
+        }
+        headers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value<'a>(headers: &'a [Header], name: &str) -> Option<&'a str> {
+        headers
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn test_only_listed_origins_are_allowed() {
+        let cors = Cors::new()
+            .allow_origin("https://board.example.com/")
+            .allow_credentials();
+
+        let headers = cors.headers(Some("https://board.example.com"));
+        assert_eq!(
+            value(&headers, "access-control-allow-origin"),
+            Some("https://board.example.com")
+        );
+        assert_eq!(
+            value(&headers, "access-control-allow-credentials"),
+            Some("true")
+        );
+
+        let headers = cors.headers(Some("https://evil.example"));
+        assert_eq!(headers, vec![("vary", "Origin".to_owned())]);
+        assert!(cors.preflight("https://evil.example").is_none());
+    }
+
+    #[test]
+    fn test_preflight_lists_methods_and_headers() {
+        let cors = Cors::new()
+            .allow_origin("https://board.example.com")
+            .allow_methods(["GET", "PUT"]);
+        assert!(Cors::is_preflight(
+            "OPTIONS",
+            Some("https://board.example.com"),
+            Some("PUT")
+        ));
+        assert!(!Cors::is_preflight("OPTIONS", None, Some("PUT")));
+
+        let headers = cors.preflight("https://board.example.com").unwrap();
+        assert_eq!(
+            value(&headers, "access-control-allow-methods"),
+            Some("GET, PUT")
+        );
+        assert!(
+            value(&headers, "access-control-allow-headers")
+                .unwrap()
+                .contains("x-csrf-token")
+        );
+        assert_eq!(value(&headers, "access-control-allow-credentials"), None);
+    }
+}
@@ -0,0 +1,213 @@
+use std::{net::IpAddr, sync::Arc};
+
+use apalis_board_types::ApiError;
+
+use crate::{auth::Credentials, proxy::TrustedProxies};
+
+/// The cookie holding the double-submit token, set by the board's frontend.
+pub const CSRF_COOKIE: &str = "apalis-board-csrf";
+
+/// The header that must repeat the value of [`CSRF_COOKIE`].
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+/// Rejects cross-site requests to the routes that change something.
+///
+/// Requests other than `GET`, `HEAD` and `OPTIONS` are refused when:
+/// - the browser reports them as cross-site through `Sec-Fetch-Site`, or their
+///   `Origin` is not the board's own host
+/// - they carry cookies without repeating the [`CSRF_COOKIE`] token in the
+///   [`CSRF_HEADER`] header
+///
+/// Requests from a [trusted](Self::trust) `Origin` always go through.
+///
+/// The board's host is the `Host` header, or `X-Forwarded-Host` for requests
+/// sent by one of the
+/// [trusted proxies](crate::framework::ApiBuilder::trusted_proxies).
+///
+/// Scripts and other clients that send neither cookies nor `Origin` are not
+/// affected.
+#[derive(Clone, Debug, Default)]
+pub struct Csrf {
+    disabled: bool,
+    trusted: Arc<[String]>,
+    proxies: TrustedProxies,
+}
+
+/// The parts of a request inspected by [`Csrf`].
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct CsrfRequest<'a> {
+    pub(crate) method: &'a str,
+    pub(crate) host: Option<&'a str>,
+    pub(crate) forwarded_host: Option<&'a str>,
+    pub(crate) peer: Option<IpAddr>,
+    pub(crate) origin: Option<&'a str>,
+    pub(crate) fetch_site: Option<&'a str>,
+    pub(crate) cookie: Option<&'a str>,
+    pub(crate) token: Option<&'a str>,
+}
+
+impl Csrf {
+    /// Protect the routes, only trusting the board's own origin.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Let every request through, e.g. when a proxy already checks them.
+    #[must_use]
+    pub fn disabled() -> Self {
+        Self {
+            disabled: true,
+            ..Self::default()
+        }
+    }
+
+    /// Also accept requests from `origin`, e.g. `https://admin.example.com`.
+    #[must_use]
+    pub fn trust(self, origin: impl Into<String>) -> Self {
+        let mut trusted = self.trusted.to_vec();
+        trusted.push(origin.into().trim_end_matches('/').to_owned());
+        Self {
+            trusted: trusted.into(),
+            ..self
+        }
+    }
+
+    /// Also trust every origin of `origins`.
+    pub(crate) fn trusts<'a>(self, origins: impl IntoIterator<Item = &'a str>) -> Self {
+        origins.into_iter().fold(self, Self::trust)
+    }
+
+    /// Believe the `X-Forwarded-Host` of requests sent by `proxies`.
+    pub(crate) fn behind(self, proxies: TrustedProxies) -> Self {
+        Self { proxies, ..self }
+    }
+
+    /// Whether the request may go through.
+    pub(crate) fn check(&self, req: &CsrfRequest<'_>) -> Result<(), ApiError> {
+        if self.disabled || matches!(req.method, "GET" | "HEAD" | "OPTIONS") {
+            return Ok(());
+        }
+        let forbidden =
+            |reason: &str| Err(ApiError::Forbidden(format!("CSRF check failed: {reason}")));
+
+        // A trusted origin on another host cannot read the cookie to repeat it
+        if req
+            .origin
+            .is_some_and(|origin| self.trusted.iter().any(|t| t == origin))
+        {
+            return Ok(());
+        }
+        let host = match self.proxies.trusts(req.peer) {
+            true => req.forwarded_host.or(req.host),
+            false => req.host,
+        };
+        let same_origin = match (req.fetch_site, req.origin) {
+            (Some(site), _) => matches!(site, "same-origin" | "none"),
+            (None, Some(origin)) => host_of(origin).is_some_and(|h| Some(h) == host),
+            (None, None) => true,
+        };
+        if !same_origin {
+            return forbidden("cross-site request");
+        }
+
+        let Some(cookie) = req.cookie else {
+            return Ok(());
+        };
+        let credentials = Credentials::new(None, Some(cookie), None);
+        match (credentials.cookie(CSRF_COOKIE), req.token) {
+            (Some(expected), Some(token)) if !expected.is_empty() && expected == token => Ok(()),
+            _ => forbidden(&format!(
+                "the `{CSRF_HEADER}` header must repeat the `{CSRF_COOKIE}` cookie"
+            )),
+        }
+    }
+}
+
+/// The `host[:port]` of an origin such as `https://example.com:8080`.
+fn host_of(origin: &str) -> Option<&str> {
+    origin
+        .split_once("://")
+        .map(|(_, host)| host.trim_end_matches('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post<'a>() -> CsrfRequest<'a> {
+        CsrfRequest {
+            method: "POST",
+            host: Some("board.local:8000"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_cross_site_requests_are_rejected() {
+        let csrf = Csrf::new();
+        assert!(csrf.check(&post()).is_ok());
+        let cross = CsrfRequest {
+            fetch_site: Some("cross-site"),
+            origin: Some("https://evil.example"),
+            ..post()
+        };
+        assert!(matches!(csrf.check(&cross), Err(ApiError::Forbidden(_))));
+        assert!(
+            csrf.clone()
+                .trust("https://evil.example/")
+                .check(&cross)
+                .is_ok()
+        );
+        assert!(
+            csrf.check(&CsrfRequest {
+                method: "GET",
+                ..cross
+            })
+            .is_ok()
+        );
+
+        let origin = |origin| CsrfRequest {
+            origin: Some(origin),
+            ..post()
+        };
+        assert!(csrf.check(&origin("http://board.local:8000")).is_ok());
+        assert!(csrf.check(&origin("http://board.local:9000")).is_err());
+        assert!(csrf.check(&origin("null")).is_err());
+        assert!(Csrf::disabled().check(&origin("null")).is_ok());
+    }
+
+    #[test]
+    fn test_forwarded_host_needs_a_trusted_proxy() {
+        let proxy = "10.0.0.1".parse().ok();
+        let forwarded = |peer| CsrfRequest {
+            forwarded_host: Some("board.example.com"),
+            peer,
+            origin: Some("https://board.example.com"),
+            ..post()
+        };
+        let csrf = Csrf::new();
+        assert!(csrf.check(&forwarded(proxy)).is_err());
+
+        let csrf = csrf.behind(TrustedProxies::new(proxy));
+        assert!(csrf.check(&forwarded(proxy)).is_ok());
+        assert!(csrf.check(&forwarded("192.0.2.9".parse().ok())).is_err());
+    }
+
+    #[test]
+    fn test_cookies_require_the_token() {
+        let csrf = Csrf::new();
+        let cookie = CsrfRequest {
+            fetch_site: Some("same-origin"),
+            cookie: Some("session=abc; apalis-board-csrf=t0k"),
+            ..post()
+        };
+        assert!(csrf.check(&cookie).is_err());
+        let token = |token| CsrfRequest {
+            token: Some(token),
+            ..cookie
+        };
+        assert!(csrf.check(&token("other")).is_err());
+        assert!(csrf.check(&token("t0k")).is_ok());
+    }
+}
@@ -0,0 +1,43 @@
+use sha2::{Digest, Sha256};
+
+/// A weak `ETag` of a response body, changing whenever the body does.
+pub(crate) fn etag(body: &[u8]) -> String {
+    let digest = Sha256::digest(body);
+    let hex: String = digest[..16].iter().map(|b| format!("{b:02x}")).collect();
+    format!("W/\"{hex}\"")
+}
+
+/// Whether an `If-None-Match` header matches `etag`, so the client's copy is current.
+///
+/// Uses the weak comparison, as required for `If-None-Match`.
+pub(crate) fn matches(if_none_match: &str, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_owned();
+    let etag = opaque(etag);
+    if_none_match
+        .split(',')
+        .any(|tag| tag.trim() == "*" || opaque(tag) == etag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_etags_follow_the_body() {
+        let tag = etag(br#"[{"name":"emails"}]"#);
+        assert!(tag.starts_with("W/\"") && tag.ends_with('"'));
+        assert_eq!(tag, etag(br#"[{"name":"emails"}]"#));
+        assert_ne!(tag, etag(br#"[{"name":"sms"}]"#));
+    }
+
+    #[test]
+    fn test_if_none_match_uses_weak_comparison() {
+        let tag = etag(b"[]");
+        let strong = tag.trim_start_matches("W/");
+        assert!(matches(&tag, &tag));
+        assert!(matches(strong, &tag));
+        assert!(matches(&format!("\"other\", {tag}"), &tag));
+        assert!(matches("*", &tag));
+        assert!(!matches("W/\"other\"", &tag));
+    }
+}
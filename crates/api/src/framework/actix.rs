@@ -1,306 +1,1180 @@
-use std::{marker::PhantomData, str::FromStr};
+use std::{
+    fmt,
+    future::{Ready, ready},
+};
 
 use actix_web::{
-    HttpResponse, Responder, Scope,
+    FromRequest, HttpMessage, HttpRequest, HttpResponse, Responder, Route, Scope,
+    dev::{HttpServiceFactory, Payload},
+    error::InternalError,
+    http::{StatusCode, header},
     web::{self, Data, Json},
 };
-use apalis_core::backend::{
-    Backend, BackendExt, FetchById, Filter, ListAllTasks, ListQueues, ListTasks, ListWorkers,
-    Metrics, TaskSink, codec::Codec,
+use apalis_board_types::{
+    ApiError, AuditAction, AuditQuery, AuditRecord, BackendQuery, BoardConfig, Permission,
+    RetriedTask, User,
 };
-use serde::{Serialize, de::DeserializeOwned};
-use tokio::sync::RwLock;
+use apalis_core::backend::{Filter, Statistic};
+use futures::FutureExt;
 
 use crate::{
-    fetch_queues,
-    framework::{ApiBuilder, RegisterRoute},
-    get_all_tasks, get_all_workers, get_task_by_id, get_tasks, get_workers, overview, push_task,
-    stats_by_queue,
+    audit::Audit,
+    backend::DynBackend,
+    cache::{CACHE_HEADER, CacheStatus, StatsCache},
+    framework::ApiBuilder,
+    proxy::TrustedProxies,
+    registry::Registry,
 };
 
 #[cfg(feature = "ui")]
 use crate::ui::ServeUI;
 
-/// Handler struct for Actix web routes.
-#[derive(Debug, Clone)]
-pub struct Handler<S, T, Compact> {
-    _phantom: PhantomData<(S, T, Compact)>,
+/// Respond with the error as JSON, using its status code.
+#[must_use]
+pub fn error_response(err: &ApiError) -> HttpResponse {
+    let status =
+        StatusCode::from_u16(err.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    HttpResponse::build(status).json(err)
+}
+
+/// Describe how the board is set up.
+pub async fn board_config(config: Data<BoardConfig>) -> impl Responder {
+    HttpResponse::Ok().json(config.get_ref())
+}
+
+/// Start an audit record of `action`, performed by the user of `req`.
+///
+/// `X-Forwarded-For` is only believed from the
+/// [trusted proxies](ApiBuilder::trusted_proxies).
+fn audit_entry(req: &HttpRequest, action: AuditAction) -> AuditRecord {
+    let header = |name| req.headers().get(name).and_then(|v| v.to_str().ok());
+    let proxies = req.app_data::<web::Data<TrustedProxies>>();
+    let origin = crate::audit::origin(
+        proxies.map_or(&TrustedProxies::default(), |p| p.get_ref()),
+        req.peer_addr().map(|addr| addr.ip()),
+        header("x-forwarded-for"),
+        header(header::USER_AGENT.as_str()),
+    );
+    crate::audit::entry(req.extensions().get::<User>(), action, origin)
+}
+
+/// Fetch the audit log.
+pub async fn get_audit(audit: Data<Audit>, query: web::Query<AuditQuery>) -> HttpResponse {
+    match audit.query(&query).await {
+        Ok(records) => HttpResponse::Ok().json(records),
+        Err(e) => error_response(&e),
+    }
+}
+
+/// Report malformed request bodies and query strings as [`ApiError::Validation`].
+fn validation_error<E: std::fmt::Display + std::fmt::Debug + 'static>(
+    err: E,
+    _req: &HttpRequest,
+) -> actix_web::Error {
+    let response = error_response(&ApiError::Validation(err.to_string()));
+    InternalError::from_response(err, response).into()
+}
+
+/// The queue named by the `{queue}` segment of the path, looked up in the [`Registry`].
+#[derive(Clone)]
+pub struct QueueBackend(pub DynBackend);
+
+impl fmt::Debug for QueueBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("QueueBackend")
+            .field(&self.0.queue())
+            .finish()
+    }
+}
+
+impl QueueBackend {
+    fn lookup(req: &HttpRequest) -> Result<Self, ApiError> {
+        let name = req.match_info().get("queue").unwrap_or_default();
+        let registry = req
+            .app_data::<Data<Registry>>()
+            .ok_or_else(|| ApiError::BackendError("Missing application state".to_owned()))?;
+        registry
+            .queue(name)
+            .map(Self)
+            .ok_or_else(|| ApiError::NotFound(format!("Queue {name} not found")))
+    }
+}
+
+impl FromRequest for QueueBackend {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Self::lookup(req).map_err(|err| {
+            let response = error_response(&err);
+            InternalError::from_response(err, response).into()
+        }))
+    }
+}
+
+/// Get the tasks of a queue.
+pub async fn get_tasks(
+    QueueBackend(queue): QueueBackend,
+    query: web::Query<Filter>,
+) -> HttpResponse {
+    match queue.list_tasks(&query).await {
+        Ok(tasks) => HttpResponse::Ok().json(tasks),
+        Err(e) => error_response(&e),
+    }
+}
+
+/// Get the statistics of a queue, see [`ApiBuilder::cache`].
+pub async fn stats_by_queue(
+    QueueBackend(queue): QueueBackend,
+    cache: Data<StatsCache>,
+) -> HttpResponse {
+    let name = queue.queue().to_owned();
+    let (stats, status) = cache
+        .get_stats(&name, move || async move { queue.stats().await }.boxed())
+        .await;
+    cached(stats, status)
+}
+
+/// Get the workers of a queue.
+pub async fn get_workers(QueueBackend(queue): QueueBackend) -> HttpResponse {
+    match queue.workers().await {
+        Ok(workers) => HttpResponse::Ok().json(workers),
+        Err(e) => error_response(&e),
+    }
+}
+
+/// Push a new task to a queue.
+pub async fn push_task(
+    QueueBackend(queue): QueueBackend,
+    task: Json<serde_json::Value>,
+    audit: Data<Audit>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let res = queue.push_task(task.into_inner()).await;
+    let mut record = audit_entry(&req, AuditAction::Push);
+    record.queue = Some(queue.queue().to_owned());
+    if let Ok(Some(id)) = &res {
+        record.task_ids.push(id.clone());
+    }
+    audit.record(record, &res).await;
+    match res {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => error_response(&e),
+    }
+}
+
+/// Retry a failed or killed task of a queue, see [`BoardBackend::retry_task`].
+///
+/// [`BoardBackend::retry_task`]: crate::backend::BoardBackend::retry_task
+pub async fn retry_task(
+    QueueBackend(queue): QueueBackend,
+    path: web::Path<(String, String)>,
+    audit: Data<Audit>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let (_, task_id) = path.into_inner();
+    let res = queue.retry_task(&task_id).await;
+    let mut record = audit_entry(&req, AuditAction::Retry);
+    record.queue = Some(queue.queue().to_owned());
+    record.task_ids.push(task_id);
+    if let Ok(Some(id)) = &res {
+        record.task_ids.push(id.clone());
+    }
+    audit.record(record, &res).await;
+    match res {
+        Ok(task_id) => HttpResponse::Ok().json(RetriedTask { task_id }),
+        Err(e) => error_response(&e),
+    }
+}
+
+/// Get a task of a queue by its ID.
+pub async fn get_task_by_id(
+    QueueBackend(queue): QueueBackend,
+    path: web::Path<(String, String)>,
+) -> HttpResponse {
+    let (_, task_id) = path.into_inner();
+    match queue.fetch_task(&task_id).await {
+        Ok(Some(task)) => HttpResponse::Ok().json(task),
+        Ok(None) => error_response(&ApiError::NotFound(format!("Task {task_id} not found"))),
+        Err(e) => error_response(&e),
+    }
+}
+
+/// Get the JSON schema of a queue's arguments.
+pub async fn get_schema(QueueBackend(queue): QueueBackend) -> HttpResponse {
+    match queue.schema() {
+        Some(schema) => HttpResponse::Ok().json(schema),
+        None => error_response(&ApiError::NotFound(
+            "No schema was registered for this queue".to_owned(),
+        )),
+    }
+}
+
+/// List the registered backends.
+pub async fn list_backends(registry: Data<Registry>) -> HttpResponse {
+    HttpResponse::Ok().json(registry.names())
 }
 
-impl<S, T, Compact> Handler<S, T, Compact> {
-    /// Get tasks for a specific queue.
-    pub async fn get_tasks(
-        storage: web::Data<RwLock<S>>,
-        query: web::Query<Filter>,
-    ) -> impl Responder
+/// Get all tasks across all queues of the registered backends.
+pub async fn get_all_tasks(
+    registry: Data<Registry>,
+    filter: web::Query<Filter>,
+    query: web::Query<BackendQuery>,
+) -> HttpResponse {
+    match registry.list_tasks(&filter, &query).await {
+        Ok(tasks) => HttpResponse::Ok().json(tasks),
+        Err(e) => error_response(&e),
+    }
+}
+
+/// Get the workers of the registered backends, on the queues the user may read.
+pub async fn get_all_workers(
+    req: HttpRequest,
+    registry: Data<Registry>,
+    query: web::Query<BackendQuery>,
+) -> HttpResponse {
+    match registry.list_workers(&query).await {
+        Ok(mut workers) => {
+            let user = req.extensions().get::<User>().cloned();
+            workers.retain(|w| crate::auth::can_read(user.as_ref(), &w.queue));
+            HttpResponse::Ok().json(workers)
+        }
+        Err(e) => error_response(&e),
+    }
+}
+
+/// Fetch the queues of the registered backends the user may read.
+pub async fn fetch_queues(
+    req: HttpRequest,
+    registry: Data<Registry>,
+    query: web::Query<BackendQuery>,
+) -> HttpResponse {
+    match registry.list_queues(&query).await {
+        Ok(mut queues) => {
+            let user = req.extensions().get::<User>().cloned();
+            queues.retain(|q| crate::auth::can_read(user.as_ref(), &q.item.name));
+            HttpResponse::Ok().json(queues)
+        }
+        Err(e) => error_response(&e),
+    }
+}
+
+/// Get an overview of statistics across the registered backends, see [`ApiBuilder::cache`].
+pub async fn overview(
+    registry: Data<Registry>,
+    cache: Data<StatsCache>,
+    query: web::Query<BackendQuery>,
+) -> HttpResponse {
+    let query = query.into_inner();
+    let key = query.backend.clone().unwrap_or_default();
+    let registry = registry.into_inner();
+    let (stats, status) = cache
+        .get_overview(&key, move || {
+            async move { registry.overview(&query).await }.boxed()
+        })
+        .await;
+    cached(stats, status)
+}
+
+/// Respond with `stats`, telling whether they came from the cache.
+fn cached(stats: Result<Vec<Statistic>, ApiError>, status: Option<CacheStatus>) -> HttpResponse {
+    match stats {
+        Ok(stats) => {
+            let mut res = HttpResponse::Ok();
+            if let Some(status) = status {
+                res.insert_header((CACHE_HEADER, status.as_str()));
+            }
+            res.json(stats)
+        }
+        Err(e) => error_response(&e),
+    }
+}
+
+/// The middleware wrapping every route of the builder.
+#[derive(Clone)]
+struct Guard {
+    auth: auth::Authenticate,
+    csrf: csrf::VerifyCsrf,
+    cors: cors::ApplyCors,
+}
+
+impl ApiBuilder<Scope> {
+    /// Mount the routes and return the scope.
+    ///
+    /// The queue routes are mounted once as `/queues/{queue}/...` and look the
+    /// queue up in the [`registry`](ApiBuilder::registry), so queues registered
+    /// later are served too.
+    pub fn build(mut self) -> Scope {
+        let guard = self.guard();
+        let router = std::mem::replace(&mut self.router, Scope::new(""))
+            .app_data(web::JsonConfig::default().error_handler(validation_error))
+            .app_data(web::QueryConfig::default().error_handler(validation_error))
+            .app_data(web::Data::new(self.audit.clone()))
+            .app_data(web::Data::new(self.proxies.clone()))
+            .app_data(web::Data::new(self.registry.clone()))
+            .app_data(web::Data::new(self.cache.clone()))
+            .service(self.queue_scope(&guard));
+        if self.root {
+            self.root_routes(router, &guard)
+        } else {
+            router
+        }
+    }
+
+    /// Add the routes listing the items of every backend, `/config` and the optional extras.
+    fn root_routes(&self, router: Scope, guard: &Guard) -> Scope {
+        #[allow(unused_mut)]
+        let mut router = router
+            .app_data(web::JsonConfig::default().error_handler(validation_error))
+            .app_data(web::QueryConfig::default().error_handler(validation_error))
+            .app_data(web::Data::new(self.config.clone()))
+            .app_data(web::Data::new(self.audit.clone()))
+            .app_data(web::Data::new(self.proxies.clone()))
+            .app_data(web::Data::new(self.registry.clone()))
+            // Any user may see who they are and how the board is set up
+            .service(authenticated("/config", web::get().to(board_config), guard))
+            .service(authenticated("/me", web::get().to(auth::me), guard))
+            .service(protected("/backends", web::get().to(list_backends), guard))
+            .service(protected("/queues", conditional(fetch_queues), guard))
+            .service(protected(
+                "/tasks",
+                every_queue(conditional(get_all_tasks)),
+                guard,
+            ))
+            .service(protected("/workers", conditional(get_all_workers), guard))
+            .service(protected(
+                "/overview",
+                every_queue(conditional(overview)),
+                guard,
+            ));
+
+        #[cfg(feature = "sse")]
+        {
+            router = router.service(protected(
+                "/events",
+                every_queue(web::get().to(sse::new_client)),
+                guard,
+            ));
+        }
+
+        router = router.service(protected(
+            "/audit",
+            web::get()
+                .to(get_audit)
+                .wrap(auth::Authorize::new(Permission::Audit, None)),
+            guard,
+        ));
+
+        if let Some(tokens) = self.tokens.clone() {
+            router = router.service(admin_tokens(tokens, self.config.read_only, guard));
+        }
+
+        #[cfg(feature = "prometheus")]
+        {
+            router = router.service(protected(
+                "/metrics",
+                every_queue(web::get().to(prometheus::metrics)),
+                guard,
+            ));
+        }
+
+        #[cfg(feature = "grafana")]
+        {
+            router = router
+                .app_data(web::Data::new(crate::grafana::Grafana::new(
+                    self.registry.clone(),
+                )))
+                .service(protected(
+                    "/grafana",
+                    every_queue(web::get().to(grafana::health)),
+                    guard,
+                ))
+                .service(protected(
+                    "/grafana/search",
+                    every_queue(web::post().to(grafana::search)),
+                    guard,
+                ))
+                .service(protected(
+                    "/grafana/query",
+                    every_queue(web::post().to(grafana::query)),
+                    guard,
+                ))
+                .service(protected(
+                    "/grafana/annotations",
+                    every_queue(web::post().to(grafana::annotations)),
+                    guard,
+                ));
+        }
+
+        #[cfg(feature = "openapi")]
+        {
+            let openapi = crate::openapi::OpenApi::new(self.registry.clone(), self.config.clone());
+            #[cfg(feature = "openapi-explorer")]
+            let openapi = openapi.with_swagger_ui(self.swagger_ui.clone());
+            router = router.app_data(web::Data::new(openapi)).service(protected(
+                "/openapi.json",
+                every_queue(web::get().to(openapi::spec)),
+                guard,
+            ));
+        }
+
+        #[cfg(feature = "openapi-explorer")]
+        {
+            router = router.service(protected(
+                "/docs",
+                every_queue(web::get().to(openapi::explorer)),
+                guard,
+            ));
+        }
+
+        router
+    }
+
+    /// The `/queues/{queue}` scope.
+    fn queue_scope(&self, guard: &Guard) -> impl HttpServiceFactory + use<> {
+        let read = || auth::Authorize::path(Permission::Read);
+        let mut scope = Scope::new("/queues/{queue}")
+            .route("/tasks", conditional(get_tasks).wrap(read()))
+            .route("/stats", web::get().to(stats_by_queue).wrap(read()))
+            .route("/workers", conditional(get_workers).wrap(read()))
+            .route("/tasks/{id}", conditional(get_task_by_id).wrap(read()))
+            .route("/schema", web::get().to(get_schema).wrap(read()));
+        if !self.config.read_only {
+            // Allow add jobs via api
+            scope = scope
+                .route(
+                    "/tasks",
+                    web::put()
+                        .to(push_task)
+                        .wrap(auth::Authorize::path(Permission::Push)),
+                )
+                .route(
+                    "/tasks/{id}/retry",
+                    web::post()
+                        .to(retry_task)
+                        .wrap(auth::Authorize::path(Permission::Retry)),
+                );
+        }
+        scope
+            .wrap(guard.auth.clone())
+            .wrap(guard.csrf.clone())
+            .wrap(guard.cors.clone())
+    }
+
+    fn guard(&self) -> Guard {
+        Guard {
+            auth: auth::Authenticate::new(self.auth()),
+            csrf: csrf::VerifyCsrf::new(self.csrf_checks()),
+            cors: cors::ApplyCors::new(self.cors.clone()),
+        }
+    }
+}
+
+/// A `GET` route to `handler`, answering conditional requests, see [`etag::Conditional`].
+fn conditional<F, Args>(handler: F) -> Route
+where
+    F: actix_web::Handler<Args>,
+    Args: FromRequest + 'static,
+    F::Output: Responder + 'static,
+{
+    web::get().to(handler).wrap(etag::Conditional)
+}
+
+/// Wrap a root route in the [`Guard`] middleware.
+fn protected(path: &str, route: Route, guard: &Guard) -> impl HttpServiceFactory + use<> {
+    authenticated(
+        path,
+        route.wrap(auth::Authorize::new(Permission::Read, None)),
+        guard,
+    )
+}
+
+/// Wrap a root route in the [`Guard`] middleware, without requiring any permission.
+fn authenticated(path: &str, route: Route, guard: &Guard) -> impl HttpServiceFactory + use<> {
+    web::resource(path)
+        .route(route)
+        .wrap(guard.auth.clone())
+        .wrap(guard.csrf.clone())
+        .wrap(guard.cors.clone())
+}
+
+/// Require reading every queue for a root route mixing their items, see
+/// [`EVERY_QUEUE`](crate::auth::EVERY_QUEUE).
+fn every_queue(route: Route) -> Route {
+    route.wrap(auth::Authorize::new(
+        Permission::Read,
+        Some(crate::auth::EVERY_QUEUE.into()),
+    ))
+}
+
+/// The `/admin/tokens` routes, see [`ApiBuilder::tokens`].
+fn admin_tokens(
+    tokens: crate::tokens::Tokens,
+    read_only: bool,
+    guard: &Guard,
+) -> impl HttpServiceFactory + use<> {
+    let manage = || auth::Authorize::new(Permission::ManageTokens, None);
+    let mut scope = web::scope("/admin/tokens")
+        .app_data(web::Data::new(tokens))
+        .route("", web::get().to(tokens::list).wrap(manage()));
+    if !read_only {
+        scope = scope
+            .route("", web::post().to(tokens::create).wrap(manage()))
+            .route("/{id}", web::delete().to(tokens::revoke).wrap(manage()));
+    }
+    scope
+        .wrap(guard.auth.clone())
+        .wrap(guard.csrf.clone())
+        .wrap(guard.cors.clone())
+}
+
+/// Authenticate requests with the [`Authenticator`](crate::auth::Authenticator) of the builder.
+pub mod auth {
+    use std::{
+        future::{Ready, ready},
+        rc::Rc,
+        sync::Arc,
+    };
+
+    use actix_web::{
+        Error, HttpMessage, HttpRequest, HttpResponse,
+        body::{EitherBody, MessageBody},
+        dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
+        http::header,
+        web::Json,
+    };
+    use apalis_board_types::{ApiError, Permission, User};
+    use futures::future::LocalBoxFuture;
+
+    use super::error_response;
+    use crate::auth::{Auth, Credentials};
+
+    /// Middleware rejecting unauthenticated requests and making the [`User`]
+    /// available in the request extensions.
+    ///
+    /// Without an authenticator every request is let through.
+    #[derive(Debug, Clone, Default)]
+    pub struct Authenticate(Option<Auth>);
+
+    impl Authenticate {
+        /// Authenticate requests with `auth`, if any.
+        #[must_use]
+        pub fn new(auth: Option<Auth>) -> Self {
+            Self(auth)
+        }
+    }
+
+    impl<S, B> Transform<S, ServiceRequest> for Authenticate
     where
-        T: Serialize + DeserializeOwned + 'static,
-        S: ListTasks<T> + Send + 'static + BackendExt,
-        S::Context: Serialize + 'static,
-        S::IdType: Serialize + 'static,
-        <S as Backend>::Error: std::error::Error + 'static,
-        S::Codec: Codec<T, Compact = Compact> + 'static,
-        Compact: 'static,
+        S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+        B: MessageBody + 'static,
     {
-        let storage = storage.into_inner();
-        let filter = query.into_inner();
+        type Response = ServiceResponse<EitherBody<B>>;
+        type Error = Error;
+        type Transform = AuthenticateService<S>;
+        type InitError = ();
+        type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+        fn new_transform(&self, service: S) -> Self::Future {
+            ready(Ok(AuthenticateService {
+                service: Rc::new(service),
+                auth: self.0.clone(),
+            }))
+        }
+    }
+
+    /// The service produced by [`Authenticate`].
+    #[derive(Debug)]
+    pub struct AuthenticateService<S> {
+        service: Rc<S>,
+        auth: Option<Auth>,
+    }
+
+    impl<S, B> Service<ServiceRequest> for AuthenticateService<S>
+    where
+        S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+        B: MessageBody + 'static,
+    {
+        type Response = ServiceResponse<EitherBody<B>>;
+        type Error = Error;
+        type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+        forward_ready!(service);
+
+        fn call(&self, req: ServiceRequest) -> Self::Future {
+            let service = self.service.clone();
+            let auth = self.auth.clone();
+            Box::pin(async move {
+                let Some(auth) = auth else {
+                    return service
+                        .call(req)
+                        .await
+                        .map(ServiceResponse::map_into_left_body);
+                };
+                match auth.authenticate(&credentials(req.request())).await {
+                    Ok(user) => {
+                        req.extensions_mut().insert(user);
+                        service
+                            .call(req)
+                            .await
+                            .map(ServiceResponse::map_into_left_body)
+                    }
+                    Err(err) => Ok(req
+                        .into_response(rejection(&auth, &err))
+                        .map_into_right_body()),
+                }
+            })
+        }
+    }
 
-        match get_tasks::<S, T, Compact>(storage, filter).await {
-            Ok(tasks) => HttpResponse::Ok().json(tasks),
-            Err(e) => HttpResponse::InternalServerError().json(e),
+    /// Middleware rejecting requests whose [`User`] lacks a permission on a queue.
+    #[derive(Debug, Clone)]
+    pub struct Authorize {
+        permission: Permission,
+        queue: Option<Arc<str>>,
+        from_path: bool,
+    }
+
+    impl Authorize {
+        /// Require `permission` on `queue`, or on any queue with `None`.
+        #[must_use]
+        pub fn new(permission: Permission, queue: Option<Arc<str>>) -> Self {
+            Self {
+                permission,
+                queue,
+                from_path: false,
+            }
+        }
+
+        /// Require `permission` on the queue named by the `{queue}` segment of the path.
+        #[must_use]
+        pub fn path(permission: Permission) -> Self {
+            Self {
+                from_path: true,
+                ..Self::new(permission, None)
+            }
         }
     }
 
-    /// Get statistics for a specific queue.
-    pub async fn stats_by_queue(storage: web::Data<RwLock<S>>) -> impl Responder
+    impl<S, B> Transform<S, ServiceRequest> for Authorize
     where
-        S::Error: std::error::Error,
-        S: Metrics + BackendExt,
+        S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+        B: MessageBody + 'static,
     {
-        let storage = storage.into_inner();
+        type Response = ServiceResponse<EitherBody<B>>;
+        type Error = Error;
+        type Transform = AuthorizeService<S>;
+        type InitError = ();
+        type Future = Ready<Result<Self::Transform, Self::InitError>>;
 
-        match stats_by_queue::<S>(storage).await {
-            Ok(stats) => HttpResponse::Ok().json(stats),
-            Err(e) => HttpResponse::InternalServerError().json(e),
+        fn new_transform(&self, service: S) -> Self::Future {
+            ready(Ok(AuthorizeService {
+                service,
+                rule: self.clone(),
+            }))
         }
     }
 
-    /// Get workers for a specific queue.
-    pub async fn get_workers(storage: web::Data<RwLock<S>>) -> impl Responder
+    /// The service produced by [`Authorize`].
+    #[derive(Debug)]
+    pub struct AuthorizeService<S> {
+        service: S,
+        rule: Authorize,
+    }
+
+    impl<S, B> Service<ServiceRequest> for AuthorizeService<S>
     where
-        S: ListWorkers + BackendExt,
-        S::Error: std::error::Error,
+        S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+        B: MessageBody + 'static,
     {
-        let storage = storage.into_inner();
+        type Response = ServiceResponse<EitherBody<B>>;
+        type Error = Error;
+        type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+        forward_ready!(service);
+
+        fn call(&self, req: ServiceRequest) -> Self::Future {
+            let queue = match self.rule.from_path {
+                true => req.match_info().get("queue"),
+                false => self.rule.queue.as_deref(),
+            };
+            let allowed =
+                crate::auth::authorize(req.extensions().get::<User>(), self.rule.permission, queue);
+            match allowed {
+                Ok(()) => {
+                    let res = self.service.call(req);
+                    Box::pin(async move { res.await.map(ServiceResponse::map_into_left_body) })
+                }
+                Err(err) => {
+                    let res = req
+                        .into_response(error_response(&err))
+                        .map_into_right_body();
+                    Box::pin(ready(Ok(res)))
+                }
+            }
+        }
+    }
+
+    /// Collect the credentials of a request.
+    pub(super) fn credentials(req: &HttpRequest) -> Credentials {
+        let headers = req.headers();
+        Credentials::new(
+            headers
+                .get(header::AUTHORIZATION)
+                .and_then(|v| v.to_str().ok()),
+            headers.get(header::COOKIE).and_then(|v| v.to_str().ok()),
+            crate::auth::credentials_query(req.path(), Some(req.query_string())),
+        )
+    }
 
-        match get_workers::<S>(storage).await {
-            Ok(workers) => HttpResponse::Ok().json(workers),
-            Err(e) => HttpResponse::InternalServerError().json(e),
+    /// Respond with the error, challenging the client when it is unauthorized.
+    pub(super) fn rejection(auth: &Auth, err: &ApiError) -> HttpResponse {
+        let mut res = error_response(err);
+        let challenge = auth
+            .challenge()
+            .filter(|_| matches!(err, ApiError::Unauthorized(_)))
+            .and_then(|c| header::HeaderValue::from_str(&c).ok());
+        if let Some(challenge) = challenge {
+            res.headers_mut()
+                .insert(header::WWW_AUTHENTICATE, challenge);
         }
+        res
     }
 
-    /// Push a new task to the specified queue.
-    pub async fn push_task(task: Json<T>, storage: Data<RwLock<S>>) -> impl Responder
+    /// The user the request was authenticated as.
+    pub async fn me(req: HttpRequest) -> Json<User> {
+        Json(
+            req.extensions()
+                .get::<User>()
+                .cloned()
+                .unwrap_or_else(User::anonymous),
+        )
+    }
+}
+
+/// Answer conditional requests to the listing routes.
+pub mod etag {
+    use std::future::{Ready, ready};
+
+    use actix_web::{
+        Error,
+        body::{EitherBody, MessageBody, to_bytes},
+        dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
+        error::ErrorInternalServerError,
+        http::{
+            StatusCode,
+            header::{self, HeaderValue},
+        },
+        web::Bytes,
+    };
+    use futures::future::LocalBoxFuture;
+
+    /// Middleware tagging successful responses with a weak `ETag` of their
+    /// body, and answering `If-None-Match` requests for an unchanged body with
+    /// `304 Not Modified`.
+    #[derive(Debug, Clone, Copy, Default)]
+    pub struct Conditional;
+
+    impl<S, B> Transform<S, ServiceRequest> for Conditional
     where
-        T: Serialize + DeserializeOwned + 'static,
-        S: TaskSink<T> + Send + BackendExt,
-        S::Error: std::error::Error,
-        S::Codec: Codec<T, Compact = Compact>,
-        <<S as BackendExt>::Codec as Codec<T>>::Error: std::error::Error,
+        S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+        B: MessageBody + 'static,
     {
-        match push_task(task.into_inner(), storage.into_inner()).await {
-            Ok(_) => HttpResponse::Ok().finish(),
-            Err(e) => HttpResponse::InternalServerError().json(e),
+        type Response = ServiceResponse<EitherBody<B, Bytes>>;
+        type Error = Error;
+        type Transform = ConditionalService<S>;
+        type InitError = ();
+        type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+        fn new_transform(&self, service: S) -> Self::Future {
+            ready(Ok(ConditionalService { service }))
         }
     }
 
-    /// Get a task by its ID.
-    pub async fn get_task_by_id(
-        task_id: web::Path<String>,
-        storage: web::Data<RwLock<S>>,
-    ) -> impl Responder
+    /// The service produced by [`Conditional`].
+    #[derive(Debug)]
+    pub struct ConditionalService<S> {
+        service: S,
+    }
+
+    impl<S, B> Service<ServiceRequest> for ConditionalService<S>
     where
-        T: Serialize + DeserializeOwned + 'static,
-        S: FetchById<T> + 'static,
-        S::Context: Serialize,
-        S::IdType: Serialize,
-        S::Context: Serialize,
-        S::Error: std::error::Error,
-        S::IdType: FromStr,
-        <<S as Backend>::IdType as FromStr>::Err: std::error::Error,
+        S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+        B: MessageBody + 'static,
     {
-        let task_id = task_id.into_inner();
-        let storage = storage.into_inner();
+        type Response = ServiceResponse<EitherBody<B, Bytes>>;
+        type Error = Error;
+        type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+        forward_ready!(service);
+
+        fn call(&self, req: ServiceRequest) -> Self::Future {
+            let if_none_match = req.headers().get(header::IF_NONE_MATCH).cloned();
+            let res = self.service.call(req);
+            Box::pin(async move {
+                let res = res.await?;
+                if res.status() != StatusCode::OK {
+                    return Ok(res.map_into_left_body());
+                }
+                let (req, res) = res.into_parts();
+                let (mut res, body) = res.into_parts();
+                let bytes = to_bytes(body)
+                    .await
+                    .map_err(|e| ErrorInternalServerError(e.into()))?;
+                let etag = crate::etag::etag(&bytes);
+                let headers = res.headers_mut();
+                if let Ok(value) = HeaderValue::from_str(&etag) {
+                    headers.insert(header::ETAG, value);
+                }
+                if !headers.contains_key(header::CACHE_CONTROL) {
+                    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+                }
+                let fresh = if_none_match
+                    .as_ref()
+                    .and_then(|v| v.to_str().ok())
+                    .is_some_and(|v| crate::etag::matches(v, &etag));
+                let res = if fresh {
+                    *res.status_mut() = StatusCode::NOT_MODIFIED;
+                    res.set_body(Bytes::new())
+                } else {
+                    res.set_body(bytes)
+                };
+                Ok(ServiceResponse::new(req, res).map_into_right_body())
+            })
+        }
+    }
+}
+
+/// Answer requests from other origins, see [`ApiBuilder::cors`].
+pub mod cors {
+    use std::future::{Ready, ready};
+
+    use actix_web::{
+        Error, HttpResponse,
+        body::{EitherBody, MessageBody},
+        dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
+        http::header::{self, HeaderMap, HeaderName, HeaderValue},
+    };
+    use apalis_board_types::ApiError;
+    use futures::future::LocalBoxFuture;
+
+    use super::error_response;
+    use crate::cors::{Cors, Header};
+
+    /// Middleware answering preflights and adding the CORS headers to responses.
+    ///
+    /// Without a configuration every request is let through untouched.
+    #[derive(Debug, Clone, Default)]
+    pub struct ApplyCors(Option<Cors>);
 
-        match get_task_by_id::<S, T>(task_id, storage).await {
-            Ok(Some(task)) => HttpResponse::Ok().json(task),
-            Ok(None) => HttpResponse::NotFound().finish(),
-            Err(e) => HttpResponse::InternalServerError().json(e),
+    impl ApplyCors {
+        /// Answer the origins allowed by `cors`, if any.
+        #[must_use]
+        pub fn new(cors: Option<Cors>) -> Self {
+            Self(cors)
         }
     }
 
-    /// Get all tasks across all queues.
-    pub async fn get_all_tasks(
-        storage: web::Data<RwLock<S>>,
-        query: web::Query<Filter>,
-    ) -> impl Responder
+    impl<S, B> Transform<S, ServiceRequest> for ApplyCors
     where
-        S: ListAllTasks + Send,
-        S::Context: Serialize,
-        S::IdType: Serialize,
-        S::Compact: Serialize,
-        <S as Backend>::Error: std::error::Error,
-        <<S as BackendExt>::Codec as Codec<<S as Backend>::Args>>::Error: std::error::Error,
+        S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+        B: MessageBody + 'static,
     {
-        let storage = storage.into_inner();
-        let filter = query.into_inner();
+        type Response = ServiceResponse<EitherBody<B>>;
+        type Error = Error;
+        type Transform = ApplyCorsService<S>;
+        type InitError = ();
+        type Future = Ready<Result<Self::Transform, Self::InitError>>;
 
-        match get_all_tasks::<S>(storage, filter).await {
-            Ok(tasks) => HttpResponse::Ok().json(tasks),
-            Err(e) => HttpResponse::InternalServerError().json(e),
+        fn new_transform(&self, service: S) -> Self::Future {
+            ready(Ok(ApplyCorsService {
+                service,
+                cors: self.0.clone(),
+            }))
         }
     }
 
-    /// Get all workers across all queues.
-    pub async fn get_all_workers(storage: web::Data<RwLock<S>>) -> impl Responder
+    /// The service produced by [`ApplyCors`].
+    #[derive(Debug)]
+    pub struct ApplyCorsService<S> {
+        service: S,
+        cors: Option<Cors>,
+    }
+
+    impl<S, B> Service<ServiceRequest> for ApplyCorsService<S>
     where
-        S: ListWorkers,
-        S::Error: std::error::Error,
+        S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+        B: MessageBody + 'static,
     {
-        let storage = storage.into_inner();
+        type Response = ServiceResponse<EitherBody<B>>;
+        type Error = Error;
+        type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+        forward_ready!(service);
 
-        match get_all_workers::<S>(storage).await {
-            Ok(workers) => HttpResponse::Ok().json(workers),
-            Err(e) => HttpResponse::InternalServerError().json(e),
+        fn call(&self, req: ServiceRequest) -> Self::Future {
+            let headers = req.headers();
+            let origin = headers
+                .get(header::ORIGIN)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_owned);
+            let (Some(cors), Some(origin)) = (self.cors.clone(), origin) else {
+                let res = self.service.call(req);
+                return Box::pin(async move { res.await.map(ServiceResponse::map_into_left_body) });
+            };
+            let preflight = Cors::is_preflight(
+                req.method().as_str(),
+                Some(&origin),
+                headers
+                    .get(header::ACCESS_CONTROL_REQUEST_METHOD)
+                    .and_then(|v| v.to_str().ok()),
+            );
+            if preflight {
+                let res = match cors.preflight(&origin) {
+                    Some(headers) => {
+                        let mut res = HttpResponse::NoContent().finish();
+                        append(res.headers_mut(), headers);
+                        res
+                    }
+                    None => error_response(&ApiError::Forbidden(format!(
+                        "The origin `{origin}` is not allowed"
+                    ))),
+                };
+                return Box::pin(ready(Ok(req.into_response(res).map_into_right_body())));
+            }
+            let res = self.service.call(req);
+            Box::pin(async move {
+                let mut res = res.await?;
+                append(res.headers_mut(), cors.headers(Some(&origin)));
+                Ok(res.map_into_left_body())
+            })
         }
     }
 
-    /// Fetch all queues.
-    pub async fn fetch_queues(storage: web::Data<RwLock<S>>) -> impl Responder
+    fn append(map: &mut HeaderMap, headers: Vec<Header>) {
+        for (name, value) in headers {
+            if let Ok(value) = HeaderValue::from_str(&value) {
+                map.append(HeaderName::from_static(name), value);
+            }
+        }
+    }
+}
+
+/// Reject cross-site requests, see [`ApiBuilder::csrf`].
+pub mod csrf {
+    use std::future::{Ready, ready};
+
+    use actix_web::{
+        Error,
+        body::{EitherBody, MessageBody},
+        dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
+        http::header,
+    };
+    use futures::future::LocalBoxFuture;
+
+    use super::error_response;
+    use crate::csrf::{CSRF_HEADER, Csrf, CsrfRequest};
+
+    /// Middleware answering cross-site requests with `403 Forbidden`.
+    #[derive(Debug, Clone, Default)]
+    pub struct VerifyCsrf(Csrf);
+
+    impl VerifyCsrf {
+        /// Check requests with `csrf`.
+        #[must_use]
+        pub fn new(csrf: Csrf) -> Self {
+            Self(csrf)
+        }
+    }
+
+    impl<S, B> Transform<S, ServiceRequest> for VerifyCsrf
     where
-        S::Error: std::error::Error,
-        S: ListQueues,
+        S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+        B: MessageBody + 'static,
     {
-        let storage = storage.into_inner();
+        type Response = ServiceResponse<EitherBody<B>>;
+        type Error = Error;
+        type Transform = VerifyCsrfService<S>;
+        type InitError = ();
+        type Future = Ready<Result<Self::Transform, Self::InitError>>;
 
-        match fetch_queues::<S>(storage).await {
-            Ok(queues) => HttpResponse::Ok().json(queues),
-            Err(e) => HttpResponse::InternalServerError().json(e),
+        fn new_transform(&self, service: S) -> Self::Future {
+            ready(Ok(VerifyCsrfService {
+                service,
+                csrf: self.0.clone(),
+            }))
         }
     }
 
-    /// Get an overview of statistics.
-    pub async fn overview(storage: web::Data<RwLock<S>>) -> impl Responder
+    /// The service produced by [`VerifyCsrf`].
+    #[derive(Debug)]
+    pub struct VerifyCsrfService<S> {
+        service: S,
+        csrf: Csrf,
+    }
+
+    impl<S, B> Service<ServiceRequest> for VerifyCsrfService<S>
     where
-        S::Error: std::error::Error,
-        S: Metrics,
+        S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+        B: MessageBody + 'static,
     {
-        let storage = storage.into_inner();
+        type Response = ServiceResponse<EitherBody<B>>;
+        type Error = Error;
+        type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+        forward_ready!(service);
 
-        match overview::<S>(storage).await {
-            Ok(stats) => HttpResponse::Ok().json(stats),
-            Err(e) => HttpResponse::InternalServerError().json(e),
+        fn call(&self, req: ServiceRequest) -> Self::Future {
+            let headers = req.headers();
+            let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+            let allowed = self.csrf.check(&CsrfRequest {
+                method: req.method().as_str(),
+                host: header(header::HOST.as_str()),
+                forwarded_host: header("x-forwarded-host"),
+                peer: req.peer_addr().map(|addr| addr.ip()),
+                origin: header(header::ORIGIN.as_str()),
+                fetch_site: header("sec-fetch-site"),
+                cookie: header(header::COOKIE.as_str()),
+                token: header(CSRF_HEADER),
+            });
+            match allowed {
+                Ok(()) => {
+                    let res = self.service.call(req);
+                    Box::pin(async move { res.await.map(ServiceResponse::map_into_left_body) })
+                }
+                Err(err) => {
+                    let res = req
+                        .into_response(error_response(&err))
+                        .map_into_right_body();
+                    Box::pin(ready(Ok(res)))
+                }
+            }
         }
     }
 }
 
-impl<B, T, Compact> RegisterRoute<B, T> for ApiBuilder<Scope>
-where
-    B: Metrics + ListWorkers + ListAllTasks + ListQueues + Send + 'static,
-    B::Context: Serialize,
-    B::IdType: Serialize,
-    <B as Backend>::Error: std::error::Error,
-    B::IdType: FromStr,
-    <<B as Backend>::IdType as FromStr>::Err: std::error::Error,
-    Compact: Serialize + 'static,
-    B::Compact: Serialize,
-    <B as Backend>::Error: std::error::Error,
-    <<B as BackendExt>::Codec as Codec<<B as Backend>::Args>>::Error: std::error::Error,
-    T: Serialize + DeserializeOwned + 'static,
-    B: ListTasks<T> + FetchById<T>,
-    B::Codec: Codec<T, Compact = Compact>,
-    <<B as BackendExt>::Codec as Codec<T>>::Error: std::error::Error,
-    B: TaskSink<T>,
-{
-    fn register(mut self, backend: B) -> Self {
-        let queue = backend.get_queue();
-        let backend = web::Data::new(RwLock::new(backend));
-        if self.root {
-            #[allow(unused_mut)]
-            let mut router = self
-                .router
-                .app_data(backend.clone())
-                .route(
-                    "/queues",
-                    web::get().to(Handler::<B, (), Compact>::fetch_queues),
-                )
-                .route(
-                    "/tasks",
-                    web::get().to(Handler::<B, (), Compact>::get_all_tasks),
-                )
-                .route(
-                    "/workers",
-                    web::get().to(Handler::<B, (), Compact>::get_all_workers),
-                )
-                .route(
-                    "/overview",
-                    web::get().to(Handler::<B, (), Compact>::overview),
-                );
+/// Manage API tokens, see [`ApiBuilder::tokens`].
+pub mod tokens {
+    use actix_web::{
+        HttpMessage, HttpRequest, HttpResponse,
+        web::{Data, Json, Path},
+    };
+    use apalis_board_types::{AuditAction, NewToken, User};
 
-            #[cfg(feature = "sse")]
-            {
-                router = router.route("/events", web::get().to(sse::new_client));
-            }
+    use super::{audit_entry, error_response};
+    use crate::{audit::Audit, tokens::Tokens};
 
-            self.router = router;
+    /// List the tokens the signed in user could have minted, without their secrets.
+    pub async fn list(tokens: Data<Tokens>, req: HttpRequest) -> HttpResponse {
+        match tokens.list(&user(&req)).await {
+            Ok(tokens) => HttpResponse::Ok().json(tokens),
+            Err(e) => error_response(&e),
         }
-        let scope = self.router.service(
-            Scope::new(&format!("/queues/{queue}"))
-                .app_data(web::Data::new(queue))
-                .app_data(backend)
-                .route("/tasks", web::get().to(Handler::<B, T, Compact>::get_tasks))
-                .route(
-                    "/stats",
-                    web::get().to(Handler::<B, T, Compact>::stats_by_queue),
-                )
-                .route(
-                    "/workers",
-                    web::get().to(Handler::<B, T, Compact>::get_workers),
-                )
-                .route("/tasks", web::put().to(Handler::<B, T, Compact>::push_task)) // Allow add jobs via api
-                .route(
-                    "/tasks/{id}",
-                    web::get().to(Handler::<B, T, Compact>::get_task_by_id),
-                ),
-        );
+    }
 
-        Self {
-            router: scope,
-            root: false,
+    /// Mint a token on behalf of the signed in user.
+    pub async fn create(
+        tokens: Data<Tokens>,
+        audit: Data<Audit>,
+        req: HttpRequest,
+        request: Json<NewToken>,
+    ) -> HttpResponse {
+        let res = tokens.mint(request.into_inner(), &user(&req)).await;
+        let mut record = audit_entry(&req, AuditAction::CreateToken);
+        record.target = res.as_ref().ok().map(|created| created.token.id.clone());
+        audit.record(record, &res).await;
+        match res {
+            Ok(created) => HttpResponse::Ok().json(created),
+            Err(e) => error_response(&e),
         }
     }
+
+    /// Revoke a token.
+    pub async fn revoke(
+        tokens: Data<Tokens>,
+        audit: Data<Audit>,
+        req: HttpRequest,
+        id: Path<String>,
+    ) -> HttpResponse {
+        let id = id.into_inner();
+        let res = tokens.revoke(&id, &user(&req)).await;
+        let mut record = audit_entry(&req, AuditAction::RevokeToken);
+        record.target = Some(id);
+        audit.record(record, &res).await;
+        match res {
+            Ok(()) => HttpResponse::NoContent().finish(),
+            Err(e) => error_response(&e),
+        }
+    }
+
+    /// The signed in user, or an anonymous admin when the board has no authentication.
+    fn user(req: &HttpRequest) -> User {
+        req.extensions()
+            .get::<User>()
+            .cloned()
+            .unwrap_or_else(User::anonymous)
+    }
 }
 
 #[cfg(feature = "ui")]
 mod ui {
+    use std::borrow::Cow;
+
     use super::ServeUI;
+    use crate::ui::UiHeaders;
     use actix_web::{
         HttpRequest, HttpResponse, HttpResponseBuilder,
         dev::HttpServiceFactory,
         http::{StatusCode, header},
+        web::Bytes,
     };
+
+    use super::auth::{credentials, rejection};
     impl ServeUI {
-        fn serve_file(path: &str) -> HttpResponse {
-            let mut file = Self::get_file(path);
-            if file.is_none() {
-                // Try fallback to index.html for unknown routes
-                file = Self::get_file("index.html");
+        async fn serve_file(&self, req: &HttpRequest, path: &str) -> HttpResponse {
+            let header = |name| req.headers().get(name).and_then(|v| v.to_str().ok());
+            let served = self
+                .serve(
+                    path,
+                    &UiHeaders {
+                        accept_encoding: header(header::ACCEPT_ENCODING.as_str()),
+                        if_none_match: header(header::IF_NONE_MATCH.as_str()),
+                        forwarded_prefix: header("x-forwarded-prefix"),
+                    },
+                )
+                .await;
+            let status = StatusCode::from_u16(served.status).unwrap_or(StatusCode::NOT_FOUND);
+            let mut builder = HttpResponseBuilder::new(status);
+            for header in served.headers {
+                builder.insert_header(header);
             }
-
-            match file {
-                Some(f) => {
-                    let path_str = f.path().to_str().unwrap_or("");
-                    let mut builder = HttpResponse::Ok();
-                    let mut builder =
-                        builder.insert_header((header::CONTENT_TYPE, Self::content_type(path_str)));
-
-                    if let Some(cache) = Self::cache_control(path_str) {
-                        builder = builder.insert_header((header::CACHE_CONTROL, cache));
-                    }
-
-                    builder.body(f.contents().to_vec())
-                }
-                None => HttpResponseBuilder::new(StatusCode::NOT_FOUND).finish(),
+            match served.body {
+                Cow::Borrowed(body) => builder.body(Bytes::from_static(body)),
+                Cow::Owned(body) => builder.body(body),
             }
         }
     }
     impl HttpServiceFactory for ServeUI {
         fn register(self, config: &mut actix_web::dev::AppService) {
             let resource = actix_web::Resource::new("/{tail:.*}").route(actix_web::web::get().to(
-                move |req: HttpRequest| async move {
-                    let path = req.match_info().query("tail");
+                move |req: HttpRequest| {
+                    let ui = self.clone();
+                    async move {
+                        let denied = match &ui.auth {
+                            Some(auth) => auth
+                                .authenticate(&credentials(&req))
+                                .await
+                                .err()
+                                .map(|err| rejection(auth, &err)),
+                            None => None,
+                        };
+                        if let Some(denied) = denied {
+                            return denied;
+                        }
+                        let path = req.match_info().query("tail");
 
-                    Self::serve_file(path)
+                        ui.serve_file(&req, path).await
+                    }
                 },
             ));
             resource.register(config);
@@ -308,6 +1182,89 @@ mod ui {
     }
 }
 
+/// Expose queue statistics to Prometheus.
+#[cfg(feature = "prometheus")]
+pub mod prometheus {
+    use actix_web::{HttpResponse, Responder, http::header, web::Data};
+
+    use crate::{
+        prometheus::{CONTENT_TYPE, render},
+        registry::Registry,
+    };
+
+    /// Render the statistics of every registered queue in the Prometheus text format.
+    pub async fn metrics(registry: Data<Registry>) -> impl Responder {
+        HttpResponse::Ok()
+            .insert_header((header::CONTENT_TYPE, CONTENT_TYPE))
+            .body(render(&registry).await)
+    }
+}
+
+/// Serve the OpenAPI document of the registered routes.
+#[cfg(feature = "openapi")]
+pub mod openapi {
+    use actix_web::{HttpResponse, Responder, web::Data};
+
+    use crate::openapi::OpenApi;
+
+    /// Render the OpenAPI document.
+    pub async fn spec(openapi: Data<OpenApi>) -> impl Responder {
+        HttpResponse::Ok().json(openapi.document())
+    }
+
+    /// Explore the OpenAPI document with Swagger UI.
+    #[cfg(feature = "openapi-explorer")]
+    pub async fn explorer(openapi: Data<OpenApi>) -> impl Responder {
+        HttpResponse::Ok()
+            .content_type("text/html; charset=utf-8")
+            .body(openapi.explorer())
+    }
+}
+
+/// Expose a Grafana simple-JSON datasource.
+#[cfg(feature = "grafana")]
+pub mod grafana {
+    use actix_web::{
+        HttpResponse, Responder,
+        web::{Data, Json},
+    };
+
+    use super::error_response;
+    use crate::grafana::{AnnotationRequest, Grafana, QueryRequest, SearchRequest};
+
+    /// Answer the datasource connection test.
+    pub async fn health() -> impl Responder {
+        HttpResponse::Ok().body("OK")
+    }
+
+    /// List the available targets.
+    pub async fn search(grafana: Data<Grafana>, req: Json<SearchRequest>) -> impl Responder {
+        match grafana.search(req.into_inner()).await {
+            Ok(targets) => HttpResponse::Ok().json(targets),
+            Err(e) => error_response(&e),
+        }
+    }
+
+    /// Return the history of the requested targets.
+    pub async fn query(grafana: Data<Grafana>, req: Json<QueryRequest>) -> impl Responder {
+        match grafana.query(req.into_inner()).await {
+            Ok(series) => HttpResponse::Ok().json(series),
+            Err(e) => error_response(&e),
+        }
+    }
+
+    /// Annotate task failures.
+    pub async fn annotations(
+        grafana: Data<Grafana>,
+        req: Json<AnnotationRequest>,
+    ) -> impl Responder {
+        match grafana.annotations(req.into_inner()).await {
+            Ok(annotations) => HttpResponse::Ok().json(annotations),
+            Err(e) => error_response(&e),
+        }
+    }
+}
+
 /// Expose Server-Sent Events (SSE) functionality.
 #[cfg(feature = "sse")]
 pub mod sse {
@@ -339,3 +1296,20 @@ pub mod sse {
         .with_keep_alive(Duration::from_secs(60 * 5))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_errors_are_json() {
+        let res = error_response(&ApiError::Conflict("duplicate task".to_owned()));
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+        let bytes = actix_web::body::to_bytes(res.into_body()).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "type": "Conflict", "message": "duplicate task" })
+        );
+    }
+}
@@ -4,12 +4,151 @@ pub mod actix;
 /// Exposes Axum framework routes.
 #[cfg(feature = "axum")]
 pub mod axum;
+/// Exposes the routes as a plain tower service.
+#[cfg(feature = "tower")]
+pub mod tower;
+
+use std::{fmt, marker::PhantomData, sync::Arc};
+
+use apalis_board_types::{ApiError, BoardConfig};
+use apalis_core::backend::BackendExt;
+
+use crate::{
+    backend::{BackendAdapter, BoardBackend},
+    registry::Registry,
+};
 
 /// Trait for registering routes with a backend
 pub trait RegisterRoute<B, T> {
     /// Register routes with the given backend
     #[must_use]
-    fn register(self, backend: B) -> Self;
+    fn register(self, backend: B) -> Self
+    where
+        Self: Sized,
+    {
+        self.register_with(backend, QueueOptions::new())
+    }
+
+    /// Register routes with the given backend and queue options
+    #[must_use]
+    fn register_with(self, backend: B, options: QueueOptions<T>) -> Self;
+}
+
+impl<R, B, T> RegisterRoute<B, T> for ApiBuilder<R>
+where
+    B: BackendExt,
+    BackendAdapter<B, T>: BoardBackend,
+{
+    fn register_with(self, backend: B, options: QueueOptions<T>) -> Self {
+        self.registry
+            .register(BackendAdapter::new(backend, options));
+        self
+    }
+}
+
+/// Options for a queue registered with [`RegisterRoute::register_with`]
+pub struct QueueOptions<T> {
+    pub(crate) schema: Option<serde_json::Value>,
+    pub(crate) validator: Validator<T>,
+    _args: PhantomData<fn() -> T>,
+}
+
+impl<T> QueueOptions<T> {
+    /// Create options with the defaults used by [`RegisterRoute::register`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            schema: None,
+            validator: Validator(None),
+            _args: PhantomData,
+        }
+    }
+
+    /// Describe the queue's arguments with a JSON schema.
+    ///
+    /// The schema is served at `/queues/{queue}/schema` and used by the board
+    /// to render a form for pushing new tasks.
+    #[must_use]
+    pub fn schema(mut self, schema: serde_json::Value) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    /// Derive the JSON schema of the queue's arguments from their [`schemars::JsonSchema`] implementation.
+    #[cfg(feature = "schemars")]
+    #[must_use]
+    pub fn json_schema(self) -> Self
+    where
+        T: schemars::JsonSchema,
+    {
+        self.schema(schemars::schema_for!(T).to_value())
+    }
+
+    /// Check pushed tasks before they reach the backend.
+    ///
+    /// Tasks rejected by `validate` are answered with [`ApiError::Validation`].
+    #[must_use]
+    pub fn validate<F>(mut self, validate: F) -> Self
+    where
+        F: Fn(&T) -> Result<(), String> + Send + Sync + 'static,
+    {
+        self.validator = Validator(Some(Arc::new(validate)));
+        self
+    }
+}
+
+impl<T> Default for QueueOptions<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for QueueOptions<T> {
+    fn clone(&self) -> Self {
+        Self {
+            schema: self.schema.clone(),
+            validator: self.validator.clone(),
+            _args: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for QueueOptions<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueueOptions")
+            .field("schema", &self.schema)
+            .field("validator", &self.validator)
+            .finish()
+    }
+}
+
+type ValidateFn<T> = Arc<dyn Fn(&T) -> Result<(), String> + Send + Sync>;
+
+/// The validation hook of a queue, see [`QueueOptions::validate`]
+pub struct Validator<T>(Option<ValidateFn<T>>);
+
+impl<T> Validator<T> {
+    /// Run the hook, if any, against the task arguments
+    pub fn check(&self, args: &T) -> Result<(), ApiError> {
+        match &self.0 {
+            Some(validate) => validate(args).map_err(ApiError::Validation),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T> Clone for Validator<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> fmt::Debug for Validator<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Validator")
+            .field(&self.0.as_ref().map(|_| "Fn"))
+            .finish()
+    }
 }
 
 /// Builder for API routes
@@ -19,25 +158,203 @@ pub struct ApiBuilder<R> {
     #[allow(unused)]
     /// may not be used in some conditional compilation
     root: bool,
+    registry: Registry,
+    #[allow(unused)]
+    /// may not be used in some conditional compilation
+    auth: Option<crate::auth::Auth>,
+    #[allow(unused)]
+    /// may not be used in some conditional compilation
+    tokens: Option<crate::tokens::Tokens>,
+    #[allow(unused)]
+    /// may not be used in some conditional compilation
+    audit: crate::audit::Audit,
+    #[allow(unused)]
+    /// may not be used in some conditional compilation
+    csrf: crate::csrf::Csrf,
+    #[allow(unused)]
+    /// may not be used in some conditional compilation
+    cors: Option<crate::cors::Cors>,
+    #[allow(unused)]
+    /// may not be used in some conditional compilation
+    proxies: crate::proxy::TrustedProxies,
+    #[allow(unused)]
+    /// may not be used in some conditional compilation
+    cache: crate::cache::StatsCache,
+    #[allow(unused)]
+    /// may not be used in some conditional compilation
+    swagger_ui: Option<String>,
+    config: BoardConfig,
 }
 
 impl<R> ApiBuilder<R> {
     /// Create a new ApiBuilder with default settings
     pub fn new(router: R) -> Self {
-        Self { router, root: true }
+        Self::new_with_router(router, true)
     }
     /// Create a new ApiBuilder with a custom scope
     /// If `register_root` is true, the root routes (/queues, /tasks, /workers, /overview)
-    /// will be registered on the provided scope.
+    /// will be registered on the provided scope. They list the items of every
+    /// registered backend, tagged with its name.
     pub fn new_with_router(router: R, register_root: bool) -> Self {
         Self {
             router,
             root: register_root,
+            registry: Registry::default(),
+            auth: None,
+            tokens: None,
+            audit: crate::audit::Audit::default(),
+            csrf: crate::csrf::Csrf::default(),
+            cors: None,
+            proxies: crate::proxy::TrustedProxies::default(),
+            cache: crate::cache::StatsCache::default(),
+            swagger_ui: None,
+            config: BoardConfig::default(),
+        }
+    }
+
+    /// Never mutate the backends: skip `PUT /queues/{queue}/tasks` and every
+    /// other write route.
+    ///
+    /// Advertised at `/config` so the board disables its actions.
+    #[must_use]
+    pub fn read_only(mut self) -> Self {
+        self.config.read_only = true;
+        self
+    }
+
+    /// Require every route to be authenticated by `authenticator`.
+    #[must_use]
+    pub fn authenticator(mut self, authenticator: impl crate::auth::Authenticator) -> Self {
+        self.auth = Some(crate::auth::Auth::new(authenticator));
+        self
+    }
+
+    /// Manage API tokens at `/admin/tokens`, kept in `store`.
+    ///
+    /// Minted tokens are accepted as `Bearer` credentials on every route, after
+    /// the [`authenticator`](Self::authenticator) rejected the request. Only
+    /// admins can manage tokens, so set an authenticator for them too.
+    #[must_use]
+    pub fn tokens(mut self, store: impl crate::tokens::TokenStore) -> Self {
+        self.tokens = Some(crate::tokens::Tokens::new(store));
+        self.config.tokens = true;
+        self
+    }
+
+    /// Record every change made through the board in `sink`, served at `/audit`.
+    ///
+    /// Defaults to a [`MemoryAuditLog`](crate::audit::MemoryAuditLog) of the
+    /// latest changes. Reading the log requires the admin role.
+    #[must_use]
+    pub fn audit(mut self, sink: impl crate::audit::AuditSink) -> Self {
+        self.audit = crate::audit::Audit::new(sink);
+        self
+    }
+
+    /// Check the routes that change something against cross-site requests with `csrf`.
+    ///
+    /// Enabled by default, use [`Csrf::trust`](crate::csrf::Csrf::trust) when
+    /// the board is served from another origin.
+    #[must_use]
+    pub fn csrf(mut self, csrf: crate::csrf::Csrf) -> Self {
+        self.csrf = csrf;
+        self
+    }
+
+    /// Answer requests from the origins allowed by `cors`, including `/events`.
+    ///
+    /// Use it when the board is hosted on another origin than the API. The
+    /// allowed origins are trusted by the [`csrf`](Self::csrf) checks.
+    #[must_use]
+    pub fn cors(mut self, cors: crate::cors::Cors) -> Self {
+        self.cors = Some(cors);
+        self
+    }
+
+    /// Believe the `X-Forwarded-For` and `X-Forwarded-Host` headers of
+    /// requests sent by `proxies`.
+    ///
+    /// The audit log records the address of the peer otherwise, and the
+    /// [`csrf`](Self::csrf) checks compare origins with the `Host` header.
+    #[must_use]
+    pub fn trusted_proxies(mut self, proxies: impl IntoIterator<Item = std::net::IpAddr>) -> Self {
+        self.proxies = crate::proxy::TrustedProxies::new(proxies);
+        self
+    }
+
+    /// Keep the responses of `/overview` and `/queues/{queue}/stats` in `cache`.
+    ///
+    /// Nothing is cached by default. Cached responses carry `X-Cache: HIT`.
+    #[must_use]
+    pub fn cache(mut self, cache: crate::cache::StatsCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Load Swagger UI for `/docs` from `url` instead of unpkg.com.
+    ///
+    /// Point it at a copy of the `swagger-ui-dist` package when the board has
+    /// no internet access, e.g. `/assets/swagger-ui`.
+    #[cfg(feature = "openapi-explorer")]
+    #[must_use]
+    pub fn swagger_ui(mut self, url: impl Into<String>) -> Self {
+        self.swagger_ui = Some(url.into());
+        self
+    }
+
+    /// Serve the queue of a [`BoardBackend`], e.g. one that is not an apalis backend.
+    #[must_use]
+    pub fn register_backend(self, backend: impl BoardBackend) -> Self {
+        self.registry.register(backend);
+        self
+    }
+
+    /// The registry of the served queues.
+    ///
+    /// Queues registered through it after the routes were built are served
+    /// right away, e.g. when they are discovered at runtime.
+    #[must_use]
+    pub fn registry(&self) -> Registry {
+        self.registry.clone()
+    }
+
+    /// Swap the router for `router`, keeping the queues and settings.
+    #[allow(unused)]
+    pub(crate) fn replace_router<S>(self, router: S) -> (R, ApiBuilder<S>) {
+        let builder = ApiBuilder {
+            router,
+            root: self.root,
+            registry: self.registry,
+            auth: self.auth,
+            tokens: self.tokens,
+            audit: self.audit,
+            csrf: self.csrf,
+            cors: self.cors,
+            proxies: self.proxies,
+            cache: self.cache,
+            swagger_ui: self.swagger_ui,
+            config: self.config,
+        };
+        (self.router, builder)
+    }
+
+    /// The CSRF checks of the routes, trusting the CORS origins and proxies.
+    #[allow(unused)]
+    pub(crate) fn csrf_checks(&self) -> crate::csrf::Csrf {
+        let csrf = self.csrf.clone().behind(self.proxies.clone());
+        match &self.cors {
+            Some(cors) => csrf.trusts(cors.origins()),
+            None => csrf,
         }
     }
 
-    /// Finalize the builder and return the router
-    pub fn build(self) -> R {
-        self.router
+    /// The authenticator of the routes, accepting API tokens if enabled.
+    #[allow(unused)]
+    pub(crate) fn auth(&self) -> Option<crate::auth::Auth> {
+        let tokens = self.tokens.clone().map(crate::auth::Auth::new);
+        match (self.auth.clone(), tokens) {
+            (Some(auth), Some(tokens)) => Some(auth.or(tokens)),
+            (auth, tokens) => auth.or(tokens),
+        }
     }
 }
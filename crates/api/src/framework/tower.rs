@@ -0,0 +1,196 @@
+use std::{
+    convert::Infallible,
+    error::Error,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use apalis_core::layers::Service;
+use axum::{
+    Router,
+    body::{Body, Bytes, HttpBody},
+};
+use futures::{FutureExt, future::BoxFuture};
+use http::{Request, Response};
+use http_body::{Frame, SizeHint};
+
+use crate::framework::ApiBuilder;
+
+#[cfg(feature = "sse")]
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "sse")]
+use crate::sse::TracingBroadcaster;
+
+#[cfg(feature = "ui")]
+use crate::ui::ServeUI;
+
+/// The board as a plain [`Service`] of [`http`] requests, for hyper or any
+/// other tower based server.
+///
+/// It runs the handlers of [`framework::axum`](crate::framework::axum), so
+/// both answer alike, but only [`http`] and [`http_body`] types show in its
+/// interface.
+#[derive(Clone, Debug, Default)]
+pub struct BoardService {
+    router: Router,
+    #[cfg(feature = "sse")]
+    events: Option<Arc<Mutex<TracingBroadcaster>>>,
+}
+
+impl BoardService {
+    /// A service answering every request with `404 Not Found`, see [`ApiBuilder::build`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serve the routes of `api` under `path`, e.g. `/api/v1`.
+    #[must_use]
+    pub fn nest(mut self, path: &str, api: Self) -> Self {
+        self.router = self.router.nest(path, api.router);
+        #[cfg(feature = "sse")]
+        {
+            self.events = self.events.or(api.events);
+        }
+        self
+    }
+
+    /// Serve the frontend for the paths matching no route.
+    #[cfg(feature = "ui")]
+    #[must_use]
+    pub fn ui(mut self, ui: ServeUI) -> Self {
+        self.router = self.router.fallback_service(ui);
+        self
+    }
+
+    /// Stream the entries of `broadcaster` at `/events`.
+    #[cfg(feature = "sse")]
+    #[must_use]
+    pub fn events(mut self, broadcaster: Arc<Mutex<TracingBroadcaster>>) -> Self {
+        self.events = Some(broadcaster);
+        self
+    }
+}
+
+impl<B> Service<Request<B>> for BoardService
+where
+    B: HttpBody<Data = Bytes> + Send + 'static,
+    B::Error: Into<Box<dyn Error + Send + Sync>>,
+{
+    type Response = Response<BoardBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Self::Response, Infallible>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Service::<Request<B>>::poll_ready(&mut self.router, cx)
+    }
+
+    #[allow(unused_mut)]
+    fn call(&mut self, mut req: Request<B>) -> Self::Future {
+        #[cfg(feature = "sse")]
+        if let Some(events) = &self.events {
+            req.extensions_mut().insert(events.clone());
+        }
+        self.router
+            .call(req)
+            .map(|res| res.map(|res| res.map(BoardBody)))
+            .boxed()
+    }
+}
+
+/// The body of the responses of a [`BoardService`].
+#[derive(Debug)]
+pub struct BoardBody(Body);
+
+impl HttpBody for BoardBody {
+    type Data = Bytes;
+    type Error = Box<dyn Error + Send + Sync>;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Self::Error>>> {
+        Pin::new(&mut self.0).poll_frame(cx).map_err(Into::into)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.0.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.0.size_hint()
+    }
+}
+
+impl ApiBuilder<BoardService> {
+    /// Mount the routes and return the service, see [`ApiBuilder::<Router>::build`](ApiBuilder::build).
+    #[must_use]
+    pub fn build(self) -> BoardService {
+        let (mut service, api) = self.replace_router(Router::new());
+        service.router = service.router.merge(api.build());
+        service
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::StatusCode;
+
+    use super::*;
+    use crate::backend::StubBackend;
+
+    async fn body(res: Response<BoardBody>) -> Bytes {
+        axum::body::to_bytes(Body::new(res.into_body()), usize::MAX)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_serves_the_api_on_http_types() {
+        let api = ApiBuilder::new(BoardService::new())
+            .register_backend(StubBackend::new("SqliteStorage", "emails"))
+            .build();
+        let mut service = BoardService::new().nest("/api/v1", api);
+        let mut get = async |path: &str| {
+            let req = Request::get(path).body(String::new()).unwrap();
+            let res = service.call(req).await.unwrap();
+            let status = res.status();
+            let json = serde_json::from_slice::<serde_json::Value>(&body(res).await).ok();
+            (status, json)
+        };
+
+        let (status, json) = get("/api/v1/queues/emails/workers").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json.unwrap()[0]["queue"], "emails");
+        let (status, json) = get("/api/v1/queues/sms/workers").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(json.unwrap()["message"], "Queue sms not found");
+        assert_eq!(get("/elsewhere").await.0, StatusCode::NOT_FOUND);
+    }
+
+    #[cfg(all(feature = "sse", feature = "ui"))]
+    #[tokio::test]
+    async fn test_serves_events_and_the_frontend() {
+        use http::header::CONTENT_TYPE;
+
+        let api = ApiBuilder::new(BoardService::new()).build();
+        let mut service = BoardService::new()
+            .events(TracingBroadcaster::create())
+            .nest("/api/v1", api)
+            .ui(ServeUI::new());
+        let mut content_type = async |path: &str| {
+            let req = Request::get(path).body(String::new()).unwrap();
+            let res = service.call(req).await.unwrap();
+            assert_eq!(res.status(), StatusCode::OK);
+            res.headers()[CONTENT_TYPE].to_str().unwrap().to_owned()
+        };
+
+        assert_eq!(content_type("/api/v1/events").await, "text/event-stream");
+        assert!(
+            content_type("/queues/emails")
+                .await
+                .starts_with("text/html")
+        );
+    }
+}
@@ -0,0 +1,451 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use apalis_board_types::ApiError;
+use apalis_core::{backend::Filter, task::status::Status};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::registry::{Registry, merge_statistics, numeric_value};
+
+/// Minimum time between two samples of the queue statistics.
+const SAMPLE_INTERVAL_MS: i64 = 5_000;
+/// Maximum number of points kept per series.
+const MAX_POINTS: usize = 2_880;
+/// Maximum number of tasks per queue and status considered for annotations.
+const MAX_ANNOTATED_TASKS: u32 = 100;
+
+/// The time range of a Grafana request.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TimeRange {
+    /// Start of the range.
+    pub from: DateTime<Utc>,
+    /// End of the range.
+    pub to: DateTime<Utc>,
+}
+
+/// Body of `POST /grafana/search`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SearchRequest {
+    /// Only return targets containing this text.
+    #[serde(default)]
+    pub target: String,
+}
+
+/// A single target of a `POST /grafana/query` request.
+#[derive(Debug, Clone, Deserialize)]
+pub struct QueryTarget {
+    /// The target name, as returned by the search endpoint.
+    pub target: String,
+}
+
+/// Body of `POST /grafana/query`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct QueryRequest {
+    /// The requested time range.
+    pub range: TimeRange,
+    /// The requested targets.
+    #[serde(default)]
+    pub targets: Vec<QueryTarget>,
+    /// The maximum number of points Grafana wants per series.
+    #[serde(rename = "maxDataPoints", default)]
+    pub max_data_points: Option<usize>,
+}
+
+/// A series returned by `POST /grafana/query`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimeSeries {
+    /// The target name.
+    pub target: String,
+    /// `[value, unix timestamp in milliseconds]` pairs.
+    pub datapoints: Vec<(f64, i64)>,
+}
+
+/// The annotation definition sent by Grafana, echoed back in every result.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AnnotationQuery {
+    /// The name of the annotation.
+    #[serde(default)]
+    pub name: String,
+    /// An optional queue name to restrict the annotations to.
+    #[serde(default)]
+    pub query: String,
+    /// Any other field sent by Grafana.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Body of `POST /grafana/annotations`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AnnotationRequest {
+    /// The requested time range.
+    pub range: TimeRange,
+    /// The annotation definition.
+    pub annotation: AnnotationQuery,
+}
+
+/// An annotation returned by `POST /grafana/annotations`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Annotation {
+    /// The annotation definition this result belongs to.
+    pub annotation: AnnotationQuery,
+    /// Unix timestamp in milliseconds.
+    pub time: i64,
+    /// The annotation title.
+    pub title: String,
+    /// Tags shown on the annotation.
+    pub tags: Vec<String>,
+    /// The annotation text.
+    pub text: String,
+}
+
+/// A Grafana simple-JSON datasource built on the registered queues.
+///
+/// Statistics are only sampled when Grafana queries the datasource, at most
+/// every few seconds, and kept in memory: the history covers the times Grafana
+/// was querying since the board started, with gaps while it wasn't.
+#[derive(Debug, Clone)]
+pub struct Grafana {
+    registry: Registry,
+    history: Arc<Mutex<History>>,
+}
+
+impl Grafana {
+    pub(crate) fn new(registry: Registry) -> Self {
+        Self {
+            registry,
+            history: Default::default(),
+        }
+    }
+
+    /// List the available targets.
+    pub async fn search(&self, req: SearchRequest) -> Result<Vec<String>, ApiError> {
+        self.sample().await;
+        let needle = req.target.to_lowercase();
+        Ok(self
+            .history
+            .lock()
+            .expect("grafana history poisoned")
+            .targets()
+            .filter(|t| t.to_lowercase().contains(&needle))
+            .collect())
+    }
+
+    /// Return the recorded history of the requested targets.
+    pub async fn query(&self, req: QueryRequest) -> Result<Vec<TimeSeries>, ApiError> {
+        self.sample().await;
+        let history = self.history.lock().expect("grafana history poisoned");
+        Ok(req
+            .targets
+            .into_iter()
+            .map(|t| TimeSeries {
+                datapoints: history.points(
+                    &t.target,
+                    req.range.from.timestamp_millis(),
+                    req.range.to.timestamp_millis(),
+                    req.max_data_points,
+                ),
+                target: t.target,
+            })
+            .collect())
+    }
+
+    /// Annotate failed and killed tasks in the requested range.
+    pub async fn annotations(&self, req: AnnotationRequest) -> Result<Vec<Annotation>, ApiError> {
+        let from = req.range.from.timestamp_millis();
+        let to = req.range.to.timestamp_millis();
+        let mut annotations = Vec::new();
+        for queue in self.registry.queues() {
+            if !req.annotation.query.is_empty() && req.annotation.query != queue.queue() {
+                continue;
+            }
+            for status in [Status::Failed, Status::Killed] {
+                let filter = Filter {
+                    status: Some(status.clone()),
+                    page: 1,
+                    page_size: Some(MAX_ANNOTATED_TASKS),
+                };
+                let tasks = queue.list_tasks(&filter).await?;
+                annotations.extend(
+                    tasks
+                        .iter()
+                        .filter_map(|task| annotate(&req.annotation, queue.queue(), &status, task))
+                        .filter(|a| (from..=to).contains(&a.time)),
+                );
+            }
+        }
+        annotations.sort_by_key(|a| a.time);
+        Ok(annotations)
+    }
+
+    /// Record the current statistics if the last sample is old enough.
+    ///
+    /// A backend or queue failing to report is logged and skipped. When none
+    /// reports, the sample is retried on the next query.
+    async fn sample(&self) {
+        let now = now_millis();
+        if self
+            .history
+            .lock()
+            .expect("grafana history poisoned")
+            .fresh(now)
+        {
+            return;
+        }
+
+        let mut reported = false;
+        let mut overviews = Vec::new();
+        for backend in self.registry.backends() {
+            match backend.overview().await {
+                Ok(overview) => {
+                    reported = true;
+                    overviews.push(overview);
+                }
+                Err(e) => {
+                    tracing::warn!(backend = backend.name(), error = %e, "Could not sample the overview");
+                }
+            }
+        }
+        let mut values: Vec<_> = merge_statistics(overviews)
+            .iter()
+            .filter_map(|s| Some((s.title.clone(), numeric_value(s)?)))
+            .collect();
+        for queue in self.registry.queues() {
+            match queue.stats().await {
+                Ok(stats) => {
+                    reported = true;
+                    values.extend(stats.iter().filter_map(|s| {
+                        Some((format!("{}:{}", queue.queue(), s.title), numeric_value(s)?))
+                    }));
+                }
+                Err(e) => {
+                    tracing::warn!(queue = queue.queue(), backend = queue.name(), error = %e, "Could not sample the queue");
+                }
+            }
+        }
+        if !reported {
+            return;
+        }
+
+        let mut history = self.history.lock().expect("grafana history poisoned");
+        // A concurrent query may have sampled in the meantime
+        if history.fresh(now) {
+            return;
+        }
+        history.last_sample = Some(now);
+        for (target, value) in values {
+            history.record(target, now, value);
+        }
+    }
+}
+
+/// In-memory series keyed by target name.
+#[derive(Debug, Default)]
+struct History {
+    last_sample: Option<i64>,
+    series: BTreeMap<String, VecDeque<(i64, f64)>>,
+}
+
+impl History {
+    /// Whether the last sample is recent enough to skip sampling at `now`.
+    fn fresh(&self, now: i64) -> bool {
+        self.last_sample
+            .is_some_and(|last| now - last < SAMPLE_INTERVAL_MS)
+    }
+
+    fn record(&mut self, target: String, time: i64, value: f64) {
+        let series = self.series.entry(target).or_default();
+        if series.len() == MAX_POINTS {
+            series.pop_front();
+        }
+        series.push_back((time, value));
+    }
+
+    fn targets(&self) -> impl Iterator<Item = String> + '_ {
+        self.series.keys().cloned()
+    }
+
+    fn points(&self, target: &str, from: i64, to: i64, max: Option<usize>) -> Vec<(f64, i64)> {
+        let Some(series) = self.series.get(target) else {
+            return Vec::new();
+        };
+        let points: Vec<_> = series
+            .iter()
+            .filter(|(t, _)| (from..=to).contains(t))
+            .map(|(t, v)| (*v, *t))
+            .collect();
+        match max {
+            Some(max) if max > 0 && points.len() > max => {
+                let step = points.len().div_ceil(max);
+                points.into_iter().step_by(step).collect()
+            }
+            _ => points,
+        }
+    }
+}
+
+/// Build an annotation from a serialized task.
+///
+/// The finish time is read from `done_at` in the task context when the backend
+/// provides it, falling back to `run_at`.
+fn annotate(
+    query: &AnnotationQuery,
+    queue: &str,
+    status: &Status,
+    task: &serde_json::Value,
+) -> Option<Annotation> {
+    let parts = task.get("parts")?;
+    let ctx = parts.get("ctx");
+    let seconds = ctx
+        .and_then(|c| c.get("done_at"))
+        .and_then(serde_json::Value::as_i64)
+        .or_else(|| parts.get("run_at").and_then(serde_json::Value::as_i64))?;
+    let task_id = parts
+        .get("task_id")
+        .and_then(serde_json::Value::as_str)
+        .unwrap_or_default();
+    let mut text = format!("Task {task_id} in {queue} is {status}");
+    if let Some(result) = ctx
+        .and_then(|c| c.get("last_result"))
+        .filter(|r| !r.is_null())
+    {
+        text.push_str(&format!(": {result}"));
+    }
+    Some(Annotation {
+        annotation: query.clone(),
+        time: seconds * 1000,
+        title: format!("Task {status}"),
+        tags: vec![queue.to_owned(), status.to_string()],
+        text,
+    })
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use apalis_core::backend::{StatType, Statistic};
+
+    use super::*;
+    use crate::backend::StubBackend;
+
+    const SEARCH: &str = include_str!("../tests/fixtures/grafana/search.json");
+    const QUERY: &str = include_str!("../tests/fixtures/grafana/query.json");
+    const ANNOTATIONS: &str = include_str!("../tests/fixtures/grafana/annotations.json");
+    const FAILED_TASK: &str = include_str!("../tests/fixtures/grafana/failed_task.json");
+
+    #[test]
+    fn test_parse_recorded_requests() {
+        let search: SearchRequest = serde_json::from_str(SEARCH).unwrap();
+        assert_eq!(search.target, "emails");
+
+        let query: QueryRequest = serde_json::from_str(QUERY).unwrap();
+        assert_eq!(query.targets.len(), 2);
+        assert_eq!(query.targets[0].target, "emails:PENDING_JOBS");
+        assert_eq!(query.max_data_points, Some(3));
+        assert_eq!(query.range.from.timestamp_millis(), 1_700_000_000_000);
+
+        let annotations: AnnotationRequest = serde_json::from_str(ANNOTATIONS).unwrap();
+        assert_eq!(annotations.annotation.query, "emails");
+        assert!(annotations.annotation.extra.contains_key("iconColor"));
+    }
+
+    #[test]
+    fn test_history_range_and_downsampling() {
+        let query: QueryRequest = serde_json::from_str(QUERY).unwrap();
+        let from = query.range.from.timestamp_millis();
+        let to = query.range.to.timestamp_millis();
+
+        let mut history = History::default();
+        history.record("emails:PENDING_JOBS".to_owned(), from - 1, 0.0);
+        for i in 0..6 {
+            history.record("emails:PENDING_JOBS".to_owned(), from + i * 1000, i as f64);
+        }
+        history.record("emails:PENDING_JOBS".to_owned(), to + 1, 0.0);
+
+        let points = history.points("emails:PENDING_JOBS", from, to, None);
+        assert_eq!(points.len(), 6);
+        assert_eq!(points[0], (0.0, from));
+
+        let points = history.points("emails:PENDING_JOBS", from, to, query.max_data_points);
+        assert_eq!(
+            points,
+            vec![(0.0, from), (2.0, from + 2000), (4.0, from + 4000)]
+        );
+
+        assert!(history.points("unknown", from, to, None).is_empty());
+    }
+
+    #[test]
+    fn test_series_are_bounded() {
+        let mut history = History::default();
+        for i in 0..(MAX_POINTS as i64 + 10) {
+            history.record("DONE_JOBS".to_owned(), i, i as f64);
+        }
+        assert_eq!(history.series["DONE_JOBS"].len(), MAX_POINTS);
+        assert_eq!(history.series["DONE_JOBS"].front(), Some(&(10, 10.0)));
+    }
+
+    #[test]
+    fn test_failed_samples_are_retried() {
+        let broken = Registry::default();
+        broken.register(StubBackend {
+            broken: true,
+            ..StubBackend::new("SqliteStorage", "emails")
+        });
+        let grafana = Grafana::new(broken);
+        futures::executor::block_on(grafana.sample());
+        assert_eq!(grafana.history.lock().unwrap().last_sample, None);
+    }
+
+    #[test]
+    fn test_failing_queues_are_skipped() {
+        let registry = Registry::default();
+        registry.register(StubBackend {
+            overview: vec![Statistic {
+                title: "PENDING_JOBS".to_owned(),
+                stat_type: StatType::Number,
+                value: "3".to_owned(),
+                priority: None,
+            }],
+            ..StubBackend::new("SqliteStorage", "emails")
+        });
+        registry.register(StubBackend {
+            broken: true,
+            ..StubBackend::new("PostgresStorage", "payments")
+        });
+        let grafana = Grafana::new(registry);
+        let search = SearchRequest {
+            target: String::new(),
+        };
+        let targets = futures::executor::block_on(grafana.search(search)).unwrap();
+        assert!(targets.contains(&"emails:PENDING_JOBS".to_owned()));
+        assert!(!targets.iter().any(|t| t.starts_with("payments:")));
+        let history = grafana.history.lock().unwrap();
+        assert!(history.last_sample.is_some());
+        assert_eq!(history.series["emails:PENDING_JOBS"].len(), 1);
+    }
+
+    #[test]
+    fn test_annotate_failed_task() {
+        let request: AnnotationRequest = serde_json::from_str(ANNOTATIONS).unwrap();
+        let task: serde_json::Value = serde_json::from_str(FAILED_TASK).unwrap();
+
+        let annotation = annotate(&request.annotation, "emails", &Status::Failed, &task).unwrap();
+        assert_eq!(annotation.time, 1_700_000_120_000);
+        assert_eq!(annotation.tags, vec!["emails", "Failed"]);
+        assert!(annotation.text.contains("01HX"));
+        assert!(annotation.text.contains("SMTP timeout"));
+
+        let response = serde_json::to_value(&annotation).unwrap();
+        assert_eq!(response["annotation"]["name"], "failures");
+    }
+}
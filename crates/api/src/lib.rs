@@ -1,5 +1,5 @@
 #![doc = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/README.md"))]
-use std::{str::FromStr, sync::Arc};
+use std::str::FromStr;
 
 use apalis_board_types::ApiError;
 use apalis_core::{
@@ -10,43 +10,131 @@ use apalis_core::{
     task::{Task, builder::TaskBuilder, task_id::TaskId},
 };
 use serde::{Serialize, de::DeserializeOwned};
-use tokio::sync::RwLock;
 
+/// Record the changes made through the board.
+pub mod audit;
+/// Authenticate requests to the board.
+pub mod auth;
+/// Adapt backends to the board.
+pub mod backend;
+/// Cache the statistics routes for a short while.
+pub mod cache;
+/// Let a board hosted on another origin call the API.
+pub mod cors;
+/// Reject cross-site requests to the routes that change something.
+pub mod csrf;
+/// Weak ETags for answering conditional requests.
+#[cfg(any(feature = "actix", feature = "axum", feature = "ui"))]
+pub(crate) mod etag;
 /// Contains different web framework routes.
 pub mod framework;
+/// A Grafana simple-JSON datasource for queue statistics.
+#[cfg(feature = "grafana")]
+pub mod grafana;
+/// Describe the registered routes as an OpenAPI document.
+#[cfg(feature = "openapi")]
+pub mod openapi;
+/// Expose queue statistics in the Prometheus text format.
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
+/// Believe the forwarding headers of known reverse proxies.
+pub mod proxy;
+/// Type-erased handles to the registered backends.
+pub mod registry;
 /// Expose Server-Sent Events (SSE) functionality.
 #[cfg(feature = "sse")]
 pub mod sse;
+/// Mint and revoke API tokens.
+pub mod tokens;
 /// Expose UI components and functionality.
 #[cfg(feature = "ui")]
 pub mod ui;
 
-/// Push a new task to the specified queue.
+/// A short, human readable name for a backend type, e.g. `SqliteStorage`.
+pub(crate) fn backend_name<B>() -> String {
+    let name = std::any::type_name::<B>();
+    let name = name.split('<').next().unwrap_or(name);
+    name.rsplit("::").next().unwrap_or(name).to_owned()
+}
+
+/// The current time in seconds since the Unix epoch.
+pub(crate) fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// How deep into the tasks a listing may reach, `page * page_size`.
+///
+/// Listing the tasks of several backends fetches every page up to the one
+/// asked for, so deeper pages are refused.
+pub(crate) const MAX_LISTED_TASKS: u32 = 10_000;
+
+/// Refuse a task listing before the first page or past [`MAX_LISTED_TASKS`].
+pub(crate) fn check_page(filter: &Filter) -> Result<(), ApiError> {
+    if filter.page == 0 {
+        return Err(ApiError::Validation("Pages start at 1".to_owned()));
+    }
+    if filter
+        .page
+        .checked_mul(filter.limit())
+        .is_none_or(|end| end > MAX_LISTED_TASKS)
+    {
+        return Err(ApiError::Validation(format!(
+            "Only the first {MAX_LISTED_TASKS} tasks can be listed"
+        )));
+    }
+    Ok(())
+}
+
+/// Run blocking file I/O on the blocking thread pool of the runtime.
+pub(crate) async fn blocking<T: Send + 'static>(
+    io: impl FnOnce() -> std::io::Result<T> + Send + 'static,
+) -> std::io::Result<T> {
+    tokio::task::spawn_blocking(io)
+        .await
+        .map_err(std::io::Error::other)?
+}
+
+/// Push a new task to the specified queue, returning its id.
+///
+/// The task gets a fresh ULID when the id type of the backend can be parsed
+/// from one. Otherwise the backend picks the id and none is returned.
+///
+/// Takes the backend mutably, pass a clone of it to push concurrently.
 pub async fn push_task<Args, B, Compact>(
     task: Args,
-    storage: Arc<RwLock<B>>,
-) -> Result<(), ApiError>
+    storage: &mut B,
+) -> Result<Option<String>, ApiError>
 where
     Args: Serialize + DeserializeOwned + 'static,
     B: TaskSink<Args> + Send + BackendExt,
     B::Error: std::error::Error,
+    B::IdType: FromStr,
     B::Codec: Codec<Args, Compact = Compact>,
     <<B as BackendExt>::Codec as Codec<Args>>::Error: std::error::Error,
 {
-    let task = TaskBuilder::new(task).build();
-    let res = storage.write().await.push_task(task).await;
+    let id = ulid::Ulid::new().to_string();
+    let mut builder = TaskBuilder::new(task);
+    let known = match TaskId::from_str(&id) {
+        Ok(task_id) => {
+            builder = builder.with_task_id(task_id);
+            true
+        }
+        Err(_) => false,
+    };
+    let res = storage.push_task(builder.build()).await;
     match res {
-        Ok(_) => Ok(()),
+        Ok(()) => Ok(known.then_some(id)),
         Err(e) => Err(ApiError::BackendError(e.to_string())),
     }
 }
 /// Get statistics for a specific queue.
-pub async fn stats_by_queue<S>(storage: Arc<RwLock<S>>) -> Result<Vec<Statistic>, ApiError>
+pub async fn stats_by_queue<S>(storage: &S) -> Result<Vec<Statistic>, ApiError>
 where
     S::Error: std::error::Error,
     S: Metrics + BackendExt,
 {
-    let storage = storage.read().await;
     let stats = storage.fetch_by_queue().await;
     match stats {
         Ok(stats) => Ok(stats),
@@ -56,8 +144,8 @@ where
 
 /// Get a list of tasks from the specified queue with filtering options.
 pub async fn get_tasks<S, T, Compact>(
-    storage: Arc<RwLock<S>>,
-    filter: Filter,
+    storage: &S,
+    filter: &Filter,
 ) -> Result<Vec<Task<T, S::Context, S::IdType>>, ApiError>
 where
     T: Serialize + DeserializeOwned + 'static,
@@ -67,20 +155,19 @@ where
     <S as Backend>::Error: std::error::Error,
     S::Codec: Codec<T, Compact = Compact>,
 {
-    let storage = storage.read().await;
+    check_page(filter)?;
     storage
-        .list_tasks(&filter)
+        .list_tasks(filter)
         .await
         .map_err(|e| ApiError::BackendError(e.to_string()))
 }
 
 /// Get workers for a specific queue.
-pub async fn get_workers<S>(storage: Arc<RwLock<S>>) -> Result<Vec<RunningWorker>, ApiError>
+pub async fn get_workers<S>(storage: &S) -> Result<Vec<RunningWorker>, ApiError>
 where
     S: ListWorkers + BackendExt,
     S::Error: std::error::Error,
 {
-    let storage = storage.read().await;
     storage
         .list_workers()
         .await
@@ -88,26 +175,25 @@ where
 }
 
 /// Get a task by its ID.
+///
+/// Takes the backend mutably, pass a clone of it to fetch concurrently.
 pub async fn get_task_by_id<B, T>(
-    task_id: String,
-    storage: Arc<RwLock<B>>,
+    task_id: &str,
+    storage: &mut B,
 ) -> Result<Option<Task<T, B::Context, B::IdType>>, ApiError>
 where
     T: Serialize + DeserializeOwned + 'static,
     B: FetchById<T> + 'static,
     B::Context: Serialize,
     B::IdType: Serialize,
-    B::Context: Serialize,
     B::Error: std::error::Error,
     B::IdType: FromStr,
     <<B as Backend>::IdType as FromStr>::Err: std::error::Error,
 {
-    let task_id = TaskId::<B::IdType>::from_str(&task_id)
-        .map_err(|e| ApiError::BackendError(e.to_string()))?;
+    let task_id = TaskId::<B::IdType>::from_str(task_id)
+        .map_err(|e| ApiError::Validation(format!("Invalid task id: {e}")))?;
 
     storage
-        .write()
-        .await
         .fetch_by_id(&task_id)
         .await
         .map_err(|e| ApiError::BackendError(e.to_string()))
@@ -115,8 +201,8 @@ where
 
 /// Get all tasks across all queues.
 pub async fn get_all_tasks<S>(
-    storage: Arc<RwLock<S>>,
-    filter: Filter,
+    storage: &S,
+    filter: &Filter,
 ) -> Result<Vec<Task<S::Compact, S::Context, S::IdType>>, ApiError>
 where
     S: ListAllTasks + Send,
@@ -126,49 +212,44 @@ where
     <S as Backend>::Error: std::error::Error,
     <<S as BackendExt>::Codec as Codec<<S as Backend>::Args>>::Error: std::error::Error,
 {
+    check_page(filter)?;
     storage
-        .read()
-        .await
-        .list_all_tasks(&filter)
+        .list_all_tasks(filter)
         .await
         .map_err(|e| ApiError::BackendError(e.to_string()))
 }
 
 /// Get all workers across all queues.
-pub async fn get_all_workers<S>(storage: Arc<RwLock<S>>) -> Result<Vec<RunningWorker>, ApiError>
+pub async fn get_all_workers<S>(storage: &S) -> Result<Vec<RunningWorker>, ApiError>
 where
     S: ListWorkers,
     S::Error: std::error::Error,
 {
     storage
-        .read()
-        .await
         .list_all_workers()
         .await
         .map_err(|e| ApiError::BackendError(e.to_string()))
 }
 
 /// Fetch all queues.
-pub async fn fetch_queues<S>(storage: Arc<RwLock<S>>) -> Result<Vec<QueueInfo>, ApiError>
+pub async fn fetch_queues<S>(storage: &S) -> Result<Vec<QueueInfo>, ApiError>
 where
     S::Error: std::error::Error,
     S: ListQueues,
 {
     storage
-        .read()
-        .await
         .list_queues()
         .await
         .map_err(|e| ApiError::BackendError(e.to_string()))
 }
 
 /// Get an overview of statistics.
-pub async fn overview<S>(storage: Arc<RwLock<S>>) -> Result<Vec<Statistic>, ApiError>
+pub async fn overview<S>(storage: &S) -> Result<Vec<Statistic>, ApiError>
 where
     S::Error: std::error::Error,
     S: Metrics,
 {
-    let overview = storage.read().await.global().await;
+    let overview = storage.global().await;
     match overview {
         Ok(overview) => Ok(overview),
         Err(e) => Err(ApiError::BackendError(e.to_string())),
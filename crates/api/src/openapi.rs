@@ -0,0 +1,989 @@
+use apalis_board_types::BoardConfig;
+use serde_json::{Map, Value, json};
+
+use crate::registry::Registry;
+
+/// The OpenAPI version of the generated document.
+pub const OPENAPI_VERSION: &str = "3.1.0";
+
+/// A queue served by the board.
+#[derive(Debug, Clone)]
+struct QueueDoc {
+    name: String,
+    backend: String,
+    /// The JSON schema of the queue's arguments, `{}` when unknown.
+    args: Value,
+    /// Definitions referenced by `args`, moved to `components/schemas` under
+    /// `{queue}.{name}` so queues don't shadow each other or the shared schemas.
+    defs: Map<String, Value>,
+    /// Whether tasks can be pushed, see [`ApiBuilder::read_only`](crate::framework::ApiBuilder::read_only).
+    push: bool,
+}
+
+/// Describes the routes mounted by an [`ApiBuilder`](crate::framework::ApiBuilder)
+/// as an OpenAPI 3 document.
+///
+/// Queues are read from the [`Registry`] whenever the document is rendered, so
+/// the document served at `/openapi.json` also covers the queues registered at
+/// runtime.
+#[derive(Clone, Debug, Default)]
+pub struct OpenApi {
+    registry: Registry,
+    /// Decides which optional root routes are documented.
+    config: BoardConfig,
+    /// Where the explorer loads Swagger UI from.
+    #[cfg(feature = "openapi-explorer")]
+    swagger_ui: String,
+}
+
+impl OpenApi {
+    /// Document the queues of `registry` and the optional root routes enabled by `config`.
+    pub(crate) fn new(registry: Registry, config: BoardConfig) -> Self {
+        Self {
+            registry,
+            config,
+            #[cfg(feature = "openapi-explorer")]
+            swagger_ui: SWAGGER_UI.to_owned(),
+        }
+    }
+
+    /// Load Swagger UI from `url` in the explorer, see
+    /// [`ApiBuilder::swagger_ui`](crate::framework::ApiBuilder::swagger_ui).
+    #[cfg(feature = "openapi-explorer")]
+    pub(crate) fn with_swagger_ui(mut self, url: Option<String>) -> Self {
+        if let Some(url) = url {
+            self.swagger_ui = url.trim_end_matches('/').to_owned();
+        }
+        self
+    }
+
+    /// A page that renders the document with Swagger UI.
+    #[cfg(feature = "openapi-explorer")]
+    #[must_use]
+    pub fn explorer(&self) -> String {
+        EXPLORER_HTML.replace("{swagger_ui}", &self.swagger_ui)
+    }
+
+    /// Render the OpenAPI document.
+    ///
+    /// Paths are relative to where the api is mounted, which is also where the
+    /// document is served from.
+    #[must_use]
+    pub fn document(&self) -> Value {
+        let mut paths = Map::new();
+        paths.insert(
+            "/backends".to_owned(),
+            json!({ "get": operation(
+                "listBackends",
+                &["queues"],
+                "List the names of the registered backends.",
+                &[],
+                &array(&json!({ "type": "string" })),
+            )}),
+        );
+        paths.insert(
+            "/queues".to_owned(),
+            json!({ "get": operation(
+                "listQueues",
+                &["queues"],
+                "List the queues of every backend with their statistics.",
+                &[backend_param()],
+                &array(&tagged(&schema_ref("QueueInfo"))),
+            )}),
+        );
+        let mut params = filter_params();
+        params.push(backend_param());
+        paths.insert(
+            "/tasks".to_owned(),
+            json!({ "get": operation(
+                "listAllTasks",
+                &["tasks"],
+                "List tasks across all queues with their arguments in the backend's compact format. The page is merged across backends.",
+                &params,
+                &array(&tagged(&task_schema(&json!({})))),
+            )}),
+        );
+        paths.insert(
+            "/workers".to_owned(),
+            json!({ "get": operation(
+                "listAllWorkers",
+                &["workers"],
+                "List the workers of every queue, `backend` naming the backend they were listed by.",
+                &[backend_param()],
+                &array(&schema_ref("RunningWorker")),
+            )}),
+        );
+        paths.insert(
+            "/overview".to_owned(),
+            json!({ "get": operation(
+                "overview",
+                &["queues"],
+                "Statistics across all queues, merged across backends.",
+                &[backend_param()],
+                &array(&schema_ref("Statistic")),
+            )}),
+        );
+        paths.insert(
+            "/config".to_owned(),
+            json!({ "get": operation(
+                "config",
+                &["board"],
+                "How the board is set up.",
+                &[],
+                &schema_ref("BoardConfig"),
+            )}),
+        );
+        paths.insert(
+            "/me".to_owned(),
+            json!({ "get": operation(
+                "me",
+                &["auth"],
+                "The user the request is authenticated as.",
+                &[],
+                &schema_ref("User"),
+            )}),
+        );
+        paths.insert(
+            "/audit".to_owned(),
+            json!({ "get": operation(
+                "listAudit",
+                &["audit"],
+                "List the changes made through the board, newest first.",
+                &audit_params(),
+                &array(&schema_ref("AuditRecord")),
+            )}),
+        );
+        if self.config.tokens {
+            tokens_paths(&mut paths, self.config.read_only);
+        }
+        #[cfg(feature = "sse")]
+        paths.insert(
+            "/events".to_owned(),
+            json!({ "get": {
+                "operationId": "events",
+                "tags": ["events"],
+                "summary": "Stream task logs as server-sent events.",
+                "responses": {
+                    "200": {
+                        "description": "Each event carries a JSON encoded `LogEntry`.",
+                        "content": { "text/event-stream": { "schema": { "type": "string" } } }
+                    }
+                }
+            }}),
+        );
+        #[cfg(feature = "prometheus")]
+        paths.insert(
+            "/metrics".to_owned(),
+            json!({ "get": {
+                "operationId": "metrics",
+                "tags": ["metrics"],
+                "summary": "Queue statistics in the Prometheus text format.",
+                "responses": {
+                    "200": {
+                        "description": "OK",
+                        "content": { "text/plain": { "schema": { "type": "string" } } }
+                    }
+                }
+            }}),
+        );
+        #[cfg(feature = "grafana")]
+        {
+            paths.insert(
+                "/grafana".to_owned(),
+                json!({ "get": {
+                    "operationId": "grafanaHealth",
+                    "tags": ["grafana"],
+                    "summary": "Grafana datasource connection test.",
+                    "responses": { "200": { "description": "OK" } }
+                }}),
+            );
+            for (path, id, summary) in [
+                (
+                    "/grafana/search",
+                    "grafanaSearch",
+                    "List the available targets.",
+                ),
+                (
+                    "/grafana/query",
+                    "grafanaQuery",
+                    "Time series of the requested targets.",
+                ),
+                (
+                    "/grafana/annotations",
+                    "grafanaAnnotations",
+                    "Failed and killed tasks.",
+                ),
+            ] {
+                paths.insert(
+                    path.to_owned(),
+                    json!({ "post": {
+                        "operationId": id,
+                        "tags": ["grafana"],
+                        "summary": summary,
+                        "requestBody": {
+                            "required": true,
+                            "content": { "application/json": { "schema": { "type": "object" } } }
+                        },
+                        "responses": json_responses(&json!({ "type": "array" })),
+                    }}),
+                );
+            }
+        }
+
+        let queues = self
+            .registry
+            .queues()
+            .iter()
+            .map(|queue| {
+                QueueDoc::new(
+                    queue.queue(),
+                    queue.name(),
+                    queue.schema().cloned(),
+                    !self.config.read_only,
+                )
+            })
+            .collect::<Vec<_>>();
+        for queue in queues.iter() {
+            queue.paths(&mut paths);
+        }
+        let tags = queues.iter().map(QueueDoc::tag).collect::<Vec<_>>();
+        let mut schemas = components();
+        if let Some(schemas) = schemas.as_object_mut() {
+            for (name, def) in queues.iter().flat_map(|q| &q.defs) {
+                schemas.insert(name.clone(), def.clone());
+            }
+        }
+
+        json!({
+            "openapi": OPENAPI_VERSION,
+            "info": {
+                "title": "apalis-board",
+                "description": "Manage apalis task queues.",
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "servers": [{ "url": "." }],
+            "tags": tags,
+            "paths": paths,
+            "components": { "schemas": schemas },
+        })
+    }
+}
+
+impl QueueDoc {
+    /// Document a queue scope, with `args` as the schema of its task arguments.
+    ///
+    /// The push route is left out of the document unless `push` is set.
+    fn new(name: &str, backend: &str, args: Option<Value>, push: bool) -> Self {
+        let mut args = args.unwrap_or_else(|| json!({}));
+        let mut defs = Map::new();
+        if let Some(schema) = args.as_object_mut() {
+            schema.remove("$schema");
+            if let Some(Value::Object(d)) = schema.remove("$defs") {
+                defs = d;
+            }
+        }
+        let prefix = component_name(name);
+        rewrite_refs(&mut args, &prefix);
+        let defs = defs
+            .into_iter()
+            .map(|(def, mut schema)| {
+                rewrite_refs(&mut schema, &prefix);
+                (format!("{prefix}.{def}"), schema)
+            })
+            .collect();
+        Self {
+            name: name.to_owned(),
+            backend: backend.to_owned(),
+            args,
+            defs,
+            push,
+        }
+    }
+
+    fn paths(&self, paths: &mut Map<String, Value>) {
+        let prefix = format!("/queues/{}", self.name);
+        let id = operation_suffix(&self.name);
+        let tag = format!("queue:{}", self.name);
+        let tags = [tag.as_str()];
+
+        let mut tasks = json!({
+            "get": operation(
+                &format!("listTasks{id}"),
+                &tags,
+                &format!("List the tasks of the `{}` queue.", self.name),
+                &filter_params(),
+                &array(&task_schema(&self.args)),
+            ),
+        });
+        if self.push {
+            tasks["put"] = json!({
+                "tags": tags,
+                "operationId": format!("pushTask{id}"),
+                "summary": format!("Push a task to the `{}` queue.", self.name),
+                "requestBody": {
+                    "required": true,
+                    "content": { "application/json": { "schema": self.args } }
+                },
+                "responses": json_responses(&json!({ "type": "null" })),
+            });
+        }
+        paths.insert(format!("{prefix}/tasks"), tasks);
+        paths.insert(
+            format!("{prefix}/tasks/{{task_id}}"),
+            json!({ "get": operation(
+                &format!("getTask{id}"),
+                &tags,
+                &format!("Fetch a task of the `{}` queue by its id.", self.name),
+                &[json!({
+                    "name": "task_id",
+                    "in": "path",
+                    "required": true,
+                    "schema": { "type": "string" }
+                })],
+                &task_schema(&self.args),
+            )}),
+        );
+        if self.push {
+            paths.insert(
+                format!("{prefix}/tasks/{{task_id}}/retry"),
+                json!({ "post": operation(
+                    &format!("retryTask{id}"),
+                    &tags,
+                    &format!(
+                        "Run a failed or killed task of the `{}` queue again as a new task.",
+                        self.name
+                    ),
+                    &[json!({
+                        "name": "task_id",
+                        "in": "path",
+                        "required": true,
+                        "schema": { "type": "string" }
+                    })],
+                    &schema_ref("RetriedTask"),
+                )}),
+            );
+        }
+        paths.insert(
+            format!("{prefix}/stats"),
+            json!({ "get": operation(
+                &format!("queueStats{id}"),
+                &tags,
+                &format!("Statistics of the `{}` queue.", self.name),
+                &[],
+                &array(&schema_ref("Statistic")),
+            )}),
+        );
+        paths.insert(
+            format!("{prefix}/workers"),
+            json!({ "get": operation(
+                &format!("listWorkers{id}"),
+                &tags,
+                &format!("Workers of the `{}` queue.", self.name),
+                &[],
+                &array(&schema_ref("RunningWorker")),
+            )}),
+        );
+        paths.insert(
+            format!("{prefix}/schema"),
+            json!({ "get": operation(
+                &format!("argsSchema{id}"),
+                &tags,
+                &format!("The JSON schema of the `{}` queue's arguments, if one was registered.", self.name),
+                &[],
+                &json!({ "type": "object" }),
+            )}),
+        );
+    }
+
+    fn tag(&self) -> Value {
+        json!({
+            "name": format!("queue:{}", self.name),
+            "description": format!("Tasks of the `{}` queue, stored in `{}`.", self.name, self.backend),
+        })
+    }
+}
+
+fn operation(id: &str, tags: &[&str], summary: &str, params: &[Value], schema: &Value) -> Value {
+    let mut op = json!({
+        "operationId": id,
+        "tags": tags,
+        "summary": summary,
+        "responses": json_responses(schema),
+    });
+    if !params.is_empty() {
+        op["parameters"] = Value::Array(params.to_vec());
+    }
+    op
+}
+
+fn tokens_paths(paths: &mut Map<String, Value>, read_only: bool) {
+    let tags = ["tokens"];
+    let mut collection = json!({ "get": operation(
+        "listTokens",
+        &tags,
+        "List the API tokens, without their secrets.",
+        &[],
+        &array(&schema_ref("ApiToken")),
+    )});
+    if read_only {
+        paths.insert("/admin/tokens".to_owned(), collection);
+        return;
+    }
+    collection["post"] = json!({
+        "tags": tags,
+        "operationId": "createToken",
+        "summary": "Mint an API token. Its secret is only returned here.",
+        "requestBody": {
+            "required": true,
+            "content": { "application/json": { "schema": schema_ref("NewToken") } }
+        },
+        "responses": json_responses(&schema_ref("CreatedToken")),
+    });
+    paths.insert("/admin/tokens".to_owned(), collection);
+    let mut revoke = operation(
+        "revokeToken",
+        &tags,
+        "Revoke an API token.",
+        &[json!({
+            "name": "id",
+            "in": "path",
+            "required": true,
+            "schema": { "type": "string" }
+        })],
+        &json!({ "type": "null" }),
+    );
+    if let Some(ok) = revoke["responses"]
+        .as_object_mut()
+        .and_then(|r| r.remove("200"))
+    {
+        revoke["responses"]["204"] = json!({ "description": ok["description"] });
+    }
+    paths.insert("/admin/tokens/{id}".to_owned(), json!({ "delete": revoke }));
+}
+
+fn json_responses(schema: &Value) -> Value {
+    json!({
+        "200": {
+            "description": "OK",
+            "content": { "application/json": { "schema": schema } }
+        },
+        "401": {
+            "description": "The request could not be authenticated",
+            "content": { "application/json": { "schema": schema_ref("ApiError") } }
+        },
+        "403": {
+            "description": "The user is not allowed to perform the request",
+            "content": { "application/json": { "schema": schema_ref("ApiError") } }
+        },
+        "404": {
+            "description": "The resource was not found",
+            "content": { "application/json": { "schema": schema_ref("ApiError") } }
+        },
+        "422": {
+            "description": "The request was malformed or failed validation",
+            "content": { "application/json": { "schema": schema_ref("ApiError") } }
+        },
+        "500": {
+            "description": "The backend failed to handle the request",
+            "content": { "application/json": { "schema": schema_ref("ApiError") } }
+        }
+    })
+}
+
+fn filter_params() -> Vec<Value> {
+    vec![
+        json!({
+            "name": "status",
+            "in": "query",
+            "required": false,
+            "schema": schema_ref("Status")
+        }),
+        json!({
+            "name": "page",
+            "in": "query",
+            "required": false,
+            "description": "Pages reaching past the first 10000 tasks, `page * page_size`, are refused.",
+            "schema": { "type": "integer", "minimum": 1, "default": 1 }
+        }),
+        json!({
+            "name": "page_size",
+            "in": "query",
+            "required": false,
+            "schema": { "type": "integer", "minimum": 0, "maximum": 10000 }
+        }),
+    ]
+}
+
+fn backend_param() -> Value {
+    json!({
+        "name": "backend",
+        "in": "query",
+        "required": false,
+        "description": "Only list the items of this backend, see `/backends`.",
+        "schema": { "type": "string" }
+    })
+}
+
+fn audit_params() -> Vec<Value> {
+    let param = |name: &str, schema: Value| json!({ "name": name, "in": "query", "required": false, "schema": schema });
+    vec![
+        param("actor", json!({ "type": "string" })),
+        param("action", schema_ref("AuditAction")),
+        param("queue", json!({ "type": "string" })),
+        param("task_id", json!({ "type": "string" })),
+        param(
+            "limit",
+            json!({ "type": "integer", "minimum": 0, "default": 100 }),
+        ),
+    ]
+}
+
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{name}") })
+}
+
+/// `schema` with the `backend` it was listed by.
+fn tagged(schema: &Value) -> Value {
+    json!({
+        "allOf": [
+            schema,
+            {
+                "type": "object",
+                "required": ["backend"],
+                "properties": { "backend": { "type": "string" } }
+            }
+        ]
+    })
+}
+
+fn array(items: &Value) -> Value {
+    json!({ "type": "array", "items": items })
+}
+
+/// A task with the given argument schema.
+fn task_schema(args: &Value) -> Value {
+    json!({
+        "type": "object",
+        "required": ["args", "parts"],
+        "properties": {
+            "args": args,
+            "parts": schema_ref("Parts"),
+        }
+    })
+}
+
+/// Turn a queue name into a camel case suffix for operation ids.
+fn operation_suffix(queue: &str) -> String {
+    queue
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            let mut chars = s.chars();
+            chars
+                .next()
+                .map(|c| c.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Turn a queue name into a valid component name prefix.
+fn component_name(queue: &str) -> String {
+    queue
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Point `$defs` references of a JSON schema at `components/schemas/{prefix}.{name}`.
+fn rewrite_refs(schema: &mut Value, prefix: &str) {
+    match schema {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                match value {
+                    Value::String(r) if key == "$ref" => {
+                        if let Some(name) = r.strip_prefix("#/$defs/") {
+                            *r = format!("#/components/schemas/{prefix}.{name}");
+                        }
+                    }
+                    _ => rewrite_refs(value, prefix),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|v| rewrite_refs(v, prefix)),
+        _ => {}
+    }
+}
+
+/// Schemas of the types shared by all backends.
+fn components() -> Value {
+    json!({
+        "Status": {
+            "type": "string",
+            "enum": ["Pending", "Queued", "Running", "Done", "Failed", "Killed"]
+        },
+        "StatType": {
+            "type": "string",
+            "enum": ["Timestamp", "Number", "Decimal", "Percentage"]
+        },
+        "Statistic": {
+            "type": "object",
+            "required": ["title", "stat_type", "value"],
+            "properties": {
+                "title": { "type": "string" },
+                "stat_type": schema_ref("StatType"),
+                "value": { "type": "string" },
+                "priority": { "type": ["integer", "null"], "minimum": 0 }
+            }
+        },
+        "QueueInfo": {
+            "type": "object",
+            "required": ["name", "stats", "workers", "activity"],
+            "properties": {
+                "name": { "type": "string" },
+                "stats": array(&schema_ref("Statistic")),
+                "workers": array(&json!({ "type": "string" })),
+                "activity": {
+                    "description": "Tasks per day over the last 7 days.",
+                    "type": "array",
+                    "items": { "type": "integer", "minimum": 0 }
+                }
+            }
+        },
+        "RunningWorker": {
+            "type": "object",
+            "required": ["id", "queue", "backend", "started_at", "last_heartbeat", "layers"],
+            "properties": {
+                "id": { "type": "string" },
+                "queue": { "type": "string" },
+                "backend": { "type": "string" },
+                "started_at": { "type": "integer", "minimum": 0 },
+                "last_heartbeat": { "type": "integer", "minimum": 0 },
+                "layers": { "type": "string" }
+            }
+        },
+        "Parts": {
+            "type": "object",
+            "required": ["attempt", "ctx", "status", "run_at"],
+            "properties": {
+                "task_id": { "type": ["string", "null"] },
+                "attempt": { "type": "integer", "minimum": 0 },
+                "ctx": {
+                    "description": "Backend specific context, e.g. `max_attempts` or `lock_by`.",
+                    "type": "object"
+                },
+                "status": schema_ref("Status"),
+                "run_at": { "type": "integer", "minimum": 0 },
+                "idempotency_key": { "type": ["string", "null"] }
+            }
+        },
+        "BoardConfig": {
+            "type": "object",
+            "required": ["read_only"],
+            "properties": {
+                "read_only": { "type": "boolean", "description": "Write routes are not registered." },
+                "tokens": { "type": "boolean", "description": "API tokens can be managed." }
+            }
+        },
+        "Grant": {
+            "type": "object",
+            "required": ["role", "queues"],
+            "properties": {
+                "role": { "type": "string", "enum": ["viewer", "operator", "admin"] },
+                "queues": { "type": "string", "description": "A queue name pattern, `*` matches anything." }
+            }
+        },
+        "ApiToken": {
+            "type": "object",
+            "required": ["id", "name", "grants", "created_by", "created_at"],
+            "properties": {
+                "id": { "type": "string" },
+                "name": { "type": "string" },
+                "grants": array(&schema_ref("Grant")),
+                "created_by": { "type": "string" },
+                "created_at": { "type": "integer", "minimum": 0 },
+                "expires_at": { "type": ["integer", "null"], "minimum": 0 }
+            }
+        },
+        "NewToken": {
+            "type": "object",
+            "required": ["name", "grants"],
+            "properties": {
+                "name": { "type": "string" },
+                "grants": { "type": "array", "items": schema_ref("Grant"), "minItems": 1 },
+                "expires_at": { "type": ["integer", "null"], "minimum": 0 }
+            }
+        },
+        "CreatedToken": {
+            "type": "object",
+            "required": ["token", "secret"],
+            "properties": {
+                "token": schema_ref("ApiToken"),
+                "secret": { "type": "string" }
+            }
+        },
+        "RetriedTask": {
+            "type": "object",
+            "required": ["task_id"],
+            "properties": {
+                "task_id": { "type": ["string", "null"] }
+            }
+        },
+        "AuditAction": {
+            "type": "string",
+            "enum": [
+                "push",
+                "retry",
+                "kill",
+                "edit",
+                "purge",
+                "pause",
+                "create_token",
+                "revoke_token"
+            ]
+        },
+        "RequestOrigin": {
+            "type": "object",
+            "properties": {
+                "address": { "type": ["string", "null"] },
+                "user_agent": { "type": ["string", "null"] }
+            }
+        },
+        "AuditRecord": {
+            "type": "object",
+            "required": ["actor", "action", "timestamp"],
+            "properties": {
+                "actor": { "type": "string" },
+                "actor_id": {
+                    "type": "string",
+                    "description": "The stable id of the user, `token:<id>` for API tokens."
+                },
+                "action": schema_ref("AuditAction"),
+                "queue": { "type": ["string", "null"] },
+                "task_ids": array(&json!({ "type": "string" })),
+                "target": {
+                    "type": ["string", "null"],
+                    "description": "The affected token, for token actions."
+                },
+                "timestamp": { "type": "integer", "minimum": 0 },
+                "origin": schema_ref("RequestOrigin"),
+                "error": {
+                    "type": ["string", "null"],
+                    "description": "Why the change failed, null when it succeeded."
+                }
+            }
+        },
+        "User": {
+            "type": "object",
+            "required": ["id", "username"],
+            "properties": {
+                "id": { "type": "string" },
+                "username": { "type": "string" },
+                "grants": array(&schema_ref("Grant"))
+            }
+        },
+        "ApiError": {
+            "type": "object",
+            "required": ["type", "message"],
+            "properties": {
+                "type": { "type": "string", "enum": [
+                        "CodecError",
+                        "BackendError",
+                        "Validation",
+                        "NotFound",
+                        "Conflict",
+                        "Unauthorized",
+                        "Forbidden"
+                    ] },
+                "message": { "type": "string" }
+            }
+        }
+    })
+}
+
+/// Where Swagger UI is loaded from unless
+/// [`ApiBuilder::swagger_ui`](crate::framework::ApiBuilder::swagger_ui) says otherwise.
+#[cfg(feature = "openapi-explorer")]
+pub const SWAGGER_UI: &str = "https://unpkg.com/swagger-ui-dist@5";
+
+/// The explorer page, `{swagger_ui}` standing for where Swagger UI is loaded from.
+#[cfg(feature = "openapi-explorer")]
+const EXPLORER_HTML: &str = r##"<!doctype html>
+<html lang="en">
+<head>
+  <meta charset="utf-8" />
+  <meta name="viewport" content="width=device-width, initial-scale=1" />
+  <title>apalis-board API</title>
+  <link rel="stylesheet" href="{swagger_ui}/swagger-ui.css" />
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="{swagger_ui}/swagger-ui-bundle.js" crossorigin></script>
+  <script>
+    window.onload = () => {
+      window.ui = SwaggerUIBundle({ url: "openapi.json", dom_id: "#swagger-ui" });
+    };
+  </script>
+</body>
+</html>
+"##;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::StubBackend;
+
+    fn queue(name: &str, schema: Option<Value>) -> StubBackend {
+        StubBackend {
+            schema,
+            ..StubBackend::new("SqliteStorage", name)
+        }
+    }
+
+    #[test]
+    fn test_document_lists_root_and_queue_routes() {
+        let registry = Registry::default();
+        registry.register(queue(
+            "email-service",
+            Some(json!({ "type": "object", "properties": { "to": { "type": "string" } } })),
+        ));
+        let config = BoardConfig {
+            read_only: false,
+            tokens: true,
+        };
+        let doc = OpenApi::new(registry.clone(), config.clone()).document();
+
+        assert_eq!(doc["openapi"], OPENAPI_VERSION);
+        for path in [
+            "/audit",
+            "/backends",
+            "/config",
+            "/me",
+            "/queues",
+            "/tasks",
+            "/workers",
+            "/overview",
+        ] {
+            assert!(doc["paths"][path]["get"].is_object(), "{path} missing");
+        }
+        let tasks = &doc["paths"]["/queues/email-service/tasks"];
+        assert_eq!(tasks["get"]["operationId"], "listTasksEmailService");
+        assert_eq!(
+            tasks["put"]["requestBody"]["content"]["application/json"]["schema"]["properties"]["to"]
+                ["type"],
+            "string"
+        );
+        assert!(doc["paths"]["/queues/email-service/tasks/{task_id}"]["get"].is_object());
+        assert!(doc["paths"]["/queues/email-service/tasks/{task_id}/retry"]["post"].is_object());
+        assert!(doc["paths"]["/admin/tokens"]["post"].is_object());
+        assert!(doc["paths"]["/admin/tokens/{id}"]["delete"]["responses"]["204"].is_object());
+
+        // Queues registered later are documented too
+        registry.register(queue("audit", None));
+        let read_only = BoardConfig {
+            read_only: true,
+            ..config
+        };
+        let doc = OpenApi::new(registry, read_only).document();
+        assert!(doc["paths"]["/queues/audit/tasks"]["get"].is_object());
+        assert!(doc["paths"]["/queues/audit/tasks"]["put"].is_null());
+        assert!(doc["paths"]["/queues/audit/tasks/{task_id}/retry"].is_null());
+    }
+
+    #[test]
+    fn test_page_is_optional() {
+        let doc = OpenApi::new(Registry::default(), BoardConfig::default()).document();
+        let page = doc["paths"]["/tasks"]["get"]["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|p| p["name"] == "page")
+            .unwrap();
+        assert_eq!(page["required"], false);
+        assert_eq!(page["schema"]["minimum"], 1);
+        assert_eq!(page["schema"]["default"], 1);
+    }
+
+    #[cfg(feature = "openapi-explorer")]
+    #[test]
+    fn test_explorer_loads_swagger_ui_from_configured_url() {
+        let openapi = OpenApi::new(Registry::default(), BoardConfig::default());
+        assert!(
+            openapi
+                .explorer()
+                .contains("https://unpkg.com/swagger-ui-dist@5/swagger-ui.css")
+        );
+
+        let html = openapi
+            .with_swagger_ui(Some("/static/swagger-ui/".to_owned()))
+            .explorer();
+        assert!(html.contains(r#"href="/static/swagger-ui/swagger-ui.css""#));
+        assert!(html.contains(r#"src="/static/swagger-ui/swagger-ui-bundle.js""#));
+        assert!(!html.contains("unpkg.com"));
+    }
+
+    #[test]
+    fn test_references_resolve() {
+        let registry = Registry::default();
+        registry.register(queue(
+            "emails",
+            Some(json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "type": "object",
+                "properties": { "to": { "$ref": "#/$defs/Address" } },
+                "$defs": { "Address": { "type": "string", "format": "email" } }
+            })),
+        ));
+        registry.register(queue(
+            "sms",
+            Some(json!({
+                "type": "object",
+                "properties": {
+                    "to": { "$ref": "#/$defs/Address" },
+                    "status": { "$ref": "#/$defs/Status" }
+                },
+                "$defs": {
+                    "Address": { "type": "string", "pattern": "^\\+[0-9]+$" },
+                    "Status": { "type": "string", "enum": ["Sent", "Delivered"] }
+                }
+            })),
+        ));
+        registry.register(queue("calls", None));
+        let doc = OpenApi::new(registry, BoardConfig::default()).document();
+        let text = doc.to_string();
+        let schemas = &doc["components"]["schemas"];
+
+        for name in text
+            .split("#/components/schemas/")
+            .skip(1)
+            .map(|s| s.split('"').next().unwrap_or_default())
+        {
+            assert!(
+                doc["components"]["schemas"][name].is_object(),
+                "unresolved {name}"
+            );
+        }
+        assert!(!text.contains("$defs"));
+        // Definitions of the same name don't shadow each other or the shared schemas
+        assert_eq!(schemas["emails.Address"]["format"], "email");
+        assert_eq!(schemas["sms.Address"]["pattern"], "^\\+[0-9]+$");
+        assert_eq!(schemas["sms.Status"]["enum"][0], "Sent");
+        assert_eq!(schemas["Status"]["enum"][0], "Pending");
+        assert_eq!(
+            doc["paths"]["/queues/sms/tasks"]["get"]["responses"]["200"]["content"]["application/json"]
+                ["schema"]["items"]["properties"]["args"]["properties"]["status"]["$ref"],
+            "#/components/schemas/sms.Status"
+        );
+        assert_eq!(
+            doc["paths"]["/queues/calls/tasks"]["put"]["requestBody"]["content"]["application/json"]
+                ["schema"],
+            json!({})
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use std::fmt::Write;
+
+use apalis_board_types::ApiError;
+use apalis_core::backend::{RunningWorker, Statistic};
+
+use crate::registry::{Registry, numeric_value};
+
+/// The content type of the Prometheus text exposition format.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Collect every registered statistic and render it in the Prometheus text format.
+///
+/// A backend or queue failing to report is logged and skipped, the scrape
+/// goes on with the others. Whether each collector succeeded is exported as
+/// `apalis_board_collector_up`.
+pub async fn render(registry: &Registry) -> String {
+    let mut samples = Vec::new();
+    let now = crate::now();
+    for backend in &registry.backends() {
+        let labels = vec![("backend", backend.name().to_owned())];
+        let overview = backend.overview().await;
+        samples.push(Sample::collector_up("overview", labels.clone(), &overview));
+        for stat in overview.unwrap_or_default() {
+            samples.extend(Sample::from_statistic("apalis", &stat, labels.clone()));
+        }
+
+        let workers = backend.list_all_workers().await;
+        samples.push(Sample::collector_up("workers", labels, &workers));
+        for worker in workers.unwrap_or_default() {
+            let worker = RunningWorker {
+                backend: backend.name().to_owned(),
+                ..worker
+            };
+            samples.push(Sample::heartbeat(&worker, now));
+        }
+    }
+    for queue in registry.queues() {
+        let labels = vec![
+            ("queue", queue.queue().to_owned()),
+            ("backend", queue.name().to_owned()),
+        ];
+        let stats = queue.stats().await;
+        samples.push(Sample::collector_up("queue", labels.clone(), &stats));
+        for stat in stats.unwrap_or_default() {
+            samples.extend(Sample::from_statistic(
+                "apalis_queue",
+                &stat,
+                labels.clone(),
+            ));
+        }
+    }
+
+    encode(&samples)
+}
+
+/// A single gauge sample.
+#[derive(Debug, Clone, PartialEq)]
+struct Sample {
+    name: String,
+    help: String,
+    labels: Vec<(&'static str, String)>,
+    value: f64,
+}
+
+impl Sample {
+    /// Map a numeric statistic to a gauge, skipping timestamps and unparsable values.
+    fn from_statistic(
+        prefix: &str,
+        stat: &Statistic,
+        labels: Vec<(&'static str, String)>,
+    ) -> Option<Self> {
+        let value = numeric_value(stat)?;
+        Some(Self {
+            name: format!("{prefix}_{}", metric_name(&stat.title)),
+            help: format!("The {} statistic reported by the backend.", stat.title),
+            labels,
+            value,
+        })
+    }
+
+    /// Whether a collector succeeded, logging why it didn't.
+    fn collector_up<T>(
+        collector: &'static str,
+        mut labels: Vec<(&'static str, String)>,
+        result: &Result<T, ApiError>,
+    ) -> Self {
+        if let Err(e) = result {
+            tracing::warn!(collector, ?labels, error = %e, "Could not collect metrics");
+        }
+        labels.insert(0, ("collector", collector.to_owned()));
+        Self {
+            name: "apalis_board_collector_up".to_owned(),
+            help: "Whether the last collection of the statistics succeeded.".to_owned(),
+            labels,
+            value: if result.is_ok() { 1.0 } else { 0.0 },
+        }
+    }
+
+    fn heartbeat(worker: &RunningWorker, now: u64) -> Self {
+        Self {
+            name: "apalis_worker_heartbeat_age_seconds".to_owned(),
+            help: "Seconds since the worker last sent a heartbeat.".to_owned(),
+            labels: vec![
+                ("worker", worker.id.clone()),
+                ("queue", worker.queue.clone()),
+                ("backend", worker.backend.clone()),
+            ],
+            value: now.saturating_sub(worker.last_heartbeat) as f64,
+        }
+    }
+}
+
+/// Turn a statistic title into a valid Prometheus metric name.
+fn metric_name(title: &str) -> String {
+    title
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Render samples grouped by metric family.
+fn encode(samples: &[Sample]) -> String {
+    let mut families: Vec<(&str, &str, Vec<&Sample>)> = Vec::new();
+    for sample in samples {
+        match families
+            .iter_mut()
+            .find(|(name, _, _)| *name == sample.name)
+        {
+            Some((_, _, family)) => family.push(sample),
+            None => families.push((&sample.name, &sample.help, vec![sample])),
+        }
+    }
+
+    let mut out = String::new();
+    for (name, help, family) in families {
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} gauge");
+        for sample in family {
+            let labels = sample
+                .labels
+                .iter()
+                .map(|(k, v)| format!("{k}=\"{}\"", escape_label(v)))
+                .collect::<Vec<_>>()
+                .join(",");
+            let _ = writeln!(out, "{name}{{{labels}}} {}", sample.value);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::StubBackend;
+    use apalis_core::backend::StatType;
+
+    fn stat(title: &str, stat_type: StatType, value: &str) -> Statistic {
+        Statistic {
+            title: title.to_owned(),
+            stat_type,
+            value: value.to_owned(),
+            priority: None,
+        }
+    }
+
+    #[test]
+    fn test_numeric_statistics_become_gauges() {
+        let labels = vec![
+            ("queue", "emails".to_owned()),
+            ("backend", "Sqlite".to_owned()),
+        ];
+        let samples: Vec<_> = [
+            stat("PENDING_JOBS", StatType::Number, "4"),
+            stat("SUCCESS_RATE", StatType::Percentage, "97.5"),
+            stat("MOST_RECENT_JOB", StatType::Timestamp, "1700000000"),
+            stat("DB_SIZE", StatType::Number, "n/a"),
+        ]
+        .iter()
+        .filter_map(|s| Sample::from_statistic("apalis_queue", s, labels.clone()))
+        .collect();
+
+        assert_eq!(samples.len(), 2);
+        let out = encode(&samples);
+        assert!(out.contains("# TYPE apalis_queue_pending_jobs gauge"));
+        assert!(out.contains("apalis_queue_pending_jobs{queue=\"emails\",backend=\"Sqlite\"} 4"));
+        assert!(
+            out.contains("apalis_queue_success_rate{queue=\"emails\",backend=\"Sqlite\"} 97.5")
+        );
+        assert!(!out.contains("most_recent_job"));
+    }
+
+    #[test]
+    fn test_families_are_grouped_and_labels_escaped() {
+        let a = Sample::from_statistic(
+            "apalis_queue",
+            &stat("PENDING_JOBS", StatType::Number, "1"),
+            vec![("queue", "a\"b".to_owned())],
+        )
+        .unwrap();
+        let b = Sample::from_statistic(
+            "apalis_queue",
+            &stat("PENDING_JOBS", StatType::Number, "2"),
+            vec![("queue", "c".to_owned())],
+        )
+        .unwrap();
+
+        let out = encode(&[a, b]);
+        assert_eq!(out.matches("# TYPE apalis_queue_pending_jobs").count(), 1);
+        assert!(out.contains("queue=\"a\\\"b\""));
+    }
+
+    #[test]
+    fn test_failing_collectors_are_skipped() {
+        let registry = Registry::default();
+        registry.register(StubBackend {
+            overview: vec![stat("PENDING_JOBS", StatType::Number, "4")],
+            ..StubBackend::new("SqliteStorage", "emails")
+        });
+        registry.register(StubBackend {
+            broken: true,
+            ..StubBackend::new("PostgresStorage", "payments")
+        });
+
+        let out = futures::executor::block_on(render(&registry));
+        assert!(out.contains("apalis_pending_jobs{backend=\"SqliteStorage\"} 4"));
+        assert!(out.contains(
+            "apalis_board_collector_up{collector=\"overview\",backend=\"SqliteStorage\"} 1"
+        ));
+        assert!(out.contains(
+            "apalis_board_collector_up{collector=\"overview\",backend=\"PostgresStorage\"} 0"
+        ));
+        assert!(out.contains(
+            "apalis_board_collector_up{collector=\"queue\",queue=\"payments\",backend=\"PostgresStorage\"} 0"
+        ));
+        assert!(out.contains("worker=\"emails-worker\""));
+    }
+
+    #[test]
+    fn test_heartbeat_age() {
+        let worker = RunningWorker {
+            id: "worker-1".to_owned(),
+            queue: "emails".to_owned(),
+            backend: "SqliteStorage".to_owned(),
+            started_at: 0,
+            last_heartbeat: 90,
+            layers: String::new(),
+        };
+        let sample = Sample::heartbeat(&worker, 100);
+        assert_eq!(sample.value, 10.0);
+        assert_eq!(sample.name, "apalis_worker_heartbeat_age_seconds");
+    }
+}
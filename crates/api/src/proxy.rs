@@ -0,0 +1,60 @@
+use std::{net::IpAddr, sync::Arc};
+
+/// The reverse proxies whose `X-Forwarded-*` headers are believed.
+///
+/// Clients can send these headers too, so they are ignored unless the request
+/// comes straight from one of the proxies.
+#[derive(Clone, Debug, Default)]
+pub struct TrustedProxies(Arc<[IpAddr]>);
+
+impl TrustedProxies {
+    /// Believe the requests coming from `proxies`.
+    pub fn new(proxies: impl IntoIterator<Item = IpAddr>) -> Self {
+        Self(proxies.into_iter().collect())
+    }
+
+    /// Whether a request from `peer` was forwarded by a trusted proxy.
+    #[must_use]
+    pub fn trusts(&self, peer: Option<IpAddr>) -> bool {
+        peer.is_some_and(|peer| self.0.contains(&peer))
+    }
+
+    /// The address of the client, following `X-Forwarded-For` back through
+    /// the trusted proxies from the `peer` that sent the request.
+    #[must_use]
+    pub fn client(&self, peer: Option<IpAddr>, forwarded_for: Option<&str>) -> Option<IpAddr> {
+        let mut client = peer;
+        for hop in forwarded_for.into_iter().flat_map(|v| v.rsplit(',')) {
+            if !self.trusts(client) {
+                break;
+            }
+            match hop.trim().parse() {
+                Ok(hop) => client = Some(hop),
+                Err(_) => break,
+            }
+        }
+        client
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(addr: &str) -> Option<IpAddr> {
+        Some(addr.parse().unwrap())
+    }
+
+    #[test]
+    fn test_forwarded_for_needs_a_trusted_proxy() {
+        let forwarded = Some("198.51.100.1, 203.0.113.7, 10.0.0.2");
+        let none = TrustedProxies::default();
+        assert_eq!(none.client(ip("10.0.0.1"), forwarded), ip("10.0.0.1"));
+
+        let proxies = TrustedProxies::new([ip("10.0.0.1").unwrap(), ip("10.0.0.2").unwrap()]);
+        assert_eq!(proxies.client(ip("10.0.0.1"), forwarded), ip("203.0.113.7"));
+        assert_eq!(proxies.client(ip("10.0.0.1"), Some("junk")), ip("10.0.0.1"));
+        assert_eq!(proxies.client(ip("192.0.2.9"), forwarded), ip("192.0.2.9"));
+        assert_eq!(proxies.client(None, forwarded), None);
+    }
+}
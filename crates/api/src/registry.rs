@@ -0,0 +1,454 @@
+use std::{
+    fmt,
+    sync::{Arc, RwLock},
+};
+
+use apalis_board_types::{ApiError, BackendQuery, Tagged};
+use apalis_core::backend::{Filter, QueueInfo, RunningWorker, StatType, Statistic};
+use futures::future::try_join_all;
+
+use crate::backend::{BoardBackend, DynBackend};
+
+#[derive(Default)]
+struct Entries {
+    /// The first queue registered of every backend, serving the root routes.
+    backends: Vec<DynBackend>,
+    queues: Vec<DynBackend>,
+}
+
+/// Keeps every [`BoardBackend`] registered with an
+/// [`ApiBuilder`](crate::framework::ApiBuilder), so the routes can find the
+/// queue they serve and the root routes and `/metrics` can report on all of
+/// them at once.
+///
+/// Queues may also be registered at runtime, through the registry returned by
+/// [`ApiBuilder::registry`](crate::framework::ApiBuilder::registry).
+///
+/// Backends are told apart by their [name](BoardBackend::name): the root
+/// routes are served by the first queue of each, as queues of the same backend
+/// are assumed to share its storage. A second storage of the same type, e.g.
+/// another Postgres database, must be registered with a distinct
+/// [`named`](crate::backend::BackendAdapter::named), or its tasks and workers
+/// are missing from the root routes.
+#[derive(Clone, Default)]
+pub struct Registry {
+    entries: Arc<RwLock<Entries>>,
+}
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let queues = self
+            .queues()
+            .iter()
+            .map(|q| q.queue().to_owned())
+            .collect::<Vec<_>>();
+        f.debug_struct("Registry")
+            .field("backends", &self.names())
+            .field("queues", &queues)
+            .finish()
+    }
+}
+
+impl Registry {
+    /// Serve the queue of `backend`, replacing a queue of the same name and backend.
+    ///
+    /// A queue already served by another backend is kept, and the new one is
+    /// ignored with a warning.
+    pub fn register(&self, backend: impl BoardBackend) {
+        self.register_dyn(Arc::new(backend));
+    }
+
+    /// Serve the queue of a type-erased `backend`, see [`register`](Self::register).
+    pub fn register_dyn(&self, backend: DynBackend) {
+        let mut entries = self.entries.write().expect("registry poisoned");
+        let served = entries
+            .queues
+            .iter()
+            .position(|q| q.queue() == backend.queue());
+        match served {
+            Some(i) if entries.queues[i].name() != backend.name() => {
+                tracing::warn!(
+                    "Queue `{}` of `{}` is already served by `{}`, name the backends or queues apart",
+                    backend.queue(),
+                    backend.name(),
+                    entries.queues[i].name()
+                );
+            }
+            Some(i) => {
+                if let Some(first) = entries
+                    .backends
+                    .iter_mut()
+                    .find(|b| b.queue() == backend.queue())
+                {
+                    *first = backend.clone();
+                }
+                entries.queues[i] = backend;
+            }
+            None => {
+                if !entries.backends.iter().any(|b| b.name() == backend.name()) {
+                    entries.backends.push(backend.clone());
+                }
+                entries.queues.push(backend);
+            }
+        }
+    }
+
+    /// The queue named `name`.
+    #[must_use]
+    pub fn queue(&self, name: &str) -> Option<DynBackend> {
+        self.entries
+            .read()
+            .expect("registry poisoned")
+            .queues
+            .iter()
+            .find(|q| q.queue() == name)
+            .cloned()
+    }
+
+    /// Every registered queue, in registration order.
+    #[must_use]
+    pub fn queues(&self) -> Vec<DynBackend> {
+        self.entries
+            .read()
+            .expect("registry poisoned")
+            .queues
+            .clone()
+    }
+
+    /// A queue of every backend, serving the root routes.
+    pub(crate) fn backends(&self) -> Vec<DynBackend> {
+        self.entries
+            .read()
+            .expect("registry poisoned")
+            .backends
+            .clone()
+    }
+
+    /// The names of the registered backends, in registration order.
+    #[must_use]
+    pub fn names(&self) -> Vec<String> {
+        self.entries
+            .read()
+            .expect("registry poisoned")
+            .backends
+            .iter()
+            .map(|b| b.name().to_owned())
+            .collect()
+    }
+
+    /// The backends selected by `query`, all of them when it names none.
+    fn select(&self, query: &BackendQuery) -> Result<Vec<DynBackend>, ApiError> {
+        let backends = self.backends();
+        match query.backend.as_deref().filter(|b| !b.is_empty()) {
+            None => Ok(backends),
+            Some(name) => {
+                let selected: Vec<_> = backends.into_iter().filter(|b| b.name() == name).collect();
+                if selected.is_empty() {
+                    return Err(ApiError::NotFound(format!("No backend named `{name}`")));
+                }
+                Ok(selected)
+            }
+        }
+    }
+
+    /// The queues of the selected backends.
+    pub async fn list_queues(
+        &self,
+        query: &BackendQuery,
+    ) -> Result<Vec<Tagged<QueueInfo>>, ApiError> {
+        let lists = try_join_all(self.select(query)?.into_iter().map(|b| async move {
+            let queues = b.list_queues().await?;
+            Ok::<_, ApiError>(tag(b.name(), queues))
+        }))
+        .await?;
+        Ok(lists.into_iter().flatten().collect())
+    }
+
+    /// A page of tasks of every queue of the selected backends.
+    ///
+    /// With several backends, each lists its tasks up to the end of the page,
+    /// and the page is cut from them merged the way the backends sort them:
+    /// latest `done_at` first, then latest `run_at`. Pages reaching past the
+    /// first 10,000 tasks are refused.
+    pub async fn list_tasks(
+        &self,
+        filter: &Filter,
+        query: &BackendQuery,
+    ) -> Result<Vec<Tagged<serde_json::Value>>, ApiError> {
+        crate::check_page(filter)?;
+        let backends = self.select(query)?;
+        if let [backend] = backends.as_slice() {
+            return Ok(tag(backend.name(), backend.list_all_tasks(filter).await?));
+        }
+        let page = filter.page;
+        let up_to_page = Filter {
+            status: filter.status.clone(),
+            page: 1,
+            page_size: Some(page.saturating_mul(filter.limit())),
+        };
+        let up_to_page = &up_to_page;
+        let lists = try_join_all(backends.iter().map(|b| async move {
+            let tasks = b.list_all_tasks(up_to_page).await?;
+            Ok::<_, ApiError>(tag(b.name(), tasks))
+        }))
+        .await?;
+        let mut tasks: Vec<_> = lists.into_iter().flatten().collect();
+        tasks.sort_by_key(|task| std::cmp::Reverse(recency(&task.item)));
+        Ok(tasks
+            .into_iter()
+            .skip(((page - 1) * filter.limit()) as usize)
+            .take(filter.limit() as usize)
+            .collect())
+    }
+
+    /// The workers of the selected backends, their `backend` set to the
+    /// name of the backend they were listed by.
+    pub async fn list_workers(&self, query: &BackendQuery) -> Result<Vec<RunningWorker>, ApiError> {
+        let lists = try_join_all(self.select(query)?.into_iter().map(|b| async move {
+            let workers = b.list_all_workers().await?;
+            Ok::<_, ApiError>(
+                workers
+                    .into_iter()
+                    .map(|worker| RunningWorker {
+                        backend: b.name().to_owned(),
+                        ..worker
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        }))
+        .await?;
+        Ok(lists.into_iter().flatten().collect())
+    }
+
+    /// The statistics of the selected backends, merged by title.
+    ///
+    /// Numbers are summed, decimals and percentages averaged. Timestamps keep
+    /// the latest value, or the earliest one for `OLDEST_*` statistics.
+    pub async fn overview(&self, query: &BackendQuery) -> Result<Vec<Statistic>, ApiError> {
+        let overviews = try_join_all(
+            self.select(query)?
+                .into_iter()
+                .map(|b| async move { b.overview().await }),
+        )
+        .await?;
+        Ok(merge_statistics(overviews))
+    }
+}
+
+/// When a listed task was done and is due, the keys the backends sort by.
+fn recency(task: &serde_json::Value) -> (Option<i64>, Option<u64>) {
+    let parts = &task["parts"];
+    (parts["ctx"]["done_at"].as_i64(), parts["run_at"].as_u64())
+}
+
+fn tag<T>(backend: &str, items: Vec<T>) -> Vec<Tagged<T>> {
+    items
+        .into_iter()
+        .map(|item| Tagged {
+            backend: backend.to_owned(),
+            item,
+        })
+        .collect()
+}
+
+/// Merge the statistics of several backends by title, see [`Registry::overview`].
+///
+/// Values that can't be parsed keep the first one.
+pub(crate) fn merge_statistics(overviews: Vec<Vec<Statistic>>) -> Vec<Statistic> {
+    if overviews.len() == 1 {
+        return overviews.into_iter().flatten().collect();
+    }
+    let mut groups: Vec<Vec<Statistic>> = Vec::new();
+    for stat in overviews.into_iter().flatten() {
+        match groups.iter_mut().find(|g| g[0].title == stat.title) {
+            Some(group) => group.push(stat),
+            None => groups.push(vec![stat]),
+        }
+    }
+    groups.into_iter().map(merge_group).collect()
+}
+
+fn merge_group(group: Vec<Statistic>) -> Statistic {
+    let parse = |s: &Statistic| s.value.trim().trim_end_matches('%').parse::<f64>().ok();
+    let values: Option<Vec<f64>> = group.iter().map(parse).collect();
+    let mut group = group.into_iter();
+    let first = group.next().expect("groups are never empty");
+    let Some(values) = values else {
+        return first;
+    };
+    match first.stat_type {
+        StatType::Timestamp => {
+            let oldest = first.title.to_ascii_uppercase().starts_with("OLDEST");
+            std::iter::once(first)
+                .chain(group)
+                .zip(values)
+                .reduce(|a, b| if (b.1 < a.1) == oldest { b } else { a })
+                .map(|(stat, _)| stat)
+                .expect("groups are never empty")
+        }
+        StatType::Number => {
+            let sum: f64 = values.iter().sum();
+            let value = if sum.fract() == 0.0 {
+                format!("{sum:.0}")
+            } else {
+                sum.to_string()
+            };
+            Statistic { value, ..first }
+        }
+        StatType::Decimal | StatType::Percentage => {
+            let average = values.iter().sum::<f64>() / values.len() as f64;
+            Statistic {
+                value: format!("{average:.2}"),
+                ..first
+            }
+        }
+    }
+}
+
+/// Parse the value of a numeric statistic, ignoring timestamps.
+#[cfg(any(feature = "prometheus", feature = "grafana"))]
+pub(crate) fn numeric_value(stat: &Statistic) -> Option<f64> {
+    if matches!(stat.stat_type, StatType::Timestamp) {
+        return None;
+    }
+    stat.value.trim().trim_end_matches('%').parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::StubBackend;
+
+    fn stat(title: &str, stat_type: StatType, value: &str) -> Statistic {
+        Statistic {
+            title: title.to_owned(),
+            stat_type,
+            value: value.to_owned(),
+            priority: None,
+        }
+    }
+
+    fn backend(name: &str, queue: &str, running: &str) -> StubBackend {
+        StubBackend {
+            overview: vec![stat("RUNNING_JOBS", StatType::Number, running)],
+            ..StubBackend::new(name, queue)
+        }
+    }
+
+    #[test]
+    fn test_root_routes_span_every_backend() {
+        let registry = Registry::default();
+        registry.register(backend("SqliteStorage", "emails", "2"));
+        registry.register(backend("PostgresStorage", "payments", "3"));
+        // Same backend, only adds a queue
+        registry.register(backend("SqliteStorage", "sms", "7"));
+        assert_eq!(registry.queues().len(), 3);
+        assert_eq!(registry.queue("sms").unwrap().name(), "SqliteStorage");
+        let all = BackendQuery::default();
+        let only = |name: &str| BackendQuery {
+            backend: Some(name.to_owned()),
+        };
+
+        let queues = futures::executor::block_on(registry.list_queues(&all)).unwrap();
+        let tags: Vec<_> = queues
+            .iter()
+            .map(|q| (q.backend.as_str(), q.item.name.as_str()))
+            .collect();
+        assert_eq!(
+            tags,
+            [("SqliteStorage", "emails"), ("PostgresStorage", "payments")]
+        );
+        let workers =
+            futures::executor::block_on(registry.list_workers(&only("PostgresStorage"))).unwrap();
+        assert_eq!(workers.len(), 1);
+        assert_eq!(workers[0].backend, "PostgresStorage");
+        assert_eq!(
+            futures::executor::block_on(registry.overview(&all)).unwrap()[0].value,
+            "5"
+        );
+        assert!(matches!(
+            futures::executor::block_on(registry.list_queues(&only("Redis"))),
+            Err(ApiError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_queue_collisions_keep_the_first_backend() {
+        let registry = Registry::default();
+        registry.register(backend("SqliteStorage", "emails", "2"));
+        registry.register(backend("PostgresStorage", "emails", "3"));
+        assert_eq!(registry.queue("emails").unwrap().name(), "SqliteStorage");
+        assert_eq!(registry.names(), ["SqliteStorage"]);
+
+        // Registering the queue again with its backend updates it
+        registry.register(StubBackend {
+            schema: Some(serde_json::json!({ "type": "string" })),
+            ..backend("SqliteStorage", "emails", "2")
+        });
+        assert!(registry.queue("emails").unwrap().schema().is_some());
+        assert!(registry.backends()[0].schema().is_some());
+    }
+
+    #[test]
+    fn test_task_pages_span_every_backend() {
+        let tasks = |done: &[i64]| {
+            done.iter()
+                .map(|at| serde_json::json!({ "parts": { "ctx": { "done_at": at }, "run_at": 0 } }))
+                .collect()
+        };
+        let registry = Registry::default();
+        registry.register(StubBackend {
+            tasks: tasks(&[60, 40, 10]),
+            ..StubBackend::new("SqliteStorage", "emails")
+        });
+        registry.register(StubBackend {
+            tasks: tasks(&[50, 30, 20]),
+            ..StubBackend::new("PostgresStorage", "payments")
+        });
+        let list = |page, page_size| {
+            let filter = Filter {
+                status: None,
+                page,
+                page_size: Some(page_size),
+            };
+            futures::executor::block_on(registry.list_tasks(&filter, &BackendQuery::default()))
+        };
+        let page = |page| {
+            list(page, 2)
+                .unwrap()
+                .iter()
+                .map(|t| t.item["parts"]["ctx"]["done_at"].as_i64().unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(page(1), [60, 50]);
+        assert_eq!(page(2), [40, 30]);
+        assert_eq!(page(3), [20, 10]);
+        assert!(page(4).is_empty());
+
+        assert!(matches!(list(0, 2), Err(ApiError::Validation(_))));
+        assert!(matches!(list(5_001, 2), Err(ApiError::Validation(_))));
+        assert!(matches!(list(1_000_000, 20), Err(ApiError::Validation(_))));
+        assert!(matches!(list(2, u32::MAX), Err(ApiError::Validation(_))));
+        assert!(list(5_000, 2).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_statistics_merge_by_type() {
+        let merged = merge_statistics(vec![
+            vec![
+                stat("KILL_RATE", StatType::Percentage, "10"),
+                stat("OLDEST_PENDING_JOB", StatType::Timestamp, "200.0"),
+                stat("MOST_RECENT_JOB", StatType::Timestamp, "300.0"),
+                stat("ERROR", StatType::Number, "n/a"),
+            ],
+            vec![
+                stat("KILL_RATE", StatType::Percentage, "20"),
+                stat("OLDEST_PENDING_JOB", StatType::Timestamp, "100.0"),
+                stat("MOST_RECENT_JOB", StatType::Timestamp, "400.0"),
+                stat("ERROR", StatType::Number, "1"),
+            ],
+        ]);
+        let values: Vec<_> = merged.iter().map(|s| s.value.as_str()).collect();
+        assert_eq!(values, ["15.00", "100.0", "400.0", "n/a"]);
+    }
+}
@@ -0,0 +1,497 @@
+use std::{
+    collections::HashMap,
+    fmt, io,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use apalis_board_types::{ApiError, ApiToken, CreatedToken, Grant, NewToken, User};
+use futures::future::{BoxFuture, FutureExt, ready};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    auth::{Authenticator, Credentials},
+    now,
+};
+
+/// The prefix of minted secrets, so leaked tokens are easy to search for.
+pub const TOKEN_PREFIX: &str = "abt_";
+
+/// A token as kept by a [`TokenStore`], with the hash of its secret.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredToken {
+    /// The public part of the token.
+    #[serde(flatten)]
+    pub token: ApiToken,
+    /// The hex encoded SHA-256 of the secret.
+    pub hash: String,
+}
+
+/// Persists API tokens.
+///
+/// Only hashes of the secrets reach the store.
+pub trait TokenStore: Send + Sync + 'static {
+    /// Every token, expired ones included.
+    fn list(&self) -> BoxFuture<'_, Result<Vec<StoredToken>, ApiError>>;
+
+    /// The token whose secret hashes to `hash`.
+    fn find<'a>(&'a self, hash: &'a str) -> BoxFuture<'a, Result<Option<StoredToken>, ApiError>>;
+
+    /// Save a new token.
+    fn insert(&self, token: StoredToken) -> BoxFuture<'_, Result<(), ApiError>>;
+
+    /// Delete a token, returning whether it existed.
+    fn remove<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, ApiError>>;
+}
+
+/// Keeps tokens in memory, they are lost on restart.
+#[derive(Debug, Default)]
+pub struct MemoryTokenStore {
+    tokens: RwLock<HashMap<String, StoredToken>>,
+}
+
+impl MemoryTokenStore {
+    /// Create an empty store.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn snapshot(&self) -> Vec<StoredToken> {
+        let mut tokens: Vec<_> = self
+            .tokens
+            .read()
+            .expect("token store poisoned")
+            .values()
+            .cloned()
+            .collect();
+        tokens.sort_by_key(|t| t.token.created_at);
+        tokens
+    }
+}
+
+impl TokenStore for MemoryTokenStore {
+    fn list(&self) -> BoxFuture<'_, Result<Vec<StoredToken>, ApiError>> {
+        ready(Ok(self.snapshot())).boxed()
+    }
+
+    fn find<'a>(&'a self, hash: &'a str) -> BoxFuture<'a, Result<Option<StoredToken>, ApiError>> {
+        let tokens = self.tokens.read().expect("token store poisoned");
+        let found = tokens.values().find(|t| t.hash == hash).cloned();
+        ready(Ok(found)).boxed()
+    }
+
+    fn insert(&self, token: StoredToken) -> BoxFuture<'_, Result<(), ApiError>> {
+        self.tokens
+            .write()
+            .expect("token store poisoned")
+            .insert(token.token.id.clone(), token);
+        ready(Ok(())).boxed()
+    }
+
+    fn remove<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, ApiError>> {
+        let removed = self
+            .tokens
+            .write()
+            .expect("token store poisoned")
+            .remove(id)
+            .is_some();
+        ready(Ok(removed)).boxed()
+    }
+}
+
+/// Keeps tokens in a JSON file, rewritten on every change.
+#[derive(Debug)]
+pub struct FileTokenStore {
+    path: PathBuf,
+    memory: MemoryTokenStore,
+    /// Held while a change is applied and saved, so writes don't interleave.
+    writing: tokio::sync::Mutex<()>,
+}
+
+impl FileTokenStore {
+    /// Load the tokens of `path`, which is created on the first change if missing.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let memory = MemoryTokenStore::new();
+        match std::fs::read(&path) {
+            Ok(bytes) => {
+                let tokens: Vec<StoredToken> = serde_json::from_slice(&bytes)?;
+                let mut map = memory.tokens.write().expect("token store poisoned");
+                for token in tokens {
+                    map.insert(token.token.id.clone(), token);
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        Ok(Self {
+            path,
+            memory,
+            writing: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// Apply `change` to the tokens and save them, undoing it if they can't be saved.
+    async fn change<T>(
+        &self,
+        change: impl FnOnce(&mut HashMap<String, StoredToken>) -> T,
+        undo: impl FnOnce(&mut HashMap<String, StoredToken>, T),
+    ) -> Result<T, ApiError> {
+        let _writing = self.writing.lock().await;
+        let changed = change(&mut self.memory.tokens.write().expect("token store poisoned"));
+        match self.save().await {
+            Ok(()) => Ok(changed),
+            Err(e) => {
+                undo(
+                    &mut self.memory.tokens.write().expect("token store poisoned"),
+                    changed,
+                );
+                Err(e)
+            }
+        }
+    }
+
+    /// Write the tokens next to the file and swap it in, off the async runtime.
+    async fn save(&self) -> Result<(), ApiError> {
+        let (tokens, path) = (self.memory.snapshot(), self.path.clone());
+        crate::blocking(move || {
+            let json = serde_json::to_vec_pretty(&tokens)?;
+            let tmp = path.with_extension("tmp");
+            std::fs::write(&tmp, json)?;
+            std::fs::rename(tmp, &path)
+        })
+        .await
+        .map_err(|e| ApiError::BackendError(format!("Could not save tokens: {e}")))
+    }
+}
+
+impl TokenStore for FileTokenStore {
+    fn list(&self) -> BoxFuture<'_, Result<Vec<StoredToken>, ApiError>> {
+        self.memory.list()
+    }
+
+    fn find<'a>(&'a self, hash: &'a str) -> BoxFuture<'a, Result<Option<StoredToken>, ApiError>> {
+        self.memory.find(hash)
+    }
+
+    fn insert(&self, token: StoredToken) -> BoxFuture<'_, Result<(), ApiError>> {
+        let id = token.token.id.clone();
+        async move {
+            self.change(
+                |tokens| tokens.insert(id.clone(), token),
+                |tokens, previous| match previous {
+                    Some(previous) => {
+                        tokens.insert(id.clone(), previous);
+                    }
+                    None => {
+                        tokens.remove(&id);
+                    }
+                },
+            )
+            .await
+            .map(|_| ())
+        }
+        .boxed()
+    }
+
+    fn remove<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<bool, ApiError>> {
+        async move {
+            let removed = self.change(
+                |tokens| tokens.remove(id),
+                |tokens, removed| {
+                    if let Some(removed) = removed {
+                        tokens.insert(id.to_owned(), removed);
+                    }
+                },
+            );
+            removed.await.map(|removed| removed.is_some())
+        }
+        .boxed()
+    }
+}
+
+/// Mints, lists and revokes API tokens, and authenticates requests carrying them.
+#[derive(Clone)]
+pub struct Tokens(Arc<dyn TokenStore>);
+
+impl fmt::Debug for Tokens {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Tokens").finish_non_exhaustive()
+    }
+}
+
+impl Tokens {
+    /// Manage the tokens of `store`.
+    pub fn new(store: impl TokenStore) -> Self {
+        Self(Arc::new(store))
+    }
+
+    /// The tokens `user` could have minted, expired ones included.
+    pub async fn list(&self, user: &User) -> Result<Vec<ApiToken>, ApiError> {
+        Ok(self
+            .0
+            .list()
+            .await?
+            .into_iter()
+            .map(|t| t.token)
+            .filter(|token| token.grants.iter().all(|g| covers(user, g)))
+            .collect())
+    }
+
+    /// Mint a token on behalf of `owner`.
+    ///
+    /// The token needs at least one grant, and may not be granted more than
+    /// `owner` has.
+    pub async fn mint(&self, request: NewToken, owner: &User) -> Result<CreatedToken, ApiError> {
+        let name = request.name.trim();
+        if name.is_empty() {
+            return Err(ApiError::Validation("The token needs a name".to_owned()));
+        }
+        let now = now();
+        if request.expires_at.is_some_and(|at| at <= now) {
+            return Err(ApiError::Validation(
+                "The token would already be expired".to_owned(),
+            ));
+        }
+        let grants = request.grants;
+        if grants.is_empty() {
+            return Err(ApiError::Validation(
+                "The token needs at least one grant".to_owned(),
+            ));
+        }
+        if let Some(grant) = grants.iter().find(|g| !covers(owner, g)) {
+            return Err(ApiError::Forbidden(format!(
+                "`{}` cannot grant {:?} on `{}`",
+                owner.username, grant.role, grant.queues
+            )));
+        }
+
+        let secret = format!("{TOKEN_PREFIX}{}", random_hex(32)?);
+        let token = ApiToken {
+            id: random_hex(8)?,
+            name: name.to_owned(),
+            grants,
+            created_by: owner.username.clone(),
+            created_at: now,
+            expires_at: request.expires_at,
+        };
+        self.0
+            .insert(StoredToken {
+                token: token.clone(),
+                hash: hash(&secret),
+            })
+            .await?;
+        Ok(CreatedToken { token, secret })
+    }
+
+    /// Revoke a token on behalf of `user`, who must cover all of its grants.
+    ///
+    /// Tokens `user` may not see are reported as not found.
+    pub async fn revoke(&self, id: &str, user: &User) -> Result<(), ApiError> {
+        let not_found = || ApiError::NotFound(format!("Token `{id}` not found"));
+        let visible = self.list(user).await?.iter().any(|token| token.id == id);
+        if visible && self.0.remove(id).await? {
+            Ok(())
+        } else {
+            Err(not_found())
+        }
+    }
+}
+
+impl Authenticator for Tokens {
+    fn authenticate<'a>(
+        &'a self,
+        credentials: &'a Credentials,
+    ) -> BoxFuture<'a, Result<User, ApiError>> {
+        async move {
+            let unauthorized = || ApiError::Unauthorized("Invalid or expired token".to_owned());
+            let secret = credentials.bearer().ok_or_else(unauthorized)?;
+            let stored = self.0.find(&hash(secret)).await?.ok_or_else(unauthorized)?;
+            if stored.token.expires_at.is_some_and(|at| at <= now()) {
+                return Err(unauthorized());
+            }
+            let mut user = User::new(stored.token.name);
+            user.id = format!("token:{}", stored.token.id);
+            user.grants = stored.token.grants;
+            Ok(user)
+        }
+        .boxed()
+    }
+
+    fn challenge(&self) -> Option<String> {
+        Some("Bearer".to_owned())
+    }
+}
+
+/// Whether `user` holds at least `grant`.
+///
+/// Patterns are compared literally, only a `*` grant covers other patterns.
+fn covers(user: &User, grant: &Grant) -> bool {
+    user.grants
+        .iter()
+        .any(|own| own.role >= grant.role && (own.queues == "*" || own.queues == grant.queues))
+}
+
+fn hash(secret: &str) -> String {
+    Sha256::digest(secret.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn random_hex(len: usize) -> Result<String, ApiError> {
+    let mut bytes = vec![0; len];
+    getrandom::fill(&mut bytes)
+        .map_err(|e| ApiError::BackendError(format!("Could not generate a token: {e}")))?;
+    Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use apalis_board_types::Role;
+
+    use super::*;
+
+    fn block_on<T>(fut: impl std::future::Future<Output = T>) -> T {
+        futures::executor::block_on(fut)
+    }
+
+    #[test]
+    fn test_minted_tokens_authenticate_until_revoked() {
+        let tokens = Tokens::new(MemoryTokenStore::new());
+        let admin = User::new("admin").grant(Role::Admin, "*");
+        let created = block_on(tokens.mint(
+            NewToken {
+                name: "ci".to_owned(),
+                grants: vec![Grant::new(Role::Operator, "emails")],
+                expires_at: None,
+            },
+            &admin,
+        ))
+        .unwrap();
+        assert!(created.secret.starts_with(TOKEN_PREFIX));
+
+        let credentials = Credentials::new(Some(&format!("Bearer {}", created.secret)), None, None);
+        let user = block_on(tokens.authenticate(&credentials)).unwrap();
+        assert_eq!(user.username, "ci");
+        assert_eq!(user.grants, vec![Grant::new(Role::Operator, "emails")]);
+
+        block_on(tokens.revoke(&created.token.id, &admin)).unwrap();
+        assert!(block_on(tokens.authenticate(&credentials)).is_err());
+    }
+
+    #[test]
+    fn test_tokens_cannot_escalate() {
+        let tokens = Tokens::new(MemoryTokenStore::new());
+        let scoped = User::new("ops").grant(Role::Admin, "emails-*");
+        let request = |role, queues: &str| NewToken {
+            name: "ci".to_owned(),
+            grants: vec![Grant::new(role, queues)],
+            expires_at: None,
+        };
+        assert!(matches!(
+            block_on(tokens.mint(request(Role::Viewer, "*"), &scoped)),
+            Err(ApiError::Forbidden(_))
+        ));
+        assert!(block_on(tokens.mint(request(Role::Operator, "emails-*"), &scoped)).is_ok());
+        assert!(matches!(
+            block_on(tokens.mint(request(Role::Viewer, "emails-*"), &User::new("nobody"))),
+            Err(ApiError::Forbidden(_))
+        ));
+
+        let inherited = NewToken {
+            grants: Vec::new(),
+            ..request(Role::Viewer, "emails-*")
+        };
+        assert!(matches!(
+            block_on(tokens.mint(inherited, &scoped)),
+            Err(ApiError::Validation(_))
+        ));
+
+        let expired = NewToken {
+            expires_at: Some(1),
+            ..request(Role::Viewer, "emails-*")
+        };
+        assert!(matches!(
+            block_on(tokens.mint(expired, &scoped)),
+            Err(ApiError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_scoped_admins_only_manage_their_tokens() {
+        let tokens = Tokens::new(MemoryTokenStore::new());
+        let admin = User::new("admin").grant(Role::Admin, "*");
+        let scoped = User::new("ops").grant(Role::Admin, "emails-*");
+        let mint = |queues: &str| {
+            let request = NewToken {
+                name: queues.to_owned(),
+                grants: vec![Grant::new(Role::Operator, queues)],
+                expires_at: None,
+            };
+            block_on(tokens.mint(request, &admin)).unwrap().token
+        };
+        let emails = mint("emails-*");
+        let everything = mint("*");
+
+        assert_eq!(
+            block_on(tokens.list(&scoped)).unwrap(),
+            vec![emails.clone()]
+        );
+        assert_eq!(block_on(tokens.list(&admin)).unwrap().len(), 2);
+        assert!(matches!(
+            block_on(tokens.revoke(&everything.id, &scoped)),
+            Err(ApiError::NotFound(_))
+        ));
+        block_on(tokens.revoke(&emails.id, &scoped)).unwrap();
+        assert_eq!(block_on(tokens.list(&admin)).unwrap(), vec![everything]);
+    }
+
+    #[tokio::test]
+    async fn test_file_store_keeps_hashes_only() {
+        let path =
+            std::env::temp_dir().join(format!("apalis-board-tokens-{}.json", std::process::id()));
+        let tokens = Tokens::new(FileTokenStore::open(&path).unwrap());
+        let admin = User::new("admin").grant(Role::Admin, "*");
+        let created = tokens
+            .mint(
+                NewToken {
+                    name: "ci".to_owned(),
+                    grants: vec![Grant::new(Role::Viewer, "*")],
+                    expires_at: None,
+                },
+                &admin,
+            )
+            .await
+            .unwrap();
+
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert!(!saved.contains(&created.secret));
+        assert!(saved.contains(&hash(&created.secret)));
+
+        let reopened = Tokens::new(FileTokenStore::open(&path).unwrap());
+        assert_eq!(reopened.list(&admin).await.unwrap(), vec![created.token]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_file_store_undoes_unsaved_changes() {
+        let path = std::env::temp_dir()
+            .join(format!("apalis-board-missing-{}", std::process::id()))
+            .join("tokens.json");
+        let tokens = Tokens::new(FileTokenStore::open(&path).unwrap());
+        let admin = User::new("admin").grant(Role::Admin, "*");
+        let request = NewToken {
+            name: "ci".to_owned(),
+            grants: vec![Grant::new(Role::Viewer, "*")],
+            expires_at: None,
+        };
+        assert!(matches!(
+            tokens.mint(request, &admin).await,
+            Err(ApiError::BackendError(_))
+        ));
+        assert!(tokens.list(&admin).await.unwrap().is_empty());
+    }
+}
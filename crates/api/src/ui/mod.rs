@@ -1,22 +1,163 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use apalis_board_types::UiConfig;
 use include_dir::{Dir, File, include_dir};
 
+use crate::{
+    auth::{Auth, Authenticator},
+    cors::Header,
+};
+
 /// Embed the built frontend directory into the lib.
 static APP_DIST: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/dist");
 
-/// A utility to serve the embedded frontend files.
+/// The gzip and brotli variants of the frontend files, compressed by the build script.
+static COMPRESSED: Dir<'_> = include_dir!("$OUT_DIR/ui");
+
+/// The content encodings of [`COMPRESSED`] by preference, with their extension.
+const ENCODINGS: [(&str, &str); 2] = [("br", "br"), ("gzip", "gz")];
+
+/// The frontend embedded at build time.
+static EMBEDDED: Bundle = Bundle {
+    files: &APP_DIST,
+    compressed: &COMPRESSED,
+    etags: OnceLock::new(),
+};
+
+/// An embedded frontend build with its precompressed variants.
+#[derive(Debug)]
+struct Bundle {
+    files: &'static Dir<'static>,
+    compressed: &'static Dir<'static>,
+    etags: OnceLock<HashMap<&'static str, String>>,
+}
+
+impl Bundle {
+    /// The weak `ETag` of the contents of the file at `path`, computed for
+    /// every file on first use.
+    fn etag(&self, path: &str) -> Option<&str> {
+        fn walk(dir: &'static Dir<'static>, etags: &mut HashMap<&'static str, String>) {
+            for file in dir.files() {
+                if let Some(path) = file.path().to_str() {
+                    etags.insert(path, crate::etag::etag(file.contents()));
+                }
+            }
+            for dir in dir.dirs() {
+                walk(dir, etags);
+            }
+        }
+        let etags = self.etags.get_or_init(|| {
+            let mut etags = HashMap::new();
+            walk(self.files, &mut etags);
+            etags
+        });
+        etags.get(path).map(String::as_str)
+    }
+}
+
+/// The request headers looked at by [`ServeUI::serve`].
+#[derive(Debug, Default)]
+pub(crate) struct UiHeaders<'a> {
+    pub(crate) accept_encoding: Option<&'a str>,
+    pub(crate) if_none_match: Option<&'a str>,
+    pub(crate) forwarded_prefix: Option<&'a str>,
+}
+
+/// The response to a request for a frontend file, see [`ServeUI::serve`].
+#[derive(Debug)]
+pub(crate) struct Served {
+    pub(crate) status: u16,
+    pub(crate) headers: Vec<Header>,
+    pub(crate) body: Cow<'static, [u8]>,
+}
+
+/// Where the frontend files come from.
+#[derive(Clone, Debug)]
+enum Source {
+    /// A frontend embedded at build time, [`EMBEDDED`] unless testing.
+    Embedded(&'static Bundle),
+    /// A frontend built into a directory, see [`ServeUI::from_dir`].
+    Dir(PathBuf),
+}
+
+impl Default for Source {
+    fn default() -> Self {
+        Self::Embedded(&EMBEDDED)
+    }
+}
+
+/// A utility to serve the frontend files, embedded or from [a directory](ServeUI::from_dir).
 #[derive(Clone, Debug, Default)]
-pub struct ServeUI;
+pub struct ServeUI {
+    #[allow(unused)]
+    /// may not be used in some conditional compilation
+    pub(crate) auth: Option<Auth>,
+    config: UiConfig,
+    source: Source,
+}
 
 impl ServeUI {
     /// Create a new `ServeUI` instance.
+    ///
+    /// It expects to be served at `/` and the API at `/api/v1`, see
+    /// [`public_url`](Self::public_url) and [`api_path`](Self::api_path).
     #[must_use]
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    /// Serve the frontend built into `dir` instead of the embedded one, e.g. a
+    /// themed build of `apalis-board-web`.
+    ///
+    /// Files are read again on every request and sent with `no-cache`, so the
+    /// page shows a rebuilt frontend once it is reloaded. Nothing watches `dir`
+    /// or reloads open pages. Paths leaving `dir` are not served.
+    #[must_use]
+    pub fn from_dir(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            source: Source::Dir(dir.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Tell the frontend where the API is mounted, `/api/v1` by default.
+    #[must_use]
+    pub fn api_path(mut self, path: impl Into<String>) -> Self {
+        self.config.api_path = path.into().trim_end_matches('/').to_owned();
+        self
+    }
+
+    /// Tell the frontend the path it is served at, `/` by default, e.g. `/admin/jobs`.
+    ///
+    /// Written into `index.html` along with the [`api_path`](Self::api_path),
+    /// so the prebuilt frontend works under any path.
+    #[must_use]
+    pub fn public_url(mut self, url: impl Into<String>) -> Self {
+        let url = url.into();
+        self.config.public_url = format!("{}/", url.trim_end_matches('/'));
+        self
+    }
+
+    /// Only serve the frontend to authenticated requests.
+    ///
+    /// Browsers cannot attach a bearer token when loading the page, so this is
+    /// meant for cookie based authenticators or [`BasicAuth`](crate::auth::BasicAuth)
+    /// with a realm. Otherwise leave the frontend open, it signs in on its own.
+    #[must_use]
+    pub fn authenticator(mut self, authenticator: impl Authenticator) -> Self {
+        self.auth = Some(Auth::new(authenticator));
+        self
     }
 
     /// Get an embedded file by URI path.
     #[must_use]
-    pub fn get_file(path: &str) -> Option<&File<'static>> {
+    pub fn get_file(path: &str) -> Option<&'static File<'static>> {
         let normalized = path.trim_start_matches('/');
 
         APP_DIST.get_file(normalized).or_else(|| {
@@ -31,16 +172,18 @@ impl ServeUI {
     /// Return a MIME type based on file extension.
     #[must_use]
     pub fn content_type(path: &str) -> &'static str {
-        if path.ends_with(".html") {
-            "text/html; charset=utf-8"
-        } else if path.ends_with(".js") {
-            "application/javascript"
-        } else if path.ends_with(".css") {
-            "text/css"
-        } else if path.ends_with(".wasm") {
-            "application/wasm"
-        } else {
-            "application/octet-stream"
+        let ext = path.rsplit_once('.').map_or("", |(_, ext)| ext);
+        match ext {
+            "html" => "text/html; charset=utf-8",
+            "js" | "mjs" => "application/javascript",
+            "css" => "text/css",
+            "wasm" => "application/wasm",
+            "svg" => "image/svg+xml",
+            "json" => "application/json",
+            "ico" => "image/x-icon",
+            "png" => "image/png",
+            "woff2" => "font/woff2",
+            _ => "application/octet-stream",
         }
     }
 
@@ -57,4 +200,370 @@ impl ServeUI {
             None
         }
     }
+
+    /// Answer a request for `path`, falling back to `index.html` for the
+    /// routes of the frontend, paths without an extension.
+    ///
+    /// Sends the brotli or gzip variant when `Accept-Encoding` allows it, and an
+    /// empty `304 Not Modified` when `If-None-Match` matches the file's `ETag`.
+    /// `index.html` gets the [`UiConfig`], under the `X-Forwarded-Prefix`.
+    pub(crate) async fn serve(&self, path: &str, req: &UiHeaders<'_>) -> Served {
+        let route = !path.rsplit('/').next().unwrap_or_default().contains('.');
+        let asset = match self.read(path).await {
+            Some(asset) => Some(asset),
+            None if route => self.read("index.html").await,
+            None => None,
+        };
+        let Some((path, contents, etag)) = asset else {
+            return Served {
+                status: 404,
+                headers: Vec::new(),
+                body: Cow::Borrowed(&[]),
+            };
+        };
+        let index = (path == "index.html")
+            .then(|| self.index(&contents, forwarded_prefix(req.forwarded_prefix)));
+        let etag = match &index {
+            Some(index) => crate::etag::etag(index),
+            None => etag,
+        };
+        let mut headers = vec![
+            ("content-type", Self::content_type(&path).to_owned()),
+            ("vary", "Accept-Encoding, X-Forwarded-Prefix".to_owned()),
+            ("etag", etag.clone()),
+        ];
+        // The files of a directory may change under the same name.
+        let cache = match self.source {
+            Source::Embedded(_) => Self::cache_control(&path),
+            Source::Dir(_) => Some("no-cache"),
+        };
+        if let Some(cache) = cache {
+            headers.push(("cache-control", cache.to_owned()));
+        }
+        if req
+            .if_none_match
+            .is_some_and(|tags| crate::etag::matches(tags, &etag))
+        {
+            return Served {
+                status: 304,
+                headers,
+                body: Cow::Borrowed(&[]),
+            };
+        }
+        if let Some(index) = index {
+            return Served {
+                status: 200,
+                headers,
+                body: Cow::Owned(index),
+            };
+        }
+        let compressed = match self.source {
+            Source::Embedded(bundle) => ENCODINGS
+                .iter()
+                .filter(|(encoding, _)| req.accept_encoding.is_some_and(|a| accepts(a, encoding)))
+                .find_map(|(encoding, ext)| {
+                    let file = bundle.compressed.get_file(format!("{path}.{ext}"))?;
+                    Some((*encoding, file.contents()))
+                }),
+            Source::Dir(_) => None,
+        };
+        let body = match compressed {
+            Some((encoding, contents)) => {
+                headers.push(("content-encoding", encoding.to_owned()));
+                Cow::Borrowed(contents)
+            }
+            None => contents,
+        };
+        Served {
+            status: 200,
+            headers,
+            body,
+        }
+    }
+
+    /// The path, contents and `ETag` of the file at the URI `path`, if it exists.
+    ///
+    /// Files of a directory are read and hashed on the blocking thread pool.
+    async fn read(&self, path: &str) -> Option<(String, Cow<'static, [u8]>, String)> {
+        match &self.source {
+            Source::Embedded(bundle) => {
+                let file = bundle.files.get_file(path.trim_start_matches('/'))?;
+                let path = file.path().to_str()?;
+                let etag = bundle.etag(path).unwrap_or_default().to_owned();
+                Some((path.to_owned(), Cow::Borrowed(file.contents()), etag))
+            }
+            Source::Dir(dir) => {
+                let (dir, path) = (dir.clone(), path.to_owned());
+                tokio::task::spawn_blocking(move || {
+                    let file = within(&dir, &path)?;
+                    let contents = fs::read(&file).ok()?;
+                    let etag = crate::etag::etag(&contents);
+                    let path = file.strip_prefix(&dir).ok()?.to_str()?.replace('\\', "/");
+                    Some((path, Cow::Owned(contents), etag))
+                })
+                .await
+                .ok()
+                .flatten()
+            }
+        }
+    }
+
+    /// `index.html` with a `<base>` at the public url and the [`UiConfig`],
+    /// both under `prefix`.
+    fn index(&self, contents: &[u8], prefix: &str) -> Vec<u8> {
+        let under_prefix = |path: &str| {
+            if path.starts_with('/') {
+                format!("{prefix}{path}")
+            } else {
+                path.to_owned()
+            }
+        };
+        let config = UiConfig {
+            api_path: under_prefix(&self.config.api_path),
+            public_url: under_prefix(&self.config.public_url),
+        };
+        // `<` can't appear in the JSON, so it can't close the script element.
+        let json = serde_json::to_string(&config)
+            .unwrap_or_default()
+            .replace('<', "\\u003c");
+        let head = format!(
+            r#"<base href="{}"><script id="{}" type="application/json">{json}</script>"#,
+            escape_attribute(&config.public_url),
+            UiConfig::ELEMENT_ID,
+        );
+
+        let mut html = String::from_utf8_lossy(contents).into_owned();
+        // Replace the `<base>` of the build
+        let base = html
+            .find("<base")
+            .and_then(|start| html[start..].find('>').map(|len| start..=start + len));
+        if let Some(base) = base {
+            html.replace_range(base, "");
+        }
+        let at = html
+            .find("<head")
+            .and_then(|start| html[start..].find('>').map(|end| start + end + 1))
+            .unwrap_or(0);
+        html.insert_str(at, &head);
+        html.into_bytes()
+    }
+}
+
+/// The file at the URI `path` under `dir`, unless the path leaves it.
+fn within(dir: &Path, path: &str) -> Option<PathBuf> {
+    let mut file = dir.to_path_buf();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => return None,
+            part if part.contains('\\') || part.contains(':') => return None,
+            part => file.push(part),
+        }
+    }
+    file.is_file().then_some(file)
+}
+
+/// The `X-Forwarded-Prefix` of a request without a trailing slash, if it is a plain path.
+fn forwarded_prefix(header: Option<&str>) -> &str {
+    let prefix = header
+        .and_then(|h| h.split(',').next())
+        .map_or("", |p| p.trim().trim_end_matches('/'));
+    let plain = prefix.starts_with('/')
+        && prefix
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-._~/%".contains(c));
+    if plain { prefix } else { "" }
+}
+
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+}
+
+/// Whether an `Accept-Encoding` header accepts `encoding`.
+fn accepts(accept_encoding: &str, encoding: &str) -> bool {
+    accept_encoding.split(',').any(|item| {
+        let mut parts = item.split(';').map(str::trim);
+        let name = parts.next().unwrap_or("");
+        let refused = parts.any(|p| {
+            p.strip_prefix("q=")
+                .and_then(|q| q.parse::<f32>().ok())
+                .is_some_and(|q| q <= 0.0)
+        });
+        (name.eq_ignore_ascii_case(encoding) || name == "*") && !refused
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header<'a>(served: &'a Served, name: &str) -> Option<&'a str> {
+        served
+            .headers
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn test_content_types_cover_the_bundle() {
+        assert_eq!(ServeUI::content_type("icons/logo.svg"), "image/svg+xml");
+        assert_eq!(ServeUI::content_type("fonts/inter.woff2"), "font/woff2");
+        assert_eq!(ServeUI::content_type("favicon.ico"), "image/x-icon");
+        assert_eq!(ServeUI::content_type("manifest.json"), "application/json");
+        assert_eq!(ServeUI::content_type("LICENSE"), "application/octet-stream");
+    }
+
+    #[test]
+    fn test_accept_encoding_is_negotiated() {
+        assert!(accepts("gzip, deflate, br", "br"));
+        assert!(accepts("GZIP;q=0.5", "gzip"));
+        assert!(accepts("*", "br"));
+        assert!(!accepts("gzip, br;q=0", "br"));
+        assert!(!accepts("identity", "gzip"));
+    }
+
+    #[tokio::test]
+    async fn test_files_are_served_with_etags() {
+        let ui = ServeUI::new();
+        let identity = UiHeaders {
+            accept_encoding: Some("identity"),
+            ..UiHeaders::default()
+        };
+        assert_eq!(ui.serve("/favicon.ico", &identity).await.status, 404);
+        let served = ui.serve("/queues/emails", &identity).await;
+        assert_eq!(served.status, 200);
+        assert_eq!(
+            header(&served, "content-type"),
+            Some("text/html; charset=utf-8")
+        );
+        assert_eq!(header(&served, "content-encoding"), None);
+
+        let etag = header(&served, "etag").unwrap().to_owned();
+        assert!(etag.starts_with("W/"));
+        let revalidate = UiHeaders {
+            if_none_match: Some(&etag),
+            ..UiHeaders::default()
+        };
+        let served = ui.serve("/", &revalidate).await;
+        assert_eq!(served.status, 304);
+        assert!(served.body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_assets_are_served_precompressed() {
+        use std::io::{Read, Write};
+
+        use include_dir::DirEntry;
+
+        fn leak<T>(value: T) -> &'static T {
+            Box::leak(Box::new(value))
+        }
+        let js = "export function greet(name) { return `Hello, ${name}!`; }\n".repeat(64);
+        let mut br = Vec::new();
+        brotli::CompressorWriter::new(&mut br, 4096, 9, 22)
+            .write_all(js.as_bytes())
+            .unwrap();
+        let files = leak([DirEntry::File(File::new("app.js", leak(js).as_bytes()))]);
+        let compressed = leak([DirEntry::File(File::new("app.js.br", leak(br)))]);
+        let ui = ServeUI {
+            source: Source::Embedded(leak(Bundle {
+                files: leak(Dir::new("", files)),
+                compressed: leak(Dir::new("", compressed)),
+                etags: OnceLock::new(),
+            })),
+            ..ServeUI::new()
+        };
+        let get = async |accept_encoding| {
+            let req = UiHeaders {
+                accept_encoding,
+                ..UiHeaders::default()
+            };
+            ui.serve("/app.js", &req).await
+        };
+
+        let plain = get(Some("gzip")).await;
+        assert_eq!(header(&plain, "content-encoding"), None);
+        assert_eq!(
+            header(&plain, "content-type"),
+            Some("application/javascript")
+        );
+        let served = get(Some("gzip, deflate, br")).await;
+        assert_eq!(served.status, 200);
+        assert_eq!(header(&served, "content-encoding"), Some("br"));
+        assert_eq!(header(&served, "etag"), header(&plain, "etag"));
+        assert!(served.body.len() < plain.body.len());
+        let mut decoded = Vec::new();
+        brotli::Decompressor::new(&*served.body, 4096)
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, plain.body.as_ref());
+    }
+
+    #[tokio::test]
+    async fn test_index_carries_the_runtime_config() {
+        let ui = ServeUI::new()
+            .public_url("/admin/jobs")
+            .api_path("/admin/api/");
+        let html = async |prefix| {
+            let req = UiHeaders {
+                forwarded_prefix: prefix,
+                ..UiHeaders::default()
+            };
+            let served = ui.serve("/queues/emails", &req).await;
+            String::from_utf8(served.body.into_owned()).unwrap()
+        };
+
+        let page = html(None).await;
+        assert!(page.contains(r#"<base href="/admin/jobs/">"#));
+        assert!(!page.contains(r#"<base href="/">"#));
+        assert!(page.contains(r#"{"api_path":"/admin/api","public_url":"/admin/jobs/"}"#));
+
+        let page = html(Some("/board/")).await;
+        assert!(page.contains(r#"<base href="/board/admin/jobs/">"#));
+        assert!(page.contains(r#""api_path":"/board/admin/api""#));
+        assert_eq!(html(Some(r#"/"><script>"#)).await, html(None).await);
+    }
+
+    #[tokio::test]
+    async fn test_dir_is_read_on_every_request() {
+        let dir = std::env::temp_dir().join(format!("apalis-board-ui-{}", std::process::id()));
+        fs::create_dir_all(dir.join("assets")).unwrap();
+        fs::write(dir.join("index.html"), "<html><head></head></html>").unwrap();
+        fs::write(dir.join("assets/app.js"), "console.log(1)").unwrap();
+        let ui = ServeUI::from_dir(&dir);
+        let get = async |path| ui.serve(path, &UiHeaders::default()).await;
+
+        let served = get("/assets/app.js").await;
+        assert_eq!(&*served.body, b"console.log(1)");
+        assert_eq!(
+            header(&served, "content-type"),
+            Some("application/javascript")
+        );
+        assert_eq!(header(&served, "cache-control"), Some("no-cache"));
+        let etag = header(&served, "etag").unwrap().to_owned();
+
+        fs::write(dir.join("assets/app.js"), "console.log(2)").unwrap();
+        let served = get("/assets/app.js").await;
+        assert_eq!(&*served.body, b"console.log(2)");
+        assert_ne!(header(&served, "etag"), Some(etag.as_str()));
+
+        for path in ["/missing.js", "/assets/missing.css", "/../Cargo.toml"] {
+            let served = get(path).await;
+            assert_eq!(served.status, 404);
+            assert!(served.body.is_empty());
+        }
+        for path in ["/workers", "/queues/emails.v2/tasks", "/assets/../../etc"] {
+            let served = get(path).await;
+            assert_eq!(
+                header(&served, "content-type"),
+                Some("text/html; charset=utf-8")
+            );
+            assert!(String::from_utf8_lossy(&served.body).contains(UiConfig::ELEMENT_ID));
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
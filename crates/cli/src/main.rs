@@ -0,0 +1,465 @@
+//! Operate the queues of an apalis board from the terminal.
+//!
+//! ```sh
+//! export APALIS_BOARD_URL=https://board.example.com/api/v1
+//! apalis-board-cli queues
+//! apalis-board-cli tasks list --queue emails --status failed
+//! apalis-board-cli task show --queue emails 01JABCDEFGHJKMNPQRSTVWXYZ0
+//! apalis-board-cli task retry --queue emails 01JABCDEFGHJKMNPQRSTVWXYZ0
+//! apalis-board-cli push --queue emails --json '{"to":"ada@example.com"}'
+//! apalis-board-cli -o json workers | jq '.[].id'
+//! ```
+
+use std::{io::Read, process::ExitCode};
+
+use apalis_board_client::{Client, RawTask};
+use apalis_board_types::{BackendQuery, LogEntry, Tagged};
+use apalis_core::{
+    backend::{Filter, QueueInfo, RunningWorker},
+    task::status::Status,
+};
+use clap::{Parser, Subcommand};
+use futures::StreamExt;
+use serde_json::Value;
+
+use crate::output::{Format, Table, ago, now, truncate};
+
+mod output;
+
+/// Operate the queues of an apalis board from the terminal.
+#[derive(Debug, Parser)]
+#[command(name = "apalis-board-cli", version)]
+struct Cli {
+    /// The URL of the board's API.
+    #[arg(
+        long,
+        env = "APALIS_BOARD_URL",
+        default_value = "http://localhost:8000/api/v1",
+        global = true
+    )]
+    url: String,
+
+    /// An API token to authenticate with.
+    #[arg(
+        long,
+        env = "APALIS_BOARD_TOKEN",
+        hide_env_values = true,
+        global = true
+    )]
+    token: Option<String>,
+
+    /// How to print the results.
+    #[arg(short, long, value_enum, default_value_t = Format::Table, global = true)]
+    output: Format,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// List the queues and their statistics.
+    Queues {
+        /// Only list the queues of this backend.
+        #[arg(long)]
+        backend: Option<String>,
+    },
+    /// List tasks.
+    Tasks {
+        #[command(subcommand)]
+        command: TasksCommand,
+    },
+    /// Inspect or retry a task.
+    Task {
+        #[command(subcommand)]
+        command: TaskCommand,
+    },
+    /// Push a task to a queue.
+    Push {
+        /// The queue to push to.
+        #[arg(long)]
+        queue: String,
+        /// The arguments of the task, `-` to read them from stdin.
+        #[arg(long, value_name = "JSON")]
+        json: String,
+    },
+    /// List the workers and how long ago they were last seen.
+    Workers {
+        /// Only list the workers of this queue.
+        #[arg(long)]
+        queue: Option<String>,
+        /// Only list the workers of this backend.
+        #[arg(long, conflicts_with = "queue")]
+        backend: Option<String>,
+    },
+    /// Follow the logs of the tasks.
+    Logs {
+        #[command(subcommand)]
+        command: LogsCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum TasksCommand {
+    /// List the tasks of a queue, or of every queue.
+    List {
+        /// Only list the tasks of this queue.
+        #[arg(long)]
+        queue: Option<String>,
+        /// Only list the tasks with this status, e.g. `failed`.
+        #[arg(long, value_parser = parse_status)]
+        status: Option<Status>,
+        /// Only list the tasks of this backend.
+        #[arg(long, conflicts_with = "queue")]
+        backend: Option<String>,
+        /// The page to list, from 1.
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+        page: u32,
+        /// How many tasks a page holds.
+        #[arg(long, default_value_t = 20)]
+        page_size: u32,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum TaskCommand {
+    /// Show a task with its arguments and context.
+    Show {
+        /// The queue of the task.
+        #[arg(long)]
+        queue: String,
+        /// The id of the task.
+        id: String,
+    },
+    /// Run a failed or killed task again as a new task, keeping the original.
+    Retry {
+        /// The queue of the task.
+        #[arg(long)]
+        queue: String,
+        /// The id of the task.
+        id: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum LogsCommand {
+    /// Print the log entries as they come, until interrupted.
+    Tail {
+        /// Only print the entries of this task.
+        #[arg(long)]
+        task: Option<String>,
+    },
+}
+
+/// Parse a task status regardless of its case, e.g. `failed`.
+fn parse_status(s: &str) -> Result<Status, String> {
+    let mut chars = s.chars();
+    let capitalized = match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    };
+    capitalized.parse().map_err(|e| format!("{e}"))
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(Cli::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = Client::new(&cli.url);
+    if let Some(token) = &cli.token {
+        client = client.bearer_token(token);
+    }
+    let format = cli.output;
+    match cli.command {
+        Command::Queues { backend } => {
+            let queues = client.queues(&BackendQuery { backend }).await?;
+            print_queues(format, &queues)?;
+        }
+        Command::Tasks {
+            command:
+                TasksCommand::List {
+                    queue,
+                    status,
+                    backend,
+                    page,
+                    page_size,
+                },
+        } => {
+            let filter = Filter {
+                status,
+                page,
+                page_size: Some(page_size),
+            };
+            let tasks = match &queue {
+                Some(queue) => client
+                    .queue_tasks(queue, &filter)
+                    .await?
+                    .into_iter()
+                    .map(|item| Tagged {
+                        backend: String::new(),
+                        item,
+                    })
+                    .collect(),
+                None => client.tasks(&filter, &BackendQuery { backend }).await?,
+            };
+            print_tasks(format, queue.as_deref(), &tasks)?;
+        }
+        Command::Task {
+            command: TaskCommand::Show { queue, id },
+        } => {
+            let task = client.task(&queue, &id).await?;
+            print_task(format, &queue, &task)?;
+        }
+        Command::Task {
+            command: TaskCommand::Retry { queue, id },
+        } => {
+            let retried = client.retry(&queue, &id).await?;
+            match retried.task_id {
+                Some(new) => eprintln!("Retrying task {id} of `{queue}` as {new}"),
+                None => eprintln!("Retrying task {id} of `{queue}`"),
+            }
+        }
+        Command::Push { queue, json } => {
+            let args: Value = if json == "-" {
+                let mut text = String::new();
+                std::io::stdin().read_to_string(&mut text)?;
+                serde_json::from_str(&text)?
+            } else {
+                serde_json::from_str(&json)?
+            };
+            client.push(&queue, &args).await?;
+            eprintln!("Pushed a task to `{queue}`");
+        }
+        Command::Workers { queue, backend } => {
+            let workers = match &queue {
+                Some(queue) => client.queue_workers(queue).await?,
+                None => client.workers(&BackendQuery { backend }).await?,
+            };
+            print_workers(format, &workers)?;
+        }
+        Command::Logs {
+            command: LogsCommand::Tail { task },
+        } => {
+            let mut events = client.events().await?;
+            while let Some(entry) = events.next().await {
+                if task
+                    .as_ref()
+                    .is_none_or(|id| entry.span.as_ref().is_some_and(|span| &span.task_id == id))
+                {
+                    print_entry(format, &entry)?;
+                }
+            }
+            return Err("The board closed the event stream".into());
+        }
+    }
+    Ok(())
+}
+
+/// The headline statistics of a queue, e.g. `PENDING_JOBS` shown as `PENDING`.
+fn headline(queue: &QueueInfo) -> impl Iterator<Item = (String, &str)> {
+    queue
+        .stats
+        .iter()
+        .filter(|stat| stat.priority == Some(1))
+        .map(|stat| {
+            let title = stat.title.strip_suffix("_JOBS").unwrap_or(&stat.title);
+            (title.to_owned(), stat.value.as_str())
+        })
+}
+
+fn print_queues(format: Format, queues: &[Tagged<QueueInfo>]) -> Result<(), serde_json::Error> {
+    if format == Format::Json {
+        return output::json(queues);
+    }
+    let mut stats: Vec<String> = Vec::new();
+    for (title, _) in queues.iter().flat_map(|q| headline(&q.item)) {
+        if !stats.contains(&title) {
+            stats.push(title);
+        }
+    }
+    let mut table = Table::new(
+        ["QUEUE", "BACKEND", "WORKERS"]
+            .map(str::to_owned)
+            .into_iter()
+            .chain(stats.iter().cloned()),
+    );
+    for queue in queues {
+        let values: Vec<_> = headline(&queue.item).collect();
+        let mut row = vec![
+            queue.item.name.clone(),
+            queue.backend.clone(),
+            queue.item.workers.len().to_string(),
+        ];
+        row.extend(stats.iter().map(|title| {
+            values
+                .iter()
+                .find(|(t, _)| t == title)
+                .map_or_else(String::new, |(_, v)| (*v).to_owned())
+        }));
+        table.row(row);
+    }
+    table.print();
+    Ok(())
+}
+
+fn print_tasks(
+    format: Format,
+    queue: Option<&str>,
+    tasks: &[Tagged<RawTask>],
+) -> Result<(), serde_json::Error> {
+    if format == Format::Json {
+        return match queue {
+            Some(_) => output::json(&tasks.iter().map(|t| &t.item).collect::<Vec<_>>()),
+            None => output::json(tasks),
+        };
+    }
+    let now = now();
+    let mut table = Table::new(["ID", "QUEUE", "STATUS", "ATTEMPTS", "RUN AT", "ARGS"]);
+    for Tagged { item: task, .. } in tasks {
+        let queue = queue
+            .or_else(|| task.parts.ctx.get("queue").and_then(Value::as_str))
+            .unwrap_or_default();
+        table.row(vec![
+            task_id(task),
+            queue.to_owned(),
+            task.parts.status.load().to_string(),
+            task.parts.attempt.current().to_string(),
+            ago(now, task.parts.run_at),
+            truncate(&task.args.to_string(), 60),
+        ]);
+    }
+    table.print();
+    Ok(())
+}
+
+fn print_task(format: Format, queue: &str, task: &RawTask) -> Result<(), serde_json::Error> {
+    if format == Format::Json {
+        return output::json(task);
+    }
+    for (key, value) in [
+        ("ID", task_id(task)),
+        ("QUEUE", queue.to_owned()),
+        ("STATUS", task.parts.status.load().to_string()),
+        ("ATTEMPTS", task.parts.attempt.current().to_string()),
+        ("RUN AT", ago(now(), task.parts.run_at)),
+    ] {
+        println!("{key:8}  {value}");
+    }
+    println!("\nARGS\n{}", serde_json::to_string_pretty(&task.args)?);
+    println!(
+        "\nCONTEXT\n{}",
+        serde_json::to_string_pretty(&task.parts.ctx)?
+    );
+    Ok(())
+}
+
+fn print_workers(format: Format, workers: &[RunningWorker]) -> Result<(), serde_json::Error> {
+    if format == Format::Json {
+        return output::json(workers);
+    }
+    let now = now();
+    let mut table = Table::new(["ID", "QUEUE", "BACKEND", "STARTED", "LAST SEEN"]);
+    for worker in workers {
+        table.row(vec![
+            worker.id.clone(),
+            worker.queue.clone(),
+            worker.backend.clone(),
+            ago(now, worker.started_at),
+            ago(now, worker.last_heartbeat),
+        ]);
+    }
+    table.print();
+    Ok(())
+}
+
+fn print_entry(format: Format, entry: &LogEntry) -> Result<(), serde_json::Error> {
+    if format == Format::Json {
+        println!("{}", serde_json::to_string(entry)?);
+        return Ok(());
+    }
+    let task = entry.span.as_ref().map_or("", |span| span.task_id.as_str());
+    let message = match (&entry.entry.message, &entry.entry.entry_type) {
+        (Some(message), _) => message.clone(),
+        (None, Some(apalis_board_types::EntryType::Result(result))) => {
+            format!("{} in {}", result.result, result.done_in)
+        }
+        _ => String::new(),
+    };
+    println!(
+        "{} {:5} {task} {message}",
+        entry.timestamp,
+        format!("{:?}", entry.level).to_uppercase()
+    );
+    Ok(())
+}
+
+fn task_id(task: &RawTask) -> String {
+    task.parts
+        .task_id
+        .as_ref()
+        .map(ToString::to_string)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn test_commands_parse() {
+        Cli::command().debug_assert();
+        let cli = Cli::try_parse_from([
+            "apalis-board-cli",
+            "tasks",
+            "list",
+            "--queue",
+            "emails",
+            "--status",
+            "failed",
+            "-o",
+            "json",
+        ])
+        .unwrap();
+        assert_eq!(cli.output, Format::Json);
+        assert!(matches!(
+            cli.command,
+            Command::Tasks {
+                command: TasksCommand::List {
+                    status: Some(Status::Failed),
+                    page: 1,
+                    ..
+                }
+            }
+        ));
+        assert!(parse_status("unknown").is_err());
+        let cli = Cli::try_parse_from([
+            "apalis-board-cli",
+            "task",
+            "retry",
+            "--queue",
+            "emails",
+            "01J",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Command::Task {
+                command: TaskCommand::Retry { .. }
+            }
+        ));
+        assert!(Cli::try_parse_from(["apalis-board-cli", "tasks", "list", "--page", "0"]).is_err());
+    }
+}
@@ -0,0 +1,133 @@
+//! Printing results as aligned tables or JSON.
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// How results are printed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub(crate) enum Format {
+    /// Aligned columns for people.
+    Table,
+    /// The JSON returned by the board, for scripts.
+    Json,
+}
+
+/// Print `value` as pretty JSON.
+pub(crate) fn json<T: Serialize + ?Sized>(value: &T) -> Result<(), serde_json::Error> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Rows of cells printed under aligned headers.
+#[derive(Debug)]
+pub(crate) struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// A table with the columns `headers`.
+    pub(crate) fn new<I, S>(headers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            headers: headers.into_iter().map(Into::into).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Add a row, one cell per column.
+    pub(crate) fn row(&mut self, cells: Vec<String>) {
+        self.rows.push(cells);
+    }
+
+    /// Print the table.
+    pub(crate) fn print(&self) {
+        print!("{}", self.render());
+    }
+
+    /// The lines of the table, the columns separated by two spaces.
+    fn render(&self) -> String {
+        let width = |cell: &String| cell.chars().count();
+        let mut widths: Vec<usize> = self.headers.iter().map(width).collect();
+        for row in &self.rows {
+            for (w, cell) in widths.iter_mut().zip(row) {
+                *w = (*w).max(width(cell));
+            }
+        }
+
+        let mut out = String::new();
+        for row in std::iter::once(&self.headers).chain(&self.rows) {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, w)| format!("{cell:w$}"))
+                .collect::<Vec<_>>()
+                .join("  ");
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// How far `secs` is from `now`, both in seconds since the Unix epoch, e.g. `5m ago`.
+pub(crate) fn ago(now: u64, secs: u64) -> String {
+    if secs > now {
+        return format!("in {}", duration(secs - now));
+    }
+    format!("{} ago", duration(now - secs))
+}
+
+/// `secs` in its largest unit, e.g. `3h`.
+pub(crate) fn duration(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3_600 => format!("{}m", secs / 60),
+        3_600..86_400 => format!("{}h", secs / 3_600),
+        _ => format!("{}d", secs / 86_400),
+    }
+}
+
+/// `text` cut to `max` characters, ending with `…` when cut.
+pub(crate) fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_owned();
+    }
+    let mut cut: String = text.chars().take(max.saturating_sub(1)).collect();
+    cut.push('…');
+    cut
+}
+
+/// The current time in seconds since the Unix epoch.
+pub(crate) fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_columns_are_aligned() {
+        let mut table = Table::new(["QUEUE", "PENDING"]);
+        table.row(vec!["emails".to_owned(), "3".to_owned()]);
+        table.row(vec!["sms".to_owned(), "12".to_owned()]);
+        assert_eq!(table.render(), "QUEUE   PENDING\nemails  3\nsms     12\n");
+    }
+
+    #[test]
+    fn test_times_are_relative() {
+        assert_eq!(ago(1_000, 995), "5s ago");
+        assert_eq!(ago(10_000, 2_800), "2h ago");
+        assert_eq!(ago(1_000, 1_120), "in 2m");
+        assert_eq!(
+            truncate("{\"to\":\"ada@example.com\"}", 10),
+            "{\"to\":\"ad…"
+        );
+    }
+}
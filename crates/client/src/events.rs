@@ -0,0 +1,106 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use apalis_board_types::LogEntry;
+use futures::{Stream, StreamExt, stream::BoxStream};
+use reqwest::Response;
+
+/// The log entries streamed by `/events`, see [`Client::events`](crate::Client::events).
+///
+/// Entries that can't be parsed are skipped, the stream ends with the connection.
+pub struct Events(BoxStream<'static, LogEntry>);
+
+impl fmt::Debug for Events {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Events").finish_non_exhaustive()
+    }
+}
+
+impl Events {
+    /// Parse the server-sent events of `resp`.
+    pub(crate) fn new(resp: Response) -> Self {
+        let state = (
+            resp.bytes_stream().boxed(),
+            Parser::default(),
+            VecDeque::new(),
+        );
+        let entries = futures::stream::unfold(state, |(mut body, mut parser, mut ready)| async {
+            loop {
+                if let Some(entry) = ready.pop_front() {
+                    return Some((entry, (body, parser, ready)));
+                }
+                let chunk = body.next().await?.ok()?;
+                ready.extend(
+                    parser
+                        .feed(&chunk)
+                        .iter()
+                        .filter_map(|data| serde_json::from_str::<LogEntry>(data).ok()),
+                );
+            }
+        });
+        Self(entries.boxed())
+    }
+}
+
+impl Stream for Events {
+    type Item = LogEntry;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.0.poll_next_unpin(cx)
+    }
+}
+
+/// Splits a `text/event-stream` body into the data of its events.
+#[derive(Debug, Default)]
+struct Parser {
+    /// The start of a line not received in full yet.
+    line: Vec<u8>,
+    /// The `data` lines of the event being received.
+    data: Vec<String>,
+}
+
+impl Parser {
+    /// Take in `chunk`, returning the data of the events it completes.
+    fn feed(&mut self, chunk: &[u8]) -> Vec<String> {
+        let mut events = Vec::new();
+        for &byte in chunk {
+            if byte != b'\n' {
+                self.line.push(byte);
+                continue;
+            }
+            let mut line = String::from_utf8_lossy(&self.line).into_owned();
+            self.line.clear();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+            if line.is_empty() {
+                if !self.data.is_empty() {
+                    events.push(self.data.join("\n"));
+                    self.data.clear();
+                }
+            } else if let Some(data) = line.strip_prefix("data:") {
+                self.data
+                    .push(data.strip_prefix(' ').unwrap_or(data).to_owned());
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_are_split_across_chunks() {
+        let mut parser = Parser::default();
+        assert!(parser.feed(b": keep-alive-text\n\nda").is_empty());
+        assert!(parser.feed(b"ta: {\"a\":\r\n").is_empty());
+        assert_eq!(parser.feed(b"data:1}\r\n\r\ndata: 2\n"), ["{\"a\":\n1}"]);
+        assert_eq!(parser.feed(b"\n"), ["2"]);
+    }
+}
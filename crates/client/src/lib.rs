@@ -0,0 +1,410 @@
+#![doc = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/README.md"))]
+use apalis_board_types::{
+    ApiError, ApiToken, AuditQuery, AuditRecord, BackendQuery, BoardConfig, CreatedToken, NewToken,
+    RetriedTask, Tagged, User,
+};
+use apalis_core::{
+    backend::{Filter, QueueInfo, RunningWorker, Statistic},
+    task::Task,
+};
+use reqwest::{Method, RequestBuilder, Response};
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
+
+/// Follow the log entries streamed by the board.
+mod events;
+
+pub use events::Events;
+
+/// A task as served by the board, with its arguments and context as JSON.
+pub type RawTask = Task<Value, Value, String>;
+
+/// Why a request to the board failed.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The board refused the request, e.g. with [`ApiError::NotFound`].
+    #[error(transparent)]
+    Api(#[from] ApiError),
+    /// The board answered with an error that isn't an [`ApiError`], e.g. from a proxy.
+    #[error("Unexpected response {status}: {body}")]
+    Status {
+        /// The HTTP status code.
+        status: u16,
+        /// The body of the response.
+        body: String,
+    },
+    /// The board couldn't be reached or its response couldn't be read.
+    #[error("Request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    /// The response isn't what the route returns.
+    #[error("Invalid response: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// How requests are authenticated.
+#[derive(Clone)]
+enum Credentials {
+    Bearer(String),
+    Basic(String, String),
+}
+
+/// A typed async client for the routes of a board.
+///
+/// ```rust,ignore
+/// let client = Client::new("http://localhost:8000/api/v1").bearer_token(token);
+/// let queues = client.queues(&BackendQuery::default()).await?;
+/// client.push("emails", &email).await?;
+/// ```
+///
+/// The client is cheap to clone, clones share their connections.
+#[derive(Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base: String,
+    credentials: Option<Credentials>,
+}
+
+impl std::fmt::Debug for Client {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Client")
+            .field("base", &self.base)
+            .field("authenticated", &self.credentials.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Client {
+    /// Call the API mounted at `base`, e.g. `http://localhost:8000/api/v1`.
+    #[must_use]
+    pub fn new(base: impl Into<String>) -> Self {
+        Self::with_http_client(base, reqwest::Client::new())
+    }
+
+    /// Call the API mounted at `base` through `http`, e.g. to set timeouts or a proxy.
+    #[must_use]
+    pub fn with_http_client(base: impl Into<String>, http: reqwest::Client) -> Self {
+        Self {
+            http,
+            base: base.into().trim_end_matches('/').to_owned(),
+            credentials: None,
+        }
+    }
+
+    /// Authenticate as the holder of an API token, or any bearer token.
+    #[must_use]
+    pub fn bearer_token(mut self, token: impl Into<String>) -> Self {
+        self.credentials = Some(Credentials::Bearer(token.into()));
+        self
+    }
+
+    /// Authenticate with HTTP basic authentication.
+    #[must_use]
+    pub fn basic_auth(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.credentials = Some(Credentials::Basic(username.into(), password.into()));
+        self
+    }
+
+    /// The URL of the API.
+    #[must_use]
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+
+    /// How the board is set up, see `/config`.
+    pub async fn config(&self) -> Result<BoardConfig, Error> {
+        Self::json(self.request(Method::GET, "/config")).await
+    }
+
+    /// The user the client is authenticated as, see `/me`.
+    pub async fn me(&self) -> Result<User, Error> {
+        Self::json(self.request(Method::GET, "/me")).await
+    }
+
+    /// The names of the registered backends, see `/backends`.
+    pub async fn backends(&self) -> Result<Vec<String>, Error> {
+        Self::json(self.request(Method::GET, "/backends")).await
+    }
+
+    /// The queues of the backends selected by `query`, see `/queues`.
+    pub async fn queues(&self, query: &BackendQuery) -> Result<Vec<Tagged<QueueInfo>>, Error> {
+        Self::json(self.request(Method::GET, "/queues").query(query)).await
+    }
+
+    /// The tasks of every queue passing `filter`, see `/tasks`.
+    pub async fn tasks(
+        &self,
+        filter: &Filter,
+        query: &BackendQuery,
+    ) -> Result<Vec<Tagged<RawTask>>, Error> {
+        Self::json(
+            self.request(Method::GET, "/tasks")
+                .query(filter)
+                .query(query),
+        )
+        .await
+    }
+
+    /// The workers of every queue, see `/workers`.
+    pub async fn workers(&self, query: &BackendQuery) -> Result<Vec<RunningWorker>, Error> {
+        Self::json(self.request(Method::GET, "/workers").query(query)).await
+    }
+
+    /// The statistics of the backends selected by `query`, see `/overview`.
+    pub async fn overview(&self, query: &BackendQuery) -> Result<Vec<Statistic>, Error> {
+        Self::json(self.request(Method::GET, "/overview").query(query)).await
+    }
+
+    /// The tasks of `queue` passing `filter`.
+    pub async fn queue_tasks(&self, queue: &str, filter: &Filter) -> Result<Vec<RawTask>, Error> {
+        let path = format!("/queues/{}/tasks", segment(queue));
+        Self::json(self.request(Method::GET, &path).query(filter)).await
+    }
+
+    /// The task of `queue` with the id `task_id`.
+    pub async fn task(&self, queue: &str, task_id: &str) -> Result<RawTask, Error> {
+        let path = format!("/queues/{}/tasks/{}", segment(queue), segment(task_id));
+        Self::json(self.request(Method::GET, &path)).await
+    }
+
+    /// Push a task with the arguments `args` to `queue`.
+    pub async fn push<T: Serialize + ?Sized>(&self, queue: &str, args: &T) -> Result<(), Error> {
+        let path = format!("/queues/{}/tasks", segment(queue));
+        Self::send(self.request(Method::PUT, &path).json(args)).await?;
+        Ok(())
+    }
+
+    /// Run the failed or killed task `task_id` of `queue` again, as a new task.
+    pub async fn retry(&self, queue: &str, task_id: &str) -> Result<RetriedTask, Error> {
+        let path = format!(
+            "/queues/{}/tasks/{}/retry",
+            segment(queue),
+            segment(task_id)
+        );
+        Self::json(self.request(Method::POST, &path)).await
+    }
+
+    /// The statistics of `queue`.
+    pub async fn stats(&self, queue: &str) -> Result<Vec<Statistic>, Error> {
+        let path = format!("/queues/{}/stats", segment(queue));
+        Self::json(self.request(Method::GET, &path)).await
+    }
+
+    /// The workers of `queue`.
+    pub async fn queue_workers(&self, queue: &str) -> Result<Vec<RunningWorker>, Error> {
+        let path = format!("/queues/{}/workers", segment(queue));
+        Self::json(self.request(Method::GET, &path)).await
+    }
+
+    /// The JSON schema of the arguments of `queue`.
+    pub async fn schema(&self, queue: &str) -> Result<Value, Error> {
+        let path = format!("/queues/{}/schema", segment(queue));
+        Self::json(self.request(Method::GET, &path)).await
+    }
+
+    /// The changes made through the board matching `query`, see `/audit`.
+    pub async fn audit(&self, query: &AuditQuery) -> Result<Vec<AuditRecord>, Error> {
+        Self::json(self.request(Method::GET, "/audit").query(query)).await
+    }
+
+    /// The API tokens, see `/admin/tokens`.
+    pub async fn tokens(&self) -> Result<Vec<ApiToken>, Error> {
+        Self::json(self.request(Method::GET, "/admin/tokens")).await
+    }
+
+    /// Mint an API token, the only time its secret is returned.
+    pub async fn create_token(&self, token: &NewToken) -> Result<CreatedToken, Error> {
+        Self::json(self.request(Method::POST, "/admin/tokens").json(token)).await
+    }
+
+    /// Revoke the API token `id`.
+    pub async fn revoke_token(&self, id: &str) -> Result<(), Error> {
+        let path = format!("/admin/tokens/{}", segment(id));
+        Self::send(self.request(Method::DELETE, &path)).await?;
+        Ok(())
+    }
+
+    /// The statistics in the Prometheus text format, see `/metrics`.
+    pub async fn metrics(&self) -> Result<String, Error> {
+        Ok(Self::send(self.request(Method::GET, "/metrics"))
+            .await?
+            .text()
+            .await?)
+    }
+
+    /// The OpenAPI document of the board, see `/openapi.json`.
+    pub async fn openapi(&self) -> Result<Value, Error> {
+        Self::json(self.request(Method::GET, "/openapi.json")).await
+    }
+
+    /// Follow the log entries of the tasks, see `/events`.
+    ///
+    /// The stream ends when the connection to the board is lost.
+    pub async fn events(&self) -> Result<Events, Error> {
+        let builder = self
+            .request(Method::GET, "/events")
+            .header("Accept", "text/event-stream");
+        Ok(Events::new(Self::send(builder).await?))
+    }
+
+    /// A request to `path`, relative to the API, with the credentials attached.
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let builder = self.http.request(method, format!("{}{path}", self.base));
+        match &self.credentials {
+            Some(Credentials::Bearer(token)) => builder.bearer_auth(token),
+            Some(Credentials::Basic(username, password)) => {
+                builder.basic_auth(username, Some(password))
+            }
+            None => builder,
+        }
+    }
+
+    /// Send a request, turning unsuccessful responses into an [`Error`].
+    async fn send(builder: RequestBuilder) -> Result<Response, Error> {
+        let resp = builder.send().await?;
+        if resp.status().is_success() {
+            return Ok(resp);
+        }
+        let status = resp.status().as_u16();
+        let body = resp.text().await?;
+        Err(match serde_json::from_str::<ApiError>(&body) {
+            Ok(err) => Error::Api(err),
+            Err(_) => Error::Status { status, body },
+        })
+    }
+
+    /// Send a request and parse the JSON it is answered with.
+    async fn json<T: DeserializeOwned>(builder: RequestBuilder) -> Result<T, Error> {
+        let body = Self::send(builder).await?.bytes().await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+}
+
+/// Percent-encode `value` to be used as a path segment.
+fn segment(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(char::from(byte));
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use apalis_board_api::{
+        framework::{ApiBuilder, RegisterRoute},
+        sse::TracingBroadcaster,
+    };
+    use apalis_board_types::{Entry, LogEntry, LogLevel, Span};
+    use apalis_sqlite::{PoolOptions, SqliteStorage};
+    use axum::{Extension, Router};
+    use futures::StreamExt;
+
+    use super::*;
+
+    /// A client for a board serving an `emails` queue on a random port.
+    async fn serve() -> (Client, Arc<Mutex<TracingBroadcaster>>) {
+        // A single connection, every connection to `:memory:` opens a new database
+        let pool = PoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        SqliteStorage::setup(&pool).await.unwrap();
+        let storage = SqliteStorage::<String, _, _>::new_in_queue(&pool, "emails");
+        let broadcaster = TracingBroadcaster::create();
+        let api = ApiBuilder::new(Router::new()).register(storage).build();
+        let router = Router::new()
+            .nest("/api/v1", api)
+            .layer(Extension(broadcaster.clone()));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        (Client::new(format!("http://{addr}/api/v1/")), broadcaster)
+    }
+
+    fn filter() -> Filter {
+        Filter {
+            status: None,
+            page: 1,
+            page_size: Some(10),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_routes_are_typed() {
+        let (client, _) = serve().await;
+        let all = BackendQuery::default();
+        client.push("emails", "ada@example.com").await.unwrap();
+
+        assert!(!client.config().await.unwrap().read_only);
+        assert_eq!(client.backends().await.unwrap(), ["SqliteStorage"]);
+        let queues = client.queues(&all).await.unwrap();
+        assert_eq!(queues[0].item.name, "emails");
+
+        let tasks = client.queue_tasks("emails", &filter()).await.unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].args, "ada@example.com");
+        let id = tasks[0].parts.task_id.as_ref().unwrap().to_string();
+        let task = client.task("emails", &id).await.unwrap();
+        assert_eq!(task.args, "ada@example.com");
+        // Only failed and killed tasks run again
+        assert!(matches!(
+            client.retry("emails", &id).await,
+            Err(Error::Api(ApiError::Validation(_)))
+        ));
+
+        let tasks = client.tasks(&filter(), &all).await.unwrap();
+        assert_eq!(tasks[0].backend, "SqliteStorage");
+        assert!(!client.stats("emails").await.unwrap().is_empty());
+        assert!(!client.overview(&all).await.unwrap().is_empty());
+        assert!(client.workers(&all).await.unwrap().is_empty());
+        assert!(client.queue_workers("emails").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_refusals_are_api_errors() {
+        let (client, _) = serve().await;
+        assert!(matches!(
+            client.task("emails", "01JABCDEFGHJKMNPQRSTVWXYZ0").await,
+            Err(Error::Api(ApiError::NotFound(_)))
+        ));
+        assert!(matches!(
+            client.stats("no such/queue").await,
+            Err(Error::Api(ApiError::NotFound(_)))
+        ));
+        assert!(matches!(
+            client.push("emails", &42).await,
+            Err(Error::Api(ApiError::Validation(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_events_are_streamed() {
+        let (client, broadcaster) = serve().await;
+        let mut events = client.events().await.unwrap();
+
+        let entry = LogEntry {
+            span: Some(Span {
+                attempt: 1,
+                task_id: "01JABCDEFGHJKMNPQRSTVWXYZ0".to_owned(),
+                name: "task".to_owned(),
+            }),
+            level: LogLevel::Info,
+            timestamp: "2026-10-19T05:07:28Z".to_owned(),
+            target: "emails".to_owned(),
+            entry: Entry {
+                message: Some("Sending email".to_owned()),
+                entry_type: None,
+            },
+        };
+        broadcaster.lock().unwrap().send(&entry).unwrap();
+        assert_eq!(events.next().await, Some(entry));
+    }
+}
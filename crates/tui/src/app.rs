@@ -0,0 +1,282 @@
+//! The state of the dashboard and how it changes.
+
+use std::collections::{HashMap, VecDeque};
+
+use apalis_board_client::{Client, Error, RawTask};
+use apalis_board_types::{BackendQuery, LogEntry, Tagged};
+use apalis_core::{
+    backend::{Filter, QueueInfo, RunningWorker, StatType, Statistic},
+    task::status::Status,
+};
+use crossterm::event::KeyCode;
+
+/// The statuses tasks are listed by, in the order of the tabs.
+pub(crate) const STATUSES: [Status; 6] = [
+    Status::Pending,
+    Status::Queued,
+    Status::Running,
+    Status::Done,
+    Status::Failed,
+    Status::Killed,
+];
+
+/// How many values of every statistic are kept for the sparklines.
+const HISTORY: usize = 30;
+
+/// How many log entries are kept.
+const MAX_LOGS: usize = 500;
+
+/// How many tasks are listed per status.
+const PAGE_SIZE: u32 = 50;
+
+/// What the board reported on a refresh.
+#[derive(Debug)]
+pub(crate) struct Snapshot {
+    overview: Vec<Statistic>,
+    queues: Vec<Tagged<QueueInfo>>,
+    workers: Vec<RunningWorker>,
+    /// The queue and status the tasks were listed for.
+    listed: Option<(String, Status)>,
+    tasks: Vec<RawTask>,
+}
+
+impl Snapshot {
+    /// Fetch everything the dashboard shows, listing the tasks of `queue` with
+    /// `status`, or of the first queue when none is selected yet.
+    pub(crate) async fn fetch(
+        client: &Client,
+        queue: Option<String>,
+        status: Status,
+    ) -> Result<Self, Error> {
+        let all = BackendQuery::default();
+        let (overview, queues, workers) = futures::try_join!(
+            client.overview(&all),
+            client.queues(&all),
+            client.workers(&all)
+        )?;
+        let Some(queue) = queue.or_else(|| queues.first().map(|q| q.item.name.clone())) else {
+            return Ok(Self {
+                overview,
+                queues,
+                workers,
+                listed: None,
+                tasks: Vec::new(),
+            });
+        };
+        let filter = Filter {
+            status: Some(status.clone()),
+            page: 1,
+            page_size: Some(PAGE_SIZE),
+        };
+        let tasks = client.queue_tasks(&queue, &filter).await?;
+        Ok(Self {
+            overview,
+            queues,
+            workers,
+            listed: Some((queue, status)),
+            tasks,
+        })
+    }
+}
+
+/// Everything the dashboard shows.
+#[derive(Debug, Default)]
+pub(crate) struct App {
+    pub(crate) overview: Vec<Statistic>,
+    /// The last values of every statistic, oldest first.
+    pub(crate) history: HashMap<String, VecDeque<u64>>,
+    pub(crate) queues: Vec<Tagged<QueueInfo>>,
+    /// The index of the selected queue.
+    pub(crate) selected: usize,
+    /// The index of the selected status in [`STATUSES`].
+    pub(crate) status: usize,
+    pub(crate) tasks: Vec<RawTask>,
+    pub(crate) workers: Vec<RunningWorker>,
+    /// The last log entries, oldest first.
+    pub(crate) logs: VecDeque<LogEntry>,
+    /// Why the last refresh failed.
+    pub(crate) error: Option<String>,
+    pub(crate) quit: bool,
+}
+
+impl App {
+    /// The name of the selected queue.
+    pub(crate) fn queue(&self) -> Option<&str> {
+        self.queues.get(self.selected).map(|q| q.item.name.as_str())
+    }
+
+    /// The selected status.
+    pub(crate) fn status(&self) -> Status {
+        STATUSES[self.status].clone()
+    }
+
+    /// Show the result of a refresh.
+    pub(crate) fn refreshed(&mut self, snapshot: Result<Snapshot, Error>) {
+        let snapshot = match snapshot {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                self.error = Some(e.to_string());
+                return;
+            }
+        };
+        self.error = None;
+
+        for stat in &snapshot.overview {
+            // A trend of timestamps means nothing
+            if matches!(stat.stat_type, StatType::Timestamp) {
+                continue;
+            }
+            // Decimals and percentages are scaled so the sparklines keep their shape
+            let value = stat
+                .value
+                .trim()
+                .trim_end_matches('%')
+                .parse::<f64>()
+                .map_or(0, |v| (v * 100.0) as u64);
+            let history = self.history.entry(stat.title.clone()).or_default();
+            history.push_back(value);
+            if history.len() > HISTORY {
+                history.pop_front();
+            }
+        }
+        self.overview = snapshot.overview;
+
+        // Keep the same queue selected when queues come and go
+        let selected = self.queue().map(str::to_owned);
+        self.queues = snapshot.queues;
+        self.selected = selected
+            .and_then(|name| self.queues.iter().position(|q| q.item.name == name))
+            .unwrap_or(0);
+        self.workers = snapshot.workers;
+
+        // Tasks listed for a previous selection are dropped, a refresh follows
+        let current = self.queue().map(|q| (q.to_owned(), self.status()));
+        if snapshot.listed == current {
+            self.tasks = snapshot.tasks;
+        }
+    }
+
+    /// Show a log entry streamed by the board.
+    pub(crate) fn log(&mut self, entry: LogEntry) {
+        self.logs.push_back(entry);
+        if self.logs.len() > MAX_LOGS {
+            self.logs.pop_front();
+        }
+    }
+
+    /// Handle a key press, returning whether the board should be refreshed.
+    pub(crate) fn on_key(&mut self, key: KeyCode) -> bool {
+        let queues = self.queues.len().max(1);
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Char('r') => return true,
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected = (self.selected + queues - 1) % queues
+            }
+            KeyCode::Down | KeyCode::Char('j') => self.selected = (self.selected + 1) % queues,
+            KeyCode::Left | KeyCode::Char('h') => {
+                self.status = (self.status + STATUSES.len() - 1) % STATUSES.len();
+            }
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Tab => {
+                self.status = (self.status + 1) % STATUSES.len();
+            }
+            _ => return false,
+        }
+        if self.quit {
+            return false;
+        }
+        self.tasks.clear();
+        true
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) fn queue(name: &str, pending: &str) -> Tagged<QueueInfo> {
+        Tagged {
+            backend: "SqliteStorage".to_owned(),
+            item: QueueInfo {
+                name: name.to_owned(),
+                stats: vec![stat("PENDING_JOBS", pending)],
+                workers: Vec::new(),
+                activity: Vec::new(),
+            },
+        }
+    }
+
+    pub(crate) fn stat(title: &str, value: &str) -> Statistic {
+        Statistic {
+            title: title.to_owned(),
+            stat_type: StatType::Number,
+            value: value.to_owned(),
+            priority: Some(1),
+        }
+    }
+
+    pub(crate) fn snapshot(queues: Vec<Tagged<QueueInfo>>, pending: &str) -> Snapshot {
+        Snapshot {
+            overview: vec![stat("PENDING_JOBS", pending)],
+            listed: queues
+                .first()
+                .map(|q| (q.item.name.clone(), Status::Pending)),
+            queues,
+            workers: Vec::new(),
+            tasks: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_selection_follows_the_queue() {
+        let mut app = App::default();
+        app.refreshed(Ok(snapshot(
+            vec![queue("emails", "1"), queue("sms", "2")],
+            "3",
+        )));
+        assert!(app.on_key(KeyCode::Down));
+        assert_eq!(app.queue(), Some("sms"));
+
+        let queues = vec![queue("push", "0"), queue("emails", "1"), queue("sms", "2")];
+        app.refreshed(Ok(snapshot(queues, "5")));
+        assert_eq!(app.queue(), Some("sms"));
+        assert!(app.on_key(KeyCode::Down));
+        assert_eq!(app.queue(), Some("push"));
+        assert!(app.on_key(KeyCode::Left));
+        assert_eq!(app.status(), Status::Killed);
+
+        assert_eq!(app.history["PENDING_JOBS"], [300, 500]);
+        assert!(!app.on_key(KeyCode::Char('q')));
+        assert!(app.quit);
+    }
+
+    #[test]
+    fn test_history_and_logs_are_capped() {
+        let mut app = App::default();
+        for i in 0..40 {
+            app.refreshed(Ok(snapshot(Vec::new(), &i.to_string())));
+        }
+        assert_eq!(app.history["PENDING_JOBS"].len(), HISTORY);
+        assert_eq!(app.history["PENDING_JOBS"][0], 1_000);
+
+        let mut snapshot = snapshot(Vec::new(), "1");
+        snapshot.overview = vec![
+            Statistic {
+                stat_type: StatType::Percentage,
+                ..stat("SUCCESS_RATE", " 12.5% ")
+            },
+            Statistic {
+                stat_type: StatType::Timestamp,
+                ..stat("LAST_RUN", "1760000000")
+            },
+        ];
+        app.refreshed(Ok(snapshot));
+        assert_eq!(app.history["SUCCESS_RATE"], [1_250]);
+        assert!(!app.history.contains_key("LAST_RUN"));
+
+        for _ in 0..MAX_LOGS + 10 {
+            app.log(LogEntry::default());
+        }
+        assert_eq!(app.logs.len(), MAX_LOGS);
+    }
+}
@@ -0,0 +1,140 @@
+//! A terminal dashboard for the queues of an apalis board.
+//!
+//! ```sh
+//! apalis-board-tui --url https://board.example.com/api/v1
+//! ```
+//!
+//! It mirrors the web board where a browser can't be used, e.g. over SSH.
+
+use std::{process::ExitCode, time::Duration};
+
+use apalis_board_client::{Client, Error};
+use apalis_board_types::LogEntry;
+use clap::Parser;
+use crossterm::event::{Event, EventStream, KeyEventKind};
+use futures::StreamExt;
+use ratatui::DefaultTerminal;
+use tokio::sync::mpsc::{UnboundedSender, unbounded_channel};
+
+use crate::app::{App, Snapshot};
+
+mod app;
+mod ui;
+
+/// A terminal dashboard for the queues of an apalis board.
+#[derive(Debug, Parser)]
+#[command(name = "apalis-board-tui", version)]
+struct Cli {
+    /// The URL of the board's API.
+    #[arg(
+        long,
+        env = "APALIS_BOARD_URL",
+        default_value = "http://localhost:8000/api/v1"
+    )]
+    url: String,
+
+    /// An API token to authenticate with.
+    #[arg(long, env = "APALIS_BOARD_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+
+    /// How often to refresh, in seconds.
+    #[arg(long, value_name = "SECONDS", default_value_t = 5)]
+    refresh: u64,
+}
+
+/// What the background tasks report to the dashboard.
+enum Message {
+    Refreshed(Result<Snapshot, Error>),
+    Log(LogEntry),
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let mut client = Client::new(&cli.url);
+    if let Some(token) = &cli.token {
+        client = client.bearer_token(token);
+    }
+
+    let terminal = ratatui::init();
+    let res = run(terminal, client, Duration::from_secs(cli.refresh.max(1))).await;
+    ratatui::restore();
+    match res {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(
+    mut terminal: DefaultTerminal,
+    client: Client,
+    every: Duration,
+) -> std::io::Result<()> {
+    let (tx, mut rx) = unbounded_channel();
+    tokio::spawn(follow_logs(client.clone(), tx.clone()));
+
+    let mut app = App::default();
+    let mut keys = EventStream::new();
+    let mut refresh = tokio::time::interval(every);
+    // Ticks are skipped while the board is slower to answer than they come
+    let mut refreshing = 0_usize;
+    while !app.quit {
+        terminal.draw(|frame| ui::draw(frame, &app))?;
+        tokio::select! {
+            _ = refresh.tick() => {
+                if refreshing == 0 {
+                    refreshing += 1;
+                    spawn_refresh(&client, &app, &tx);
+                }
+            }
+            Some(message) = rx.recv() => match message {
+                Message::Refreshed(snapshot) => {
+                    refreshing -= 1;
+                    app.refreshed(snapshot);
+                }
+                Message::Log(entry) => app.log(entry),
+            },
+            event = keys.next() => match event {
+                Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => {
+                    if app.on_key(key.code) {
+                        refreshing += 1;
+                        spawn_refresh(&client, &app, &tx);
+                    }
+                }
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e),
+                None => break,
+            },
+        }
+    }
+    Ok(())
+}
+
+/// Fetch what the dashboard shows for the current selection in the background.
+fn spawn_refresh(client: &Client, app: &App, tx: &UnboundedSender<Message>) {
+    let client = client.clone();
+    let tx = tx.clone();
+    let queue = app.queue().map(str::to_owned);
+    let status = app.status();
+    tokio::spawn(async move {
+        let snapshot = Snapshot::fetch(&client, queue, status).await;
+        let _ = tx.send(Message::Refreshed(snapshot));
+    });
+}
+
+/// Forward the log entries of the board, reconnecting when the stream ends.
+async fn follow_logs(client: Client, tx: UnboundedSender<Message>) {
+    loop {
+        if let Ok(mut events) = client.events().await {
+            while let Some(entry) = events.next().await {
+                if tx.send(Message::Log(entry)).is_err() {
+                    return;
+                }
+            }
+        }
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    }
+}
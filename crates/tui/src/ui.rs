@@ -0,0 +1,292 @@
+//! Drawing the dashboard.
+
+use apalis_board_types::{EntryType, LogEntry, LogLevel};
+use apalis_core::backend::{StatType, Statistic};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, ListState, Paragraph, Row, Sparkline, Table, Tabs},
+};
+
+use crate::app::{App, STATUSES};
+
+/// How many statistics get a card, by priority.
+const CARDS: usize = 6;
+
+/// Draw the whole dashboard.
+pub(crate) fn draw(frame: &mut Frame<'_>, app: &App) {
+    let [cards, middle, bottom, footer] = Layout::vertical([
+        Constraint::Length(6),
+        Constraint::Min(8),
+        Constraint::Length(12),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [queues, tasks] =
+        Layout::horizontal([Constraint::Length(28), Constraint::Min(0)]).areas(middle);
+    let [workers, logs] =
+        Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(bottom);
+
+    draw_cards(frame, app, cards);
+    draw_queues(frame, app, queues);
+    draw_tasks(frame, app, tasks);
+    draw_workers(frame, app, workers);
+    draw_logs(frame, app, logs);
+    draw_footer(frame, app, footer);
+}
+
+/// A card with the value and the sparkline of every headline statistic.
+fn draw_cards(frame: &mut Frame<'_>, app: &App, area: Rect) {
+    let mut stats: Vec<&Statistic> = app.overview.iter().collect();
+    stats.sort_by_key(|stat| stat.priority.unwrap_or(u64::MAX));
+    stats.truncate(CARDS);
+    if stats.is_empty() {
+        frame.render_widget(Block::bordered().title(" Overview "), area);
+        return;
+    }
+
+    let areas = Layout::horizontal(vec![Constraint::Fill(1); stats.len()]).split(area);
+    for (stat, area) in stats.into_iter().zip(areas.iter()) {
+        let block = Block::bordered().title(format!(" {} ", title(&stat.title)));
+        let inner = block.inner(*area);
+        frame.render_widget(block, *area);
+
+        let [value, sparkline] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(inner);
+        frame.render_widget(Paragraph::new(format_stat(stat).bold()), value);
+        if let Some(history) = app.history.get(&stat.title) {
+            let data: Vec<u64> = history.iter().copied().collect();
+            frame.render_widget(
+                Sparkline::default().data(&data).style(Color::Cyan),
+                sparkline,
+            );
+        }
+    }
+}
+
+fn draw_queues(frame: &mut Frame<'_>, app: &App, area: Rect) {
+    let items: Vec<ListItem<'_>> = app
+        .queues
+        .iter()
+        .map(|queue| {
+            let pending = queue
+                .item
+                .stats
+                .iter()
+                .find(|stat| stat.title == "PENDING_JOBS")
+                .map_or("", |stat| stat.value.as_str());
+            ListItem::new(Line::from(vec![
+                Span::raw(queue.item.name.clone()),
+                Span::raw(format!(" {pending}")).dim(),
+            ]))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(Block::bordered().title(" Queues "))
+        .highlight_style(Style::new().reversed())
+        .highlight_symbol("> ");
+    let mut state = ListState::default().with_selected(Some(app.selected));
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn draw_tasks(frame: &mut Frame<'_>, app: &App, area: Rect) {
+    let block = Block::bordered().title(format!(" Tasks of {} ", app.queue().unwrap_or("-")));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let [tabs, table] = Layout::vertical([Constraint::Length(2), Constraint::Min(0)]).areas(inner);
+
+    frame.render_widget(
+        Tabs::new(STATUSES.iter().map(ToString::to_string))
+            .select(app.status)
+            .highlight_style(Style::new().bold().reversed()),
+        tabs,
+    );
+
+    let now = now();
+    let rows = app.tasks.iter().map(|task| {
+        Row::new(vec![
+            task.parts
+                .task_id
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            task.parts.attempt.current().to_string(),
+            ago(now, task.parts.run_at),
+            task.args.to_string(),
+        ])
+    });
+    let widths = [
+        Constraint::Length(26),
+        Constraint::Length(8),
+        Constraint::Length(10),
+        Constraint::Min(0),
+    ];
+    frame.render_widget(
+        Table::new(rows, widths)
+            .header(Row::new(["ID", "ATTEMPTS", "RUN AT", "ARGS"]).bold())
+            .column_spacing(2),
+        table,
+    );
+}
+
+fn draw_workers(frame: &mut Frame<'_>, app: &App, area: Rect) {
+    let now = now();
+    let rows = app.workers.iter().map(|worker| {
+        let age = now.saturating_sub(worker.last_heartbeat);
+        let color = match age {
+            0..30 => Color::Green,
+            30..120 => Color::Yellow,
+            _ => Color::Red,
+        };
+        Row::new(vec![
+            Span::raw(worker.id.clone()),
+            Span::raw(worker.queue.clone()),
+            Span::styled(ago(now, worker.last_heartbeat), color),
+        ])
+    });
+    let widths = [
+        Constraint::Fill(2),
+        Constraint::Fill(1),
+        Constraint::Length(9),
+    ];
+    frame.render_widget(
+        Table::new(rows, widths)
+            .header(Row::new(["ID", "QUEUE", "HEARTBEAT"]).bold())
+            .block(Block::bordered().title(" Workers ")),
+        area,
+    );
+}
+
+fn draw_logs(frame: &mut Frame<'_>, app: &App, area: Rect) {
+    let height = area.height.saturating_sub(2) as usize;
+    let skip = app.logs.len().saturating_sub(height);
+    let items: Vec<ListItem<'_>> = app.logs.iter().skip(skip).map(log_line).collect();
+    frame.render_widget(
+        List::new(items).block(Block::bordered().title(" Logs ")),
+        area,
+    );
+}
+
+fn log_line(entry: &LogEntry) -> ListItem<'_> {
+    let (level, color) = match entry.level {
+        LogLevel::Error => ("ERROR", Color::Red),
+        LogLevel::Warn => ("WARN", Color::Yellow),
+        LogLevel::Info => ("INFO", Color::Green),
+        LogLevel::Debug => ("DEBUG", Color::Blue),
+        LogLevel::Trace => ("TRACE", Color::Magenta),
+    };
+    let message = match (&entry.entry.message, &entry.entry.entry_type) {
+        (Some(message), _) => message.clone(),
+        (None, Some(EntryType::Result(result))) => {
+            format!("{} in {}", result.result, result.done_in)
+        }
+        _ => String::new(),
+    };
+    let task = entry.span.as_ref().map_or("", |span| span.task_id.as_str());
+    ListItem::new(Line::from(vec![
+        Span::raw(format!("{level:5} ")).fg(color),
+        Span::raw(format!("{task} ")).dim(),
+        Span::raw(message),
+    ]))
+}
+
+fn draw_footer(frame: &mut Frame<'_>, app: &App, area: Rect) {
+    let line = match &app.error {
+        Some(error) => Line::from(error.clone()).fg(Color::Red),
+        None => Line::from("q quit  ↑↓ queue  ←→ status  r refresh").add_modifier(Modifier::DIM),
+    };
+    frame.render_widget(Paragraph::new(line), area);
+}
+
+/// `PENDING_JOBS` as `Pending jobs`.
+fn title(name: &str) -> String {
+    let lower = name.replace('_', " ").to_lowercase();
+    let mut chars = lower.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
+fn format_stat(stat: &Statistic) -> String {
+    match stat.stat_type {
+        StatType::Percentage => format!("{}%", stat.value),
+        StatType::Timestamp => stat
+            .value
+            .parse()
+            .map_or_else(|_| stat.value.clone(), |secs| ago(now(), secs)),
+        StatType::Number | StatType::Decimal => stat.value.clone(),
+    }
+}
+
+/// How far `secs` is from `now`, both in seconds since the Unix epoch, e.g. `5m ago`.
+fn ago(now: u64, secs: u64) -> String {
+    let (prefix, suffix, delta) = match secs > now {
+        true => ("in ", "", secs - now),
+        false => ("", " ago", now - secs),
+    };
+    let amount = match delta {
+        0..60 => format!("{delta}s"),
+        60..3_600 => format!("{}m", delta / 60),
+        3_600..86_400 => format!("{}h", delta / 3_600),
+        _ => format!("{}d", delta / 86_400),
+    };
+    format!("{prefix}{amount}{suffix}")
+}
+
+/// The current time in seconds since the Unix epoch.
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use apalis_board_types::{Entry, Span as LogSpan};
+    use ratatui::{Terminal, backend::TestBackend};
+
+    use super::*;
+    use crate::app::tests::{queue, snapshot};
+
+    #[test]
+    fn test_dashboard_renders() {
+        let mut app = App::default();
+        app.refreshed(Ok(snapshot(vec![queue("emails", "3")], "3")));
+        app.log(LogEntry {
+            span: Some(LogSpan {
+                attempt: 1,
+                task_id: "01JABCDEFGHJKMNPQRSTVWXYZ0".to_owned(),
+                name: "task".to_owned(),
+            }),
+            level: LogLevel::Warn,
+            entry: Entry {
+                message: Some("SMTP is slow".to_owned()),
+                entry_type: None,
+            },
+            ..LogEntry::default()
+        });
+
+        let mut terminal = Terminal::new(TestBackend::new(120, 32)).unwrap();
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        for text in [
+            "Pending jobs",
+            "Tasks of emails",
+            "> emails 3",
+            "Killed",
+            "WARN  01JABCDEFGHJKMNPQRSTVWXYZ0 SMTP is slow",
+        ] {
+            assert!(screen.contains(text), "`{text}` is not on the screen");
+        }
+        assert_eq!(title("STALE_RUNNING_JOBS"), "Stale running jobs");
+        assert_eq!(ago(1_000, 1_120), "in 2m");
+    }
+}
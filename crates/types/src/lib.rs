@@ -97,8 +97,388 @@ pub struct Span {
     pub name: String,
 }
 
+/// What a user may do, from least to most privileged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Browse queues, tasks, workers and logs.
+    Viewer,
+    /// Also push, retry and kill tasks.
+    Operator,
+    /// Also purge and pause queues.
+    Admin,
+}
+
+/// An action guarded by the API, see [`Permission::role`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Permission {
+    /// Any `GET` route and the event stream.
+    Read,
+    /// Push a new task.
+    Push,
+    /// Retry a failed task.
+    Retry,
+    /// Kill a running task.
+    Kill,
+    /// Remove the tasks of a queue.
+    Purge,
+    /// Pause or resume a queue.
+    Pause,
+    /// Mint and revoke API tokens.
+    #[serde(rename = "manage_tokens")]
+    ManageTokens,
+    /// Read the audit log.
+    Audit,
+}
+
+impl Permission {
+    /// The least privileged role allowed to perform the action.
+    #[must_use]
+    pub fn role(self) -> Role {
+        match self {
+            Self::Read => Role::Viewer,
+            Self::Push | Self::Retry | Self::Kill => Role::Operator,
+            Self::Purge | Self::Pause | Self::ManageTokens | Self::Audit => Role::Admin,
+        }
+    }
+
+    /// The name of the permission, as serialized.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Push => "push",
+            Self::Retry => "retry",
+            Self::Kill => "kill",
+            Self::Purge => "purge",
+            Self::Pause => "pause",
+            Self::ManageTokens => "manage_tokens",
+            Self::Audit => "audit",
+        }
+    }
+}
+
+/// A role on the queues whose names match a pattern.
+///
+/// The pattern may contain `*` wildcards, e.g. `emails-*`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Grant {
+    /// The granted role.
+    pub role: Role,
+    /// The queues the role applies to.
+    pub queues: String,
+}
+
+impl Grant {
+    /// Grant `role` on the queues matching `queues`.
+    #[must_use]
+    pub fn new(role: Role, queues: impl Into<String>) -> Self {
+        Self {
+            role,
+            queues: queues.into(),
+        }
+    }
+
+    /// Whether the grant covers `queue`.
+    #[must_use]
+    pub fn matches(&self, queue: &str) -> bool {
+        glob_match(&self.queues, queue)
+    }
+}
+
+/// Match `text` against a pattern where `*` stands for any run of characters.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // No wildcard, the whole text must be the prefix.
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// The user a request was authenticated as.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct User {
+    /// A stable identifier for the user.
+    pub id: String,
+    /// The name shown in the board.
+    pub username: String,
+    /// The roles of the user. A user without grants may do nothing.
+    #[serde(default)]
+    pub grants: Vec<Grant>,
+}
+
+impl User {
+    /// Create a user whose id is its name.
+    #[must_use]
+    pub fn new(username: impl Into<String>) -> Self {
+        let username = username.into();
+        Self {
+            id: username.clone(),
+            username,
+            grants: Vec::new(),
+        }
+    }
+
+    /// The user of boards that have no authentication configured, an admin
+    /// of every queue.
+    #[must_use]
+    pub fn anonymous() -> Self {
+        Self::new("anonymous").grant(Role::Admin, "*")
+    }
+
+    /// Grant `role` on the queues matching `queues`, `*` for every queue.
+    #[must_use]
+    pub fn grant(mut self, role: Role, queues: impl Into<String>) -> Self {
+        self.grants.push(Grant::new(role, queues));
+        self
+    }
+
+    /// Whether the user may perform `permission` on `queue`.
+    ///
+    /// Routes that are not tied to a queue pass `None`, any grant with a
+    /// sufficient role allows them. A queue named `*` stands for every queue,
+    /// only grants on `*` allow it. Without grants, nothing is allowed.
+    #[must_use]
+    pub fn can(&self, permission: Permission, queue: Option<&str>) -> bool {
+        self.grants
+            .iter()
+            .any(|grant| grant.role >= permission.role() && queue.is_none_or(|q| grant.matches(q)))
+    }
+}
+
+/// How the board is set up, served at `/config` so the UI can adapt to it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoardConfig {
+    /// Write routes such as pushing tasks are not registered.
+    pub read_only: bool,
+    /// API tokens can be managed at `/admin/tokens`.
+    #[serde(default)]
+    pub tokens: bool,
+}
+
+/// Where the frontend and the API are served, injected into `index.html`
+/// so a prebuilt frontend can be mounted anywhere.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UiConfig {
+    /// The prefix of the API routes, without a trailing slash, e.g. `/api/v1`.
+    pub api_path: String,
+    /// The path the frontend is served at, with a trailing slash, e.g. `/admin/jobs/`.
+    pub public_url: String,
+}
+
+impl UiConfig {
+    /// The id of the `<script type="application/json">` element holding the config.
+    pub const ELEMENT_ID: &str = "apalis-board-config";
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            api_path: "/api/v1".to_owned(),
+            public_url: "/".to_owned(),
+        }
+    }
+}
+
+/// An API token, without its secret.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiToken {
+    /// Identifies the token when revoking it.
+    pub id: String,
+    /// What the token is used for, e.g. the CI job.
+    pub name: String,
+    /// The roles of the token.
+    pub grants: Vec<Grant>,
+    /// The user that minted the token.
+    pub created_by: String,
+    /// When the token was minted, in seconds since the Unix epoch.
+    pub created_at: u64,
+    /// When the token stops being accepted, in seconds since the Unix epoch.
+    pub expires_at: Option<u64>,
+}
+
+/// The request to mint an [`ApiToken`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewToken {
+    /// What the token is used for.
+    pub name: String,
+    /// The roles of the token, at least one and none wider than the minting user's.
+    #[serde(default)]
+    pub grants: Vec<Grant>,
+    /// When the token stops being accepted, in seconds since the Unix epoch.
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+/// A freshly minted token. The secret is only ever returned here.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreatedToken {
+    /// The stored token.
+    pub token: ApiToken,
+    /// The bearer token to hand to the client.
+    pub secret: String,
+}
+
+/// The answer to retrying a task, which runs again as a new task.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetriedTask {
+    /// The id of the new task, when the backend reports it.
+    pub task_id: Option<String>,
+}
+
+/// An item listed by `/queues` or `/tasks`, tagged with the backend it came from.
+///
+/// Serialized as the item with an extra `backend` field.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Tagged<T> {
+    /// The name of the backend, e.g. `SqliteStorage`.
+    pub backend: String,
+    /// The listed item.
+    #[serde(flatten)]
+    pub item: T,
+}
+
+/// Restricts a root route to one of the registered backends.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackendQuery {
+    /// The name of the backend, every backend when missing.
+    #[serde(default)]
+    pub backend: Option<String>,
+}
+
+/// A change made through the board, see [`AuditRecord`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    /// A task was pushed.
+    Push,
+    /// A task was retried.
+    Retry,
+    /// A task was killed.
+    Kill,
+    /// The arguments of a task were edited.
+    Edit,
+    /// The tasks of a queue were removed.
+    Purge,
+    /// A queue was paused or resumed.
+    Pause,
+    /// An API token was minted.
+    CreateToken,
+    /// An API token was revoked.
+    RevokeToken,
+}
+
+impl AuditAction {
+    /// The name of the action, as serialized.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Push => "push",
+            Self::Retry => "retry",
+            Self::Kill => "kill",
+            Self::Edit => "edit",
+            Self::Purge => "purge",
+            Self::Pause => "pause",
+            Self::CreateToken => "create_token",
+            Self::RevokeToken => "revoke_token",
+        }
+    }
+}
+
+/// Where a request came from.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestOrigin {
+    /// The client address, taken from `X-Forwarded-For` behind a trusted proxy.
+    pub address: Option<String>,
+    /// The `User-Agent` of the client.
+    pub user_agent: Option<String>,
+}
+
+/// Who changed what through the board, served at `/audit`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// The username of the authenticated user.
+    pub actor: String,
+    /// The stable id of the user, e.g. `token:<id>` for API tokens, whose
+    /// names may match a username.
+    #[serde(default)]
+    pub actor_id: String,
+    /// What was done.
+    pub action: AuditAction,
+    /// The affected queue.
+    pub queue: Option<String>,
+    /// The affected tasks.
+    #[serde(default)]
+    pub task_ids: Vec<String>,
+    /// The affected token, for token actions.
+    #[serde(default)]
+    pub target: Option<String>,
+    /// When the change was made, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// Where the request came from.
+    #[serde(default)]
+    pub origin: RequestOrigin,
+    /// Why the change failed, none when it succeeded.
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Filters the records served at `/audit`, newest first.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditQuery {
+    /// Only changes made by this username.
+    pub actor: Option<String>,
+    /// Only changes of this kind.
+    pub action: Option<AuditAction>,
+    /// Only changes to this queue.
+    pub queue: Option<String>,
+    /// Only changes touching this task.
+    pub task_id: Option<String>,
+    /// At most this many records, 100 by default.
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    /// The number of records returned without a `limit`.
+    pub const DEFAULT_LIMIT: usize = 100;
+
+    /// Whether `record` passes the filters.
+    #[must_use]
+    pub fn matches(&self, record: &AuditRecord) -> bool {
+        self.actor.as_ref().is_none_or(|a| *a == record.actor)
+            && self.action.is_none_or(|a| a == record.action)
+            && self
+                .queue
+                .as_ref()
+                .is_none_or(|q| record.queue.as_ref() == Some(q))
+            && self
+                .task_id
+                .as_ref()
+                .is_none_or(|id| record.task_ids.contains(id))
+    }
+
+    /// The maximum number of records to return.
+    #[must_use]
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(Self::DEFAULT_LIMIT)
+    }
+}
+
 /// An enumeration of possible API errors.
-#[derive(Debug, thiserror::Error, Serialize, Deserialize)]
+#[derive(Clone, Debug, thiserror::Error, Serialize, Deserialize)]
 #[serde(tag = "type", content = "message")]
 pub enum ApiError {
     /// Error related to codec operations.
@@ -107,4 +487,34 @@ pub enum ApiError {
     /// Error related to backend operations.
     #[error("Backend error: {0}")]
     BackendError(String),
+    /// The request was well formed but its content was rejected.
+    #[error("Validation error: {0}")]
+    Validation(String),
+    /// The requested resource does not exist.
+    #[error("Not found: {0}")]
+    NotFound(String),
+    /// The request conflicts with the current state of the resource.
+    #[error("Conflict: {0}")]
+    Conflict(String),
+    /// The request lacks valid credentials.
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+    /// The user is not allowed to perform the request.
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+}
+
+impl ApiError {
+    /// The HTTP status code the error is reported with.
+    #[must_use]
+    pub fn status_code(&self) -> u16 {
+        match self {
+            Self::CodecError(_) | Self::BackendError(_) => 500,
+            Self::Validation(_) => 422,
+            Self::NotFound(_) => 404,
+            Self::Conflict(_) => 409,
+            Self::Unauthorized(_) => 401,
+            Self::Forbidden(_) => 403,
+        }
+    }
 }
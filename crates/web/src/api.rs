@@ -1,20 +1,128 @@
-use crate::config::API_PATH;
-use gloo_net::http::Request;
-use serde::de::DeserializeOwned;
+use crate::{
+    auth::{CSRF_HEADER, authorization, csrf_token},
+    config::api_path,
+};
+use apalis_board_types::ApiError;
+use gloo_net::http::{Method, RequestBuilder, Response};
+use serde::{Serialize, de::DeserializeOwned};
+use std::{cell::RefCell, collections::HashMap};
+
+/// How many responses are kept for revalidation before starting over.
+const MAX_VALIDATED: usize = 64;
+
+thread_local! {
+    /// The `ETag` and body of the last response to every url, see [`ApiClient::get`].
+    static VALIDATED: RefCell<HashMap<String, (String, String)>> = RefCell::new(HashMap::new());
+}
+
 pub struct ApiClient;
 
 impl ApiClient {
+    /// Attach the credentials of the signed in user and the CSRF token.
+    fn request(builder: RequestBuilder) -> RequestBuilder {
+        let builder = match csrf_token() {
+            Some(token) => builder.header(CSRF_HEADER, &token),
+            None => builder,
+        };
+        match authorization() {
+            Some(value) => builder.header("Authorization", &value),
+            None => builder,
+        }
+    }
+
+    /// Turn an unsuccessful response into the message of its [`ApiError`].
+    async fn error(resp: Response) -> String {
+        let text = resp.text().await.unwrap_or_default();
+        match serde_json::from_str::<ApiError>(&text) {
+            Ok(err) => err.to_string(),
+            Err(_) => format!("{} {}", resp.status(), text),
+        }
+    }
+
+    /// Fetch `path`, reusing the last response when the server answers `304 Not Modified`.
+    ///
+    /// The periodic refreshes then only download what changed.
     pub async fn get<T: DeserializeOwned>(path: &str) -> Result<T, String> {
-        let url = format!("{API_PATH}{path}");
+        let url = format!("{}{path}", api_path());
+        let validated = VALIDATED.with_borrow(|v| v.get(&url).cloned());
 
-        let resp: T = Request::get(&url)
+        let mut builder = RequestBuilder::new(&url);
+        if let Some((etag, _)) = &validated {
+            builder = builder.header("If-None-Match", etag);
+        }
+        let resp = Self::request(builder)
             .send()
             .await
+            .map_err(|e| e.to_string())?;
+        if let (304, Some((_, body))) = (resp.status(), &validated) {
+            return serde_json::from_str(body).map_err(|e| e.to_string());
+        }
+        if !resp.ok() {
+            return Err(Self::error(resp).await);
+        }
+
+        let etag = resp.headers().get("ETag");
+        let body = resp.text().await.map_err(|e| e.to_string())?;
+        let parsed = serde_json::from_str(&body).map_err(|e| e.to_string());
+        if let Some(etag) = etag {
+            VALIDATED.with_borrow_mut(|v| {
+                if v.len() >= MAX_VALIDATED {
+                    v.clear();
+                }
+                v.insert(url, (etag, body));
+            });
+        }
+        parsed
+    }
+
+    pub async fn post<B: Serialize, T: DeserializeOwned>(
+        path: &str,
+        body: &B,
+    ) -> Result<T, String> {
+        let url = format!("{}{path}", api_path());
+
+        let resp = Self::request(RequestBuilder::new(&url).method(Method::POST))
+            .json(body)
             .map_err(|e| e.to_string())?
-            .json()
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !resp.ok() {
+            return Err(Self::error(resp).await);
+        }
+
+        resp.json().await.map_err(|e| e.to_string())
+    }
+
+    pub async fn delete(path: &str) -> Result<(), String> {
+        let url = format!("{}{path}", api_path());
+
+        let resp = Self::request(RequestBuilder::new(&url).method(Method::DELETE))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if resp.ok() {
+            Ok(())
+        } else {
+            Err(Self::error(resp).await)
+        }
+    }
+
+    pub async fn put<B: Serialize>(path: &str, body: &B) -> Result<(), String> {
+        let url = format!("{}{path}", api_path());
+
+        let resp = Self::request(RequestBuilder::new(&url).method(Method::PUT))
+            .json(body)
+            .map_err(|e| e.to_string())?
+            .send()
             .await
             .map_err(|e| e.to_string())?;
 
-        Ok(resp)
+        if resp.ok() {
+            Ok(())
+        } else {
+            Err(Self::error(resp).await)
+        }
     }
 }
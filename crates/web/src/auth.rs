@@ -0,0 +1,136 @@
+//! Credentials of the signed in user.
+//!
+//! The `Authorization` value is kept in `sessionStorage` so that it survives
+//! reloads but not the tab, and is attached to every API request by
+//! [`ApiClient`](crate::api::ApiClient).
+
+const STORAGE_KEY: &str = "apalis-board-authorization";
+
+fn storage() -> Option<web_sys::Storage> {
+    leptos::prelude::window().session_storage().ok().flatten()
+}
+
+/// The stored `Authorization` value, if the user signed in.
+pub fn authorization() -> Option<String> {
+    storage()?.get_item(STORAGE_KEY).ok().flatten()
+}
+
+/// Remember the `Authorization` value, or forget it with `None`.
+pub fn set_authorization(authorization: Option<&str>) {
+    let Some(storage) = storage() else {
+        return;
+    };
+    let _ = match authorization {
+        Some(value) => storage.set_item(STORAGE_KEY, value),
+        None => storage.remove_item(STORAGE_KEY),
+    };
+}
+
+/// The cookie repeated in the [`CSRF_HEADER`] of every API request.
+const CSRF_COOKIE: &str = "apalis-board-csrf";
+
+/// The header the API expects the CSRF token in.
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
+
+/// The double-submit token protecting the API against cross-site requests.
+///
+/// A random token is stored in a cookie on first use. Other sites cannot read
+/// it, so only the board can repeat it in the [`CSRF_HEADER`].
+pub fn csrf_token() -> Option<String> {
+    use leptos::wasm_bindgen::JsCast;
+
+    let document = leptos::prelude::document()
+        .dyn_into::<web_sys::HtmlDocument>()
+        .ok()?;
+    let cookies = document.cookie().ok()?;
+    if let Some(token) = cookie(&cookies, CSRF_COOKIE) {
+        return Some(token.to_owned());
+    }
+    let mut bytes = [0u8; 16];
+    leptos::prelude::window()
+        .crypto()
+        .ok()?
+        .get_random_values_with_u8_array(&mut bytes)
+        .ok()?;
+    let token: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    document
+        .set_cookie(&format!("{CSRF_COOKIE}={token}; path=/; SameSite=Strict"))
+        .ok()?;
+    Some(token)
+}
+
+/// The value of the cookie `name` in a `document.cookie` string.
+fn cookie<'a>(cookies: &'a str, name: &str) -> Option<&'a str> {
+    cookies
+        .split(';')
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+        .filter(|value| !value.is_empty())
+}
+
+/// The `Authorization` value of a bearer token.
+pub fn bearer(token: &str) -> String {
+    format!("Bearer {}", token.trim())
+}
+
+/// The `Authorization` value of a username and password.
+pub fn basic(username: &str, password: &str) -> Option<String> {
+    leptos::prelude::window()
+        .btoa(&format!("{username}:{password}"))
+        .ok()
+        .map(|encoded| format!("Basic {encoded}"))
+}
+
+/// Append the credentials to a URL, for requests that cannot set headers like `EventSource`.
+pub fn with_authorization(url: &str, authorization: Option<&str>) -> String {
+    match authorization {
+        Some(authorization) => {
+            let separator = if url.contains('?') { '&' } else { '?' };
+            format!(
+                "{url}{separator}authorization={}",
+                encode_component(authorization)
+            )
+        }
+        None => url.to_owned(),
+    }
+}
+
+/// Percent-encode a query string component.
+pub(crate) fn encode_component(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_authorization_in_url() {
+        assert_eq!(
+            with_authorization("/api/v1/events", Some("Bearer a+b=")),
+            "/api/v1/events?authorization=Bearer%20a%2Bb%3D"
+        );
+        assert_eq!(
+            with_authorization("/events?x=1", Some("Bearer t")),
+            "/events?x=1&authorization=Bearer%20t"
+        );
+        assert_eq!(with_authorization("/events", None), "/events");
+    }
+
+    #[test]
+    fn test_cookie_lookup() {
+        let cookies = "theme=dark; apalis-board-csrf=abc123";
+        assert_eq!(cookie(cookies, CSRF_COOKIE), Some("abc123"));
+        assert_eq!(cookie(cookies, "session"), None);
+        assert_eq!(cookie("apalis-board-csrf=", CSRF_COOKIE), None);
+    }
+}
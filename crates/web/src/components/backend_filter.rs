@@ -0,0 +1,59 @@
+use leptos::prelude::*;
+
+use crate::{api::ApiClient, auth::encode_component};
+
+/// `path` restricted to the items of `backend`, unchanged when it is empty.
+pub fn with_backend(path: &str, backend: &str) -> String {
+    if backend.is_empty() {
+        return path.to_owned();
+    }
+    let separator = if path.contains('?') { '&' } else { '?' };
+    format!("{path}{separator}backend={}", encode_component(backend))
+}
+
+/// Pick one of the registered backends, hidden when there is only one.
+#[component]
+pub fn BackendFilter(backend: RwSignal<String>) -> impl IntoView {
+    let backends =
+        LocalResource::new(|| async { ApiClient::get::<Vec<String>>("/backends").await });
+
+    move || {
+        let backends = backends.get().and_then(Result::ok).unwrap_or_default();
+        (backends.len() > 1).then(|| {
+            view! {
+                <select
+                    class="bg-charcoal-900 border border-charcoal-700 px-2 h-6 rounded-sm text-gray-200 focus:outline-none focus:border-charcoal-500 text-xs"
+                    prop:value=backend
+                    on:change=move |ev| backend.set(event_target_value(&ev))
+                >
+                    <option value="">"All backends"</option>
+                    {backends
+                        .into_iter()
+                        .map(|name| {
+                            let label = name.clone();
+                            view! { <option value=name>{label}</option> }
+                        })
+                        .collect_view()}
+                </select>
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_backend_appends_the_filter() {
+        assert_eq!(with_backend("/queues", ""), "/queues");
+        assert_eq!(
+            with_backend("/queues", "SqliteStorage"),
+            "/queues?backend=SqliteStorage"
+        );
+        assert_eq!(
+            with_backend("/tasks?page=1", "Postgres Storage"),
+            "/tasks?page=1&backend=Postgres%20Storage"
+        );
+    }
+}
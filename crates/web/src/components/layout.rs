@@ -1,19 +1,49 @@
-use leptos::prelude::*;
+use leptos::{prelude::*, reactive::spawn_local};
 use leptos_meta::Title;
 use leptos_router::components::Outlet;
 
-use crate::components::sidebar::Sidebar;
+use crate::{
+    BoardConfig, User,
+    api::ApiClient,
+    components::{login::Login, sidebar::Sidebar},
+    use_board_config, use_user_signal,
+};
 
 #[component]
 pub fn Layout() -> impl IntoView {
     let formatter = |text| format!("{text} — Apalis Board");
+    let user = use_user_signal();
+    let config = use_board_config();
+    // Resume the stored session, or learn that the board is open.
+    let checked = RwSignal::new(false);
+    spawn_local(async move {
+        if let Ok(me) = ApiClient::get::<User>("/me").await {
+            user.set(Some(me));
+        }
+        checked.set(true);
+    });
+    Effect::new(move |_| {
+        if user.with(Option::is_some) {
+            spawn_local(async move {
+                if let Ok(board) = ApiClient::get::<BoardConfig>("/config").await {
+                    config.set(board);
+                }
+            });
+        }
+    });
+
     view! {
-        <div class="grid grid-cols-[14rem_1fr]">
-            <Title formatter />
-            <Sidebar />
-            <main class="flex flex-col h-screen">
-                <Outlet />
-            </main>
-        </div>
+        <Title formatter />
+        <Show
+            when=move || user.with(Option::is_some)
+            fallback=move || checked.get().then(|| view! { <Login /> })
+        >
+            <div class="grid grid-cols-[14rem_1fr]">
+                <Sidebar />
+                <main class="flex flex-col h-screen">
+                    <Outlet />
+                </main>
+            </div>
+        </Show>
     }
 }
@@ -0,0 +1,120 @@
+use leptos::{prelude::*, reactive::spawn_local};
+
+use crate::{
+    User,
+    api::ApiClient,
+    auth::{basic, bearer, set_authorization},
+    use_user_signal,
+};
+
+const INPUT_CLASS: &str = "w-full bg-charcoal-900 border border-charcoal-700 px-2 py-1 rounded-sm text-gray-200 placeholder-charcoal-500 focus:outline-none focus:border-charcoal-500 text-sm";
+
+/// Sign in with a token or a username and password.
+///
+/// The credentials are checked against `/me` before they are kept.
+#[component]
+pub fn Login() -> impl IntoView {
+    let user = use_user_signal();
+    let use_token = RwSignal::new(true);
+    let token = RwSignal::new(String::new());
+    let username = RwSignal::new(String::new());
+    let password = RwSignal::new(String::new());
+    let error = RwSignal::new(None::<String>);
+    let pending = RwSignal::new(false);
+
+    let on_submit = move |ev: leptos::ev::SubmitEvent| {
+        ev.prevent_default();
+        let authorization = if use_token.get_untracked() {
+            Some(bearer(&token.get_untracked()))
+        } else {
+            basic(&username.get_untracked(), &password.get_untracked())
+        };
+        set_authorization(authorization.as_deref());
+        pending.set(true);
+        spawn_local(async move {
+            match ApiClient::get::<User>("/me").await {
+                Ok(me) => {
+                    error.set(None);
+                    user.set(Some(me));
+                }
+                Err(e) => {
+                    set_authorization(None);
+                    error.set(Some(e));
+                }
+            }
+            pending.set(false);
+        });
+    };
+
+    let tab = move |token_tab: bool, label: &'static str| {
+        view! {
+            <button
+                type="button"
+                class=move || {
+                    if use_token.get() == token_tab {
+                        "px-2 py-1 text-xs text-text-bright border-b border-primary"
+                    } else {
+                        "px-2 py-1 text-xs text-text-dimmed hover:text-text-bright"
+                    }
+                }
+                on:click=move |_| use_token.set(token_tab)
+            >
+                {label}
+            </button>
+        }
+    };
+
+    view! {
+        <div class="flex items-center justify-center h-screen bg-background-bright">
+            <form
+                class="flex flex-col gap-3 w-80 p-6 border border-grid-bright rounded-md"
+                on:submit=on_submit
+            >
+                <h3 class="font-sans text-sm font-medium text-text-bright">
+                    "Sign in to Apalis Board"
+                </h3>
+                <div class="flex gap-2 border-b border-grid-bright">
+                    {tab(true, "Token")} {tab(false, "Password")}
+                </div>
+                <Show
+                    when=move || use_token.get()
+                    fallback=move || {
+                        view! {
+                            <input
+                                class=INPUT_CLASS
+                                placeholder="Username"
+                                autocomplete="username"
+                                prop:value=username
+                                on:input=move |ev| username.set(event_target_value(&ev))
+                            />
+                            <input
+                                type="password"
+                                class=INPUT_CLASS
+                                placeholder="Password"
+                                autocomplete="current-password"
+                                prop:value=password
+                                on:input=move |ev| password.set(event_target_value(&ev))
+                            />
+                        }
+                    }
+                >
+                    <input
+                        type="password"
+                        class=INPUT_CLASS
+                        placeholder="Token"
+                        prop:value=token
+                        on:input=move |ev| token.set(event_target_value(&ev))
+                    />
+                </Show>
+                <button
+                    type="submit"
+                    class="rounded-md bg-primary px-2.5 py-1 text-xs font-medium text-background-bright hover:bg-primary/80 disabled:opacity-50"
+                    disabled=pending
+                >
+                    "Sign in"
+                </button>
+                {move || error.get().map(|e| view! { <span class="text-xs text-error">{e}</span> })}
+            </form>
+        </div>
+    }
+}
@@ -2,8 +2,10 @@ use leptos::prelude::*;
 
 use crate::relative_timestamp;
 
+pub mod backend_filter;
 pub mod icon;
 pub mod layout;
+pub mod login;
 pub mod not_found;
 pub mod pagination;
 pub mod sidebar;
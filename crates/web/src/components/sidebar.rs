@@ -1,7 +1,10 @@
 use leptos::{ev::MouseEvent, prelude::*};
 use leptos_router::{components::A, hooks::use_location};
 
-use crate::use_sse_provider;
+use crate::{
+    auth::{authorization, set_authorization},
+    use_sse_provider, use_user_signal,
+};
 
 #[component]
 pub fn Sidebar() -> impl IntoView {
@@ -11,6 +14,11 @@ pub fn Sidebar() -> impl IntoView {
     let location = use_location();
 
     let sse = use_sse_provider();
+    let user = use_user_signal();
+    let sign_out = move |_| {
+        set_authorization(None);
+        user.set(None);
+    };
 
     let is_healthy = move || {
         sse.is_healthy()
@@ -137,6 +145,12 @@ pub fn Sidebar() -> impl IntoView {
                                     text="Logs"
                                     is_active=location.pathname.get().starts_with("/logs")
                                 />
+                                <NavItem
+                                    href="/audit"
+                                    icon=audit_icon()
+                                    text="Audit"
+                                    is_active=location.pathname.get().starts_with("/audit")
+                                />
                                 <NavItem
                                     href="/settings"
                                     icon=settings_icon()
@@ -151,6 +165,26 @@ pub fn Sidebar() -> impl IntoView {
 
             // Footer
             <div class="font-sans text-xs text-text-dimmed">
+                {move || {
+                    // Open boards sign everyone in as anonymous, there is nothing to sign out of.
+                    user.get()
+                        .filter(|_| authorization().is_some())
+                        .map(|user| {
+                            view! {
+                                <div class="flex items-center justify-between border-t border-grid-bright px-3 py-1">
+                                    <span class="text-sm text-text-bright truncate">
+                                        {user.username}
+                                    </span>
+                                    <button
+                                        class="text-xs text-muted-foreground hover:text-text-bright"
+                                        on:click=sign_out
+                                    >
+                                        "Sign out"
+                                    </button>
+                                </div>
+                            }
+                        })
+                }}
                 <div class="flex flex-col gap-1 border-t border-grid-bright p-1">
                     <div class="flex flex-col w-full mx-2">
                         <div class="flex gap-2 text-sm">
@@ -392,7 +426,25 @@ pub fn logs_icon() -> impl IntoView {
     }
 }
 
-fn settings_icon() -> impl IntoView {
+pub fn audit_icon() -> impl IntoView {
+    view! {
+        <svg
+            xmlns="http://www.w3.org/2000/svg"
+            viewBox="0 0 20 20"
+            fill="currentColor"
+            aria-hidden="true"
+            class="h-[1.125rem] text-text-dimmed shrink-0 justify-start"
+        >
+            <path
+                fill-rule="evenodd"
+                d="M9.661 2.237a.531.531 0 0 1 .678 0 11.947 11.947 0 0 0 7.078 2.749.5.5 0 0 1 .479.425c.069.52.104 1.05.104 1.59 0 5.162-3.26 9.563-7.834 11.256a.48.48 0 0 1-.332 0C5.26 16.564 2 12.163 2 7c0-.538.035-1.069.104-1.589a.5.5 0 0 1 .48-.425 11.947 11.947 0 0 0 7.077-2.75Zm4.196 5.954a.75.75 0 0 0-1.214-.882l-3.483 4.79-1.88-1.88a.75.75 0 1 0-1.06 1.061l2.5 2.5a.75.75 0 0 0 1.137-.089l4-5.5Z"
+                clip-rule="evenodd"
+            ></path>
+        </svg>
+    }
+}
+
+pub fn settings_icon() -> impl IntoView {
     view! {
         <svg
             xmlns="http://www.w3.org/2000/svg"
@@ -1,4 +1,10 @@
-//! Compile-time configuration for the web UI.
+//! Configuration for the web UI.
+//!
+//! # Runtime
+//!
+//! `ServeUI` writes a [`UiConfig`] into `index.html`, so a prebuilt frontend can be served
+//! under any path. [`ui_config`] reads it, falling back to the compile-time defaults below
+//! when the page has none, e.g. under `trunk serve`.
 //!
 //! # API base
 //!
@@ -16,7 +22,10 @@
 //! `Trunk.toml` or pass `--public-url` without exporting `TRUNK_BUILD_PUBLIC_URL`, the WASM
 //! router base may still default to `"/"` unless you also export this variable to the same value.
 
-use std::borrow::Cow;
+use std::{borrow::Cow, sync::OnceLock};
+
+use apalis_board_types::UiConfig;
+use leptos::prelude::document;
 
 pub const API_PATH: &str = match option_env!("APALIS_BOARD_API_PATH") {
     Some(path) => path,
@@ -28,10 +37,31 @@ pub const PUBLIC_URL: &str = match option_env!("TRUNK_BUILD_PUBLIC_URL") {
     None => "/",
 };
 
+/// The config written into `index.html`, read once.
+pub fn ui_config() -> &'static UiConfig {
+    static CONFIG: OnceLock<UiConfig> = OnceLock::new();
+    CONFIG.get_or_init(|| {
+        document()
+            .get_element_by_id(UiConfig::ELEMENT_ID)
+            .and_then(|element| element.text_content())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_else(|| UiConfig {
+                api_path: API_PATH.to_owned(),
+                public_url: PUBLIC_URL.to_owned(),
+            })
+    })
+}
+
+/// The prefix of the API routes.
+#[must_use]
+pub fn api_path() -> &'static str {
+    &ui_config().api_path
+}
+
 /// [`leptos_router::components::Router`] `base` value (empty at `/`).
 #[must_use]
 pub fn router_base() -> Cow<'static, str> {
-    match PUBLIC_URL.trim_end_matches('/') {
+    match ui_config().public_url.trim_end_matches('/') {
         "" | "/" => Cow::Borrowed(""),
         path => Cow::Borrowed(path),
     }
@@ -1,11 +1,11 @@
 #![doc = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/README.md"))]
 
-use apalis_board_types::LogEntry;
+use apalis_board_types::{LogEntry, Permission};
 use chrono::{DateTime, Local, Utc};
 use leptos::{prelude::*, reactive::spawn_local};
-use serde::{Deserialize, Serialize};
 
 pub mod api;
+pub mod auth;
 pub mod components;
 pub mod config;
 pub mod pages;
@@ -17,17 +17,31 @@ pub mod locales {
     leptos_i18n::load_locales!();
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
-pub struct User {
-    pub id: String,
-    pub username: String,
-}
+pub use apalis_board_types::{BoardConfig, User};
 
 type UserSignal = RwSignal<Option<User>>;
 pub fn use_user_signal() -> UserSignal {
     use_context::<UserSignal>().expect("UserSignal")
 }
 
+/// How the board is set up, loaded from `/config`.
+pub fn use_board_config() -> RwSignal<BoardConfig> {
+    use_context::<RwSignal<BoardConfig>>().expect("BoardConfig")
+}
+
+/// Whether the signed in user may perform `permission` on `queue`.
+///
+/// Used to hide the actions the API would reject.
+pub fn use_can(permission: Permission, queue: Signal<Option<String>>) -> Signal<bool> {
+    let user = use_user_signal();
+    Signal::derive(move || {
+        user.with(|user| {
+            user.as_ref()
+                .is_some_and(|user| queue.with(|queue| user.can(permission, queue.as_deref())))
+        })
+    })
+}
+
 pub fn use_sse_signal() -> RwSignal<Vec<String>> {
     use_context::<RwSignal<Vec<String>>>().expect("SSE Signal")
 }
@@ -38,6 +52,15 @@ pub struct SseProvider {
     is_healthy: RwSignal<bool>,
 }
 
+impl Default for SseProvider {
+    fn default() -> Self {
+        Self {
+            event_source: RwSignal::new(LogEntry::default()),
+            is_healthy: RwSignal::new(true),
+        }
+    }
+}
+
 impl SseProvider {
     pub fn event_source(&self) -> RwSignal<LogEntry> {
         self.event_source
@@ -46,6 +69,41 @@ impl SseProvider {
     pub fn is_healthy(&self) -> RwSignal<bool> {
         self.is_healthy
     }
+
+    /// Stream the log entries of `url` until the current reactive owner is cleaned up.
+    pub fn connect(&self, url: &str) {
+        use futures::{StreamExt, future::abortable};
+        let data = self.event_source;
+        let is_healthy = self.is_healthy;
+        let mut source = gloo_net::eventsource::futures::EventSource::new(url)
+            .expect("couldn't connect to SSE stream");
+        let mut stream = source
+            .subscribe("message")
+            .unwrap()
+            .map(|value| match value {
+                Ok(value) => Ok(serde_json::from_str::<LogEntry>(
+                    &value.1.data().as_string().expect("expected string value"),
+                )
+                .expect("couldn't parse message")),
+                Err(e) => Err(e),
+            });
+        is_healthy.set(true);
+        let (task, handle) = abortable(async move {
+            while let Some(next_value) = stream.next().await {
+                if let Ok(log_entry) = next_value {
+                    data.set(log_entry);
+                } else {
+                    is_healthy.set(false);
+                    source.close();
+                    break;
+                }
+            }
+        });
+        spawn_local(async move {
+            let _ = task.await;
+        });
+        on_cleanup(move || handle.abort());
+    }
 }
 
 pub fn use_sse_provider() -> SseProvider {
@@ -53,36 +111,9 @@ pub fn use_sse_provider() -> SseProvider {
 }
 
 pub fn create_sse_resource(url: &str) -> SseProvider {
-    use futures::StreamExt;
-    let data = RwSignal::new(LogEntry::default());
-    let mut source = gloo_net::eventsource::futures::EventSource::new(url)
-        .expect("couldn't connect to SSE stream");
-    let mut stream = source
-        .subscribe("message")
-        .unwrap()
-        .map(|value| match value {
-            Ok(value) => Ok(serde_json::from_str::<LogEntry>(
-                &value.1.data().as_string().expect("expected string value"),
-            )
-            .expect("couldn't parse message")),
-            Err(e) => Err(e),
-        });
-    let is_healthy = RwSignal::new(true);
-    spawn_local(async move {
-        while let Some(next_value) = stream.next().await {
-            if let Ok(log_entry) = next_value {
-                data.set(log_entry);
-            } else {
-                is_healthy.set(false);
-                source.close();
-                break;
-            }
-        }
-    });
-    SseProvider {
-        event_source: data,
-        is_healthy,
-    }
+    let provider = SseProvider::default();
+    provider.connect(url);
+    provider
 }
 
 pub fn relative_timestamp(timestamp: u64) -> String {
@@ -1,15 +1,18 @@
+use apalis_board_web::auth::{authorization, with_authorization};
 use apalis_board_web::components::layout::Layout;
 use apalis_board_web::components::not_found::NotFound;
-use apalis_board_web::config::{API_PATH, router_base};
-use apalis_board_web::create_sse_resource;
+use apalis_board_web::config::{api_path, router_base};
+use apalis_board_web::pages::audit::AuditPage;
 use apalis_board_web::pages::home::Home;
 use apalis_board_web::pages::logs::LogsPage;
 use apalis_board_web::pages::queues::index::QueuePage;
 use apalis_board_web::pages::queues::single::SingleQueuePage;
 use apalis_board_web::pages::queues::status::StatusPage;
+use apalis_board_web::pages::settings::SettingsPage;
 use apalis_board_web::pages::tasks::index::AllTasksPage;
 use apalis_board_web::pages::tasks::single::TaskPage;
 use apalis_board_web::pages::workers::index::WorkersPage;
+use apalis_board_web::{BoardConfig, SseProvider, User};
 use leptos::prelude::*;
 use leptos_router::components::*;
 use leptos_router::path;
@@ -20,9 +23,22 @@ use apalis_board_web::locales::i18n::I18nContextProvider;
 #[component]
 pub fn AppRoutes() -> impl IntoView {
     leptos_meta::provide_meta_context();
-    let events_url = format!("{API_PATH}/events");
-    let sse_provider = create_sse_resource(&events_url);
+    let user = RwSignal::new(None::<User>);
+    provide_context(user);
+    provide_context(RwSignal::new(BoardConfig::default()));
+    let sse_provider = SseProvider::default();
     provide_context(sse_provider);
+    // `EventSource` cannot send headers, so the credentials go in the query string.
+    let signed_in = Memo::new(move |_| user.with(Option::is_some));
+    Effect::new(move |_| {
+        if signed_in.get() {
+            let events_url = with_authorization(
+                &format!("{}/events", api_path()),
+                authorization().as_deref(),
+            );
+            sse_provider.connect(&events_url);
+        }
+    });
 
     view! {
         <I18nContextProvider>
@@ -38,6 +54,8 @@ pub fn AppRoutes() -> impl IntoView {
                         <Route path=path!("/tasks/:status") view=AllTasksPage />
                         <Route path=path!("/workers") view=WorkersPage />
                         <Route path=path!("/logs") view=LogsPage />
+                        <Route path=path!("/audit") view=AuditPage />
+                        <Route path=path!("/settings") view=SettingsPage />
                     </ParentRoute>
                 </Routes>
             </Router>
@@ -0,0 +1,205 @@
+use apalis_board_types::{AuditAction, AuditRecord, Permission};
+use leptos::prelude::*;
+use leptos_meta::Title;
+
+use crate::{
+    api::ApiClient, auth::encode_component, components::sidebar::audit_icon, relative_timestamp,
+    use_can,
+};
+
+const INPUT_CLASS: &str = "bg-charcoal-900 border border-charcoal-700 px-2 py-1 rounded-sm text-gray-200 placeholder-charcoal-500 focus:outline-none focus:border-charcoal-500 text-sm";
+
+const ACTIONS: [AuditAction; 8] = [
+    AuditAction::Push,
+    AuditAction::Retry,
+    AuditAction::Kill,
+    AuditAction::Edit,
+    AuditAction::Purge,
+    AuditAction::Pause,
+    AuditAction::CreateToken,
+    AuditAction::RevokeToken,
+];
+
+/// The `/audit` path filtered by the non-empty fields.
+pub fn audit_path(actor: &str, action: &str, queue: &str) -> String {
+    let params: Vec<_> = [("actor", actor), ("action", action), ("queue", queue)]
+        .into_iter()
+        .map(|(name, value)| (name, value.trim()))
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(name, value)| format!("{name}={}", encode_component(value)))
+        .collect();
+    if params.is_empty() {
+        "/audit".to_owned()
+    } else {
+        format!("/audit?{}", params.join("&"))
+    }
+}
+
+#[component]
+pub fn AuditPage() -> impl IntoView {
+    let can_audit = use_can(Permission::Audit, Signal::stored(None));
+
+    view! {
+        <Title text="Audit" />
+        <div class="flex flex-col h-full w-full">
+            <div class="w-full bg-background-bright border-b border-gray-700 flex items-center h-[2.75rem]">
+                <span class="ml-2 mr-1 rounded p-1 bg-charcoal-700 text-text-bright p-2">
+                    {audit_icon()}
+                </span>
+                <h3 class="text-base font-bold m-2">Audit</h3>
+            </div>
+            <div class="flex-1 overflow-y-auto p-3 scrollbar-thin scrollbar-track-transparent scrollbar-thumb-charcoal-600">
+                <Show
+                    when=move || can_audit.get()
+                    fallback=|| {
+                        view! {
+                            <p class="text-sm text-text-dimmed">
+                                "Only admins can read the audit log."
+                            </p>
+                        }
+                    }
+                >
+                    <AuditLog />
+                </Show>
+            </div>
+        </div>
+    }
+}
+
+/// The filtered audit records, newest first.
+#[component]
+fn AuditLog() -> impl IntoView {
+    let actor = RwSignal::new(String::new());
+    let action = RwSignal::new(String::new());
+    let queue = RwSignal::new(String::new());
+    let records = LocalResource::new(move || {
+        let path = audit_path(&actor.get(), &action.get(), &queue.get());
+        async move { ApiClient::get::<Vec<AuditRecord>>(&path).await }
+    });
+
+    view! {
+        <div class="flex gap-2 mb-3">
+            <input
+                class=INPUT_CLASS
+                placeholder="Actor"
+                prop:value=actor
+                on:change=move |ev| actor.set(event_target_value(&ev))
+            />
+            <select
+                class=INPUT_CLASS
+                prop:value=action
+                on:change=move |ev| action.set(event_target_value(&ev))
+            >
+                <option value="">"All actions"</option>
+                {ACTIONS
+                    .iter()
+                    .map(|a| view! { <option value=a.as_str()>{a.as_str()}</option> })
+                    .collect_view()}
+            </select>
+            <input
+                class=INPUT_CLASS
+                placeholder="Queue"
+                prop:value=queue
+                on:change=move |ev| queue.set(event_target_value(&ev))
+            />
+        </div>
+        <table class="w-full text-sm text-left">
+            <thead class="text-xs text-text-dimmed border-b border-grid-bright">
+                <tr>
+                    <th class="py-1">"When"</th>
+                    <th>"Actor"</th>
+                    <th>"Action"</th>
+                    <th>"Queue"</th>
+                    <th>"Ids"</th>
+                    <th>"Origin"</th>
+                    <th>"Outcome"</th>
+                </tr>
+            </thead>
+            <tbody>
+                {move || match records.get() {
+                    None => {
+                        view! {
+                            <tr>
+                                <td class="py-1 text-text-dimmed">"Loading..."</td>
+                            </tr>
+                        }
+                            .into_any()
+                    }
+                    Some(Err(e)) => {
+                        view! {
+                            <tr>
+                                <td class="py-1 text-error">{e}</td>
+                            </tr>
+                        }
+                            .into_any()
+                    }
+                    Some(Ok(records)) if records.is_empty() => {
+                        view! {
+                            <tr>
+                                <td class="py-1 text-text-dimmed">"No changes recorded"</td>
+                            </tr>
+                        }
+                            .into_any()
+                    }
+                    Some(Ok(records)) => {
+                        records
+                            .into_iter()
+                            .map(|record| {
+                                let user_agent = record.origin.user_agent.unwrap_or_default();
+                                // Tokens may be named like users, show who it really was
+                                let actor_id = (record.actor_id != record.actor)
+                                    .then_some(record.actor_id);
+                                view! {
+                                    <tr class="border-b border-grid-bright">
+                                        <td class="py-1">{relative_timestamp(record.timestamp)}</td>
+                                        <td class="text-text-bright">
+                                            {record.actor}
+                                            <span class="block font-mono text-xs">{actor_id}</span>
+                                        </td>
+                                        <td>{record.action.as_str()}</td>
+                                        <td>{record.queue.unwrap_or_default()}</td>
+                                        <td class="font-mono text-xs">
+                                            {record
+                                                .target
+                                                .unwrap_or_else(|| record.task_ids.join(", "))}
+                                        </td>
+                                        <td title=user_agent>
+                                            {record.origin.address.unwrap_or_default()}
+                                        </td>
+                                        <td>
+                                            {match record.error {
+                                                Some(e) => {
+                                                    view! { <span class="text-error">{e}</span> }
+                                                        .into_any()
+                                                }
+                                                None => {
+                                                    view! { <span class="text-success">"ok"</span> }
+                                                        .into_any()
+                                                }
+                                            }}
+                                        </td>
+                                    </tr>
+                                }
+                            })
+                            .collect_view()
+                            .into_any()
+                    }
+                }}
+            </tbody>
+        </table>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audit_path_skips_empty_filters() {
+        assert_eq!(audit_path("", "", " "), "/audit");
+        assert_eq!(
+            audit_path("ops team", "retry", "payments"),
+            "/audit?actor=ops%20team&action=retry&queue=payments"
+        );
+    }
+}
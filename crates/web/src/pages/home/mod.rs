@@ -4,34 +4,23 @@ use std::{
 };
 
 use apalis_core::backend::{QueueInfo, Statistic};
-use gloo_net::http::Request;
 use leptos::{prelude::*, reactive::spawn_local};
 use leptos_meta::Title;
 use leptos_router::components::A;
 use serde::Serialize;
 
-use crate::{config::API_PATH, locales::i18n::*, relative_timestamp, translate::KnownStatistic};
+use crate::{api::ApiClient, locales::i18n::*, relative_timestamp, translate::KnownStatistic};
 
 pub fn resolve_json<V: Serialize>(val: V) -> String {
     serde_json::to_string_pretty(&val).unwrap()
 }
 
 async fn queue_list() -> Result<Vec<QueueInfo>, String> {
-    let res = Request::get(&format!("{API_PATH}/queues"))
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-    let queues: Vec<QueueInfo> = res.json().await.map_err(|e| e.to_string())?;
-    Ok(queues)
+    ApiClient::get("/queues").await
 }
 
 async fn overview() -> Result<Vec<Statistic>, String> {
-    let res = Request::get(&format!("{API_PATH}/overview"))
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-    let stats: Vec<Statistic> = res.json().await.map_err(|e| e.to_string())?;
-    Ok(stats)
+    ApiClient::get("/overview").await
 }
 
 #[component]
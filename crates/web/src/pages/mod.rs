@@ -1,5 +1,7 @@
+pub mod audit;
 pub mod home;
 pub mod logs;
 pub mod queues;
+pub mod settings;
 pub mod tasks;
 pub mod workers;
@@ -7,7 +7,7 @@ use leptos_router::{components::A, hooks::use_params_map};
 use leptos_struct_table::*;
 
 use crate::{
-    components::{pagination::Paginator, sidebar::queues_icon},
+    components::{backend_filter::BackendFilter, pagination::Paginator, sidebar::queues_icon},
     pages::queues::{CustomTableRowRenderer, provider::QueueProvider},
 };
 
@@ -53,7 +53,8 @@ pub fn SearchBox() -> impl IntoView {
 
 #[component]
 pub fn QueuePage() -> impl IntoView {
-    let rows = QueueProvider::new();
+    let backend = RwSignal::new(String::new());
+    let rows = QueueProvider::new(backend.into());
     let pagination_controller = PaginationController::default();
     view! {
         <Title text="Queues" />
@@ -85,6 +86,7 @@ pub fn QueuePage() -> impl IntoView {
             </div>
             <div class="flex items-center gap-1 p-2 border-b border-gray-700">
                 <SearchBox />
+                <BackendFilter backend />
             </div>
             <div class="flex-1 overflow-hidden overflow-y-auto scrollbar-thin scrollbar-track-transparent scrollbar-thumb-charcoal-600 hover:scrollbar-thumb-charcoal-500 w-full">
                 <table class="whitespace-nowrap transition-all duration-200 w-full">
@@ -8,6 +8,7 @@ use leptos_struct_table::{EventHandler, TableRow};
 use serde::{Deserialize, Serialize};
 
 pub mod index;
+pub mod new_task;
 pub mod provider;
 pub mod single;
 pub mod status;
@@ -18,6 +19,9 @@ pub struct Queue {
     /// Unique identifier for the queue
     #[table(renderer = "TitleRenderer")]
     pub name: String,
+    /// The backend the queue is stored in
+    #[serde(default)]
+    pub backend: String,
     /// The stats of the queue
     #[table(renderer = "StatsCellRenderer")]
     pub stats: Vec<Statistic>,
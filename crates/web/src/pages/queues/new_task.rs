@@ -0,0 +1,368 @@
+use leptos::{prelude::*, reactive::spawn_local};
+use serde_json::{Map, Value};
+
+use crate::api::ApiClient;
+
+/// How a field of the task arguments is edited.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldKind {
+    Text,
+    Integer,
+    Number,
+    Boolean,
+    Select(Vec<String>),
+    /// Anything without a dedicated input is edited as raw JSON.
+    Json,
+}
+
+/// A top level property of the arguments schema.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub label: String,
+    pub description: Option<String>,
+    pub kind: FieldKind,
+    pub required: bool,
+    pub default: String,
+}
+
+/// Follow a local `$ref` to `$defs` or `definitions`.
+fn resolve<'a>(root: &'a Value, schema: &'a Value) -> &'a Value {
+    schema
+        .get("$ref")
+        .and_then(Value::as_str)
+        .and_then(|r| r.strip_prefix('#'))
+        .and_then(|pointer| root.pointer(pointer))
+        .unwrap_or(schema)
+}
+
+fn field_kind(schema: &Value) -> FieldKind {
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        let options: Vec<String> = options
+            .iter()
+            .filter_map(|o| o.as_str().map(ToOwned::to_owned))
+            .collect();
+        if !options.is_empty() {
+            return FieldKind::Select(options);
+        }
+    }
+    // `Option<T>` is described as `["T", "null"]`
+    let ty = match schema.get("type") {
+        Some(Value::String(ty)) => Some(ty.as_str()),
+        Some(Value::Array(types)) => types
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|t| *t != "null"),
+        _ => None,
+    };
+    match ty {
+        Some("string") => FieldKind::Text,
+        Some("integer") => FieldKind::Integer,
+        Some("number") => FieldKind::Number,
+        Some("boolean") => FieldKind::Boolean,
+        _ => FieldKind::Json,
+    }
+}
+
+/// List the editable fields of an object schema, or `None` if the arguments are not an object.
+pub fn fields_from_schema(root: &Value) -> Option<Vec<Field>> {
+    let schema = resolve(root, root);
+    let properties = schema.get("properties")?.as_object()?;
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|r| r.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    let fields = properties
+        .iter()
+        .map(|(name, property)| {
+            let property = resolve(root, property);
+            let kind = field_kind(property);
+            let default = match (property.get("default"), &kind) {
+                (Some(Value::String(s)), _) => s.clone(),
+                (Some(value), _) => value.to_string(),
+                (None, FieldKind::Boolean) => "false".to_owned(),
+                (None, FieldKind::Select(options)) => options[0].clone(),
+                (None, _) => String::new(),
+            };
+            Field {
+                name: name.clone(),
+                label: property
+                    .get("title")
+                    .and_then(Value::as_str)
+                    .unwrap_or(name)
+                    .to_owned(),
+                description: property
+                    .get("description")
+                    .and_then(Value::as_str)
+                    .map(ToOwned::to_owned),
+                required: required.contains(&name.as_str()),
+                kind,
+                default,
+            }
+        })
+        .collect();
+    Some(fields)
+}
+
+/// Build the task arguments from the values entered in the form.
+pub fn build_payload(fields: &[Field], values: &[String]) -> Result<Value, String> {
+    let mut payload = Map::new();
+    for (field, value) in fields.iter().zip(values) {
+        let value = value.trim();
+        if value.is_empty() && field.kind != FieldKind::Text {
+            if field.required {
+                return Err(format!("`{}` is required", field.name));
+            }
+            continue;
+        }
+        let value = match &field.kind {
+            FieldKind::Text | FieldKind::Select(_) => Value::String(value.to_owned()),
+            FieldKind::Integer => value
+                .parse::<i64>()
+                .map(Value::from)
+                .map_err(|_| format!("`{}` must be an integer", field.name))?,
+            FieldKind::Number => value
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(Value::Number)
+                .ok_or_else(|| format!("`{}` must be a number", field.name))?,
+            FieldKind::Boolean => Value::Bool(value == "true"),
+            FieldKind::Json => serde_json::from_str(value)
+                .map_err(|e| format!("`{}` is not valid JSON: {e}", field.name))?,
+        };
+        payload.insert(field.name.clone(), value);
+    }
+    Ok(Value::Object(payload))
+}
+
+const INPUT_CLASS: &str = "w-full bg-charcoal-900 border border-charcoal-700 px-2 py-1 rounded-sm text-gray-200 placeholder-charcoal-500 focus:outline-none focus:border-charcoal-500 text-sm";
+
+#[component]
+fn FieldInput(field: Field, value: RwSignal<String>) -> impl IntoView {
+    let input = match field.kind {
+        FieldKind::Boolean => view! {
+            <input
+                type="checkbox"
+                prop:checked=move || value.get() == "true"
+                on:change=move |ev| value.set(event_target_checked(&ev).to_string())
+            />
+        }
+        .into_any(),
+        FieldKind::Select(options) => view! {
+            <select
+                class=INPUT_CLASS
+                prop:value=value
+                on:change=move |ev| value.set(event_target_value(&ev))
+            >
+                {options
+                    .into_iter()
+                    .map(|o| view! { <option value=o.clone()>{o.clone()}</option> })
+                    .collect_view()}
+            </select>
+        }
+        .into_any(),
+        FieldKind::Json => view! {
+            <textarea
+                class=format!("{INPUT_CLASS} font-mono")
+                rows="3"
+                placeholder="JSON"
+                prop:value=value
+                on:input=move |ev| value.set(event_target_value(&ev))
+            ></textarea>
+        }
+        .into_any(),
+        kind => {
+            let ty = if matches!(kind, FieldKind::Text) {
+                "text"
+            } else {
+                "number"
+            };
+            view! {
+                <input
+                    type=ty
+                    step=if matches!(kind, FieldKind::Number) { "any" } else { "1" }
+                    class=INPUT_CLASS
+                    prop:value=value
+                    on:input=move |ev| value.set(event_target_value(&ev))
+                />
+            }
+            .into_any()
+        }
+    };
+    view! {
+        <label class="flex flex-col gap-1 text-sm">
+            <span class="text-text-bright">
+                {field.label}
+                {field.required.then_some(view! { <span class="text-error">" *"</span> })}
+            </span>
+            {input}
+            {field.description.map(|d| view! { <span class="text-xs text-text-dimmed">{d}</span> })}
+        </label>
+    }
+}
+
+/// A form that pushes a task to a queue, rendered from the queue's arguments schema.
+///
+/// Without a schema the arguments are entered as raw JSON.
+#[component]
+pub fn NewTaskForm(#[prop(into)] queue: Signal<String>, schema: Option<Value>) -> impl IntoView {
+    let fields = schema.as_ref().and_then(fields_from_schema);
+    let values: Vec<RwSignal<String>> = match &fields {
+        Some(fields) => fields
+            .iter()
+            .map(|f| RwSignal::new(f.default.clone()))
+            .collect(),
+        None => vec![RwSignal::new("{}".to_owned())],
+    };
+    let result = RwSignal::new(None::<Result<String, String>>);
+    let pending = RwSignal::new(false);
+
+    let on_submit = {
+        let fields = fields.clone();
+        let values = values.clone();
+        move |ev: leptos::ev::SubmitEvent| {
+            ev.prevent_default();
+            let entered: Vec<String> = values.iter().map(|v| v.get_untracked()).collect();
+            let payload = match &fields {
+                Some(fields) => build_payload(fields, &entered),
+                None => serde_json::from_str::<Value>(&entered[0]).map_err(|e| e.to_string()),
+            };
+            let payload = match payload {
+                Ok(payload) => payload,
+                Err(e) => {
+                    result.set(Some(Err(e)));
+                    return;
+                }
+            };
+            let queue = queue.get_untracked();
+            pending.set(true);
+            spawn_local(async move {
+                let res = ApiClient::put(&format!("/queues/{queue}/tasks"), &payload).await;
+                result.set(Some(res.map(|_| format!("Task pushed to {queue}"))));
+                pending.set(false);
+            });
+        }
+    };
+
+    let inputs = match fields {
+        Some(fields) => fields
+            .into_iter()
+            .zip(values)
+            .map(|(field, value)| view! { <FieldInput field value /> })
+            .collect_view()
+            .into_any(),
+        None => {
+            let value = values[0];
+            view! {
+                <label class="flex flex-col gap-1 text-sm">
+                    <span class="text-text-bright">"Arguments"</span>
+                    <textarea
+                        class=format!("{INPUT_CLASS} font-mono")
+                        rows="8"
+                        prop:value=value
+                        on:input=move |ev| value.set(event_target_value(&ev))
+                    ></textarea>
+                    <span class="text-xs text-text-dimmed">
+                        "This queue has no schema, enter the task arguments as JSON."
+                    </span>
+                </label>
+            }
+            .into_any()
+        }
+    };
+
+    view! {
+        <form class="flex flex-col gap-3 p-3 border-b border-gray-700 max-w-xl" on:submit=on_submit>
+            {inputs}
+            <div class="flex items-center gap-3">
+                <button
+                    type="submit"
+                    class="rounded-md bg-primary px-2.5 py-1 text-xs font-medium text-background-bright hover:bg-primary/80 disabled:opacity-50"
+                    disabled=pending
+                >
+                    "Push task"
+                </button>
+                {move || {
+                    result
+                        .get()
+                        .map(|res| match res {
+                            Ok(msg) => view! { <span class="text-xs text-success">{msg}</span> }.into_any(),
+                            Err(e) => view! { <span class="text-xs text-error">{e}</span> }.into_any(),
+                        })
+                }}
+            </div>
+        </form>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn email_schema() -> Value {
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": "Email",
+            "type": "object",
+            "properties": {
+                "to": { "type": "string" },
+                "index": { "type": "integer", "format": "uint", "minimum": 0 },
+                "priority": { "$ref": "#/$defs/Priority" },
+                "cc": { "type": ["array", "null"], "items": { "type": "string" } }
+            },
+            "required": ["to", "index", "priority"],
+            "$defs": {
+                "Priority": { "type": "string", "enum": ["Low", "High"] }
+            }
+        })
+    }
+
+    #[test]
+    fn test_fields_follow_schema() {
+        let fields = fields_from_schema(&email_schema()).unwrap();
+        let kinds: Vec<_> = fields.iter().map(|f| (f.name.as_str(), &f.kind)).collect();
+        assert!(kinds.contains(&("to", &FieldKind::Text)));
+        assert!(kinds.contains(&("index", &FieldKind::Integer)));
+        assert!(kinds.contains(&(
+            "priority",
+            &FieldKind::Select(vec!["Low".to_owned(), "High".to_owned()])
+        )));
+        assert!(kinds.contains(&("cc", &FieldKind::Json)));
+        assert!(fields_from_schema(&json!({ "type": "string" })).is_none());
+    }
+
+    #[test]
+    fn test_payload_is_typed() {
+        let fields = fields_from_schema(&email_schema()).unwrap();
+        let value_of = |name: &str| match name {
+            "to" => "a@b.c",
+            "index" => "3",
+            "priority" => "High",
+            _ => "",
+        };
+        let values: Vec<String> = fields
+            .iter()
+            .map(|f| value_of(&f.name).to_owned())
+            .collect();
+        assert_eq!(
+            build_payload(&fields, &values).unwrap(),
+            json!({ "to": "a@b.c", "index": 3, "priority": "High" })
+        );
+
+        let values: Vec<String> = fields
+            .iter()
+            .map(|f| {
+                if f.name == "index" {
+                    "x".to_owned()
+                } else {
+                    value_of(&f.name).to_owned()
+                }
+            })
+            .collect();
+        assert!(build_payload(&fields, &values).is_err());
+    }
+}
@@ -1,18 +1,15 @@
+use leptos::prelude::{GetUntracked, Signal, Track};
 use leptos_struct_table::PaginatedTableDataProvider;
 
-use crate::{api::ApiClient, pages::queues::Queue};
+use crate::{api::ApiClient, components::backend_filter::with_backend, pages::queues::Queue};
 
-pub struct QueueProvider {}
-
-impl Default for QueueProvider {
-    fn default() -> Self {
-        Self::new()
-    }
+pub struct QueueProvider {
+    backend: Signal<String>,
 }
 
 impl QueueProvider {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(backend: Signal<String>) -> Self {
+        Self { backend }
     }
 }
 
@@ -20,11 +17,16 @@ impl PaginatedTableDataProvider<Queue, usize> for QueueProvider {
     const PAGE_ROW_COUNT: usize = 100;
 
     async fn get_page(&self, _: usize) -> Result<Vec<Queue>, String> {
-        let resp: Vec<Queue> = ApiClient::get("/queues").await.map_err(|e| e.to_string())?;
+        let path = with_backend("/queues", &self.backend.get_untracked());
+        let resp: Vec<Queue> = ApiClient::get(&path).await.map_err(|e| e.to_string())?;
         Ok(resp)
     }
 
     async fn row_count(&self) -> Option<usize> {
         Some(1)
     }
+
+    fn track(&self) {
+        self.backend.track();
+    }
 }
@@ -3,9 +3,14 @@ use leptos_meta::Title;
 use leptos_router::hooks::use_params_map;
 use leptos_struct_table::*;
 
+use apalis_board_types::Permission;
+
+use crate::api::ApiClient;
 use crate::components::sidebar::queues_icon;
 use crate::pages::queues::index::QueueNav;
+use crate::pages::queues::new_task::NewTaskForm;
 use crate::pages::workers::provider::WorkerProvider;
+use crate::{use_board_config, use_can};
 
 #[component]
 pub fn SingleQueuePage() -> impl IntoView {
@@ -14,6 +19,14 @@ pub fn SingleQueuePage() -> impl IntoView {
 
     // Make rows reactive by using a closure
     let rows = WorkerProvider::new(queue);
+    let show_form = RwSignal::new(false);
+    let can_push = use_can(Permission::Push, Signal::derive(move || Some(queue.get())));
+    let config = use_board_config();
+    let read_only = move || config.with(|c| c.read_only);
+    let schema = LocalResource::new(move || {
+        let url = format!("/queues/{}/schema", queue.get());
+        async move { ApiClient::get::<serde_json::Value>(&url).await.ok() }
+    });
     // let pagination_controller = PaginationController::default();
     view! {
         <Title text=move || format!("Queue - {}", queue.get()) />
@@ -22,8 +35,24 @@ pub fn SingleQueuePage() -> impl IntoView {
                 <span class="ml-2 mr-1 rounded p-1 bg-charcoal-700 text-text-bright p-2">
                     {queues_icon()}
                 </span>
-                <h3 class="text-base font-bold m-2">{queue}</h3>
+                <h3 class="text-base font-bold m-2 flex-1">{queue}</h3>
+                <Show when=move || can_push.get()>
+                    <button
+                        class="mr-4 rounded-md border border-transparent bg-primary px-2.5 py-1 text-xs font-medium text-background-bright shadow-sm hover:bg-primary/80 transition-colors cursor-pointer disabled:opacity-50 disabled:cursor-not-allowed"
+                        disabled=read_only
+                        title=move || read_only().then_some("The board is read-only")
+                        on:click=move |_| show_form.update(|open| *open = !*open)
+                    >
+                        {move || if show_form.get() { "Cancel" } else { "New task" }}
+                    </button>
+                </Show>
             </div>
+            {move || {
+                (show_form.get() && can_push.get() && !read_only())
+                    .then(|| {
+                        schema.get().map(|schema| view! { <NewTaskForm queue schema /> })
+                    })
+            }}
             <div class="flex items-center gap-1 p-2 border-b border-gray-700">
                 <QueueNav />
             </div>
@@ -0,0 +1,290 @@
+use apalis_board_types::{ApiToken, CreatedToken, Grant, NewToken, Permission, Role};
+use chrono::Utc;
+use leptos::{prelude::*, reactive::spawn_local};
+use leptos_meta::Title;
+
+use crate::{
+    api::ApiClient, components::sidebar::settings_icon, relative_timestamp, use_board_config,
+    use_can,
+};
+
+const INPUT_CLASS: &str = "w-full bg-charcoal-900 border border-charcoal-700 px-2 py-1 rounded-sm text-gray-200 placeholder-charcoal-500 focus:outline-none focus:border-charcoal-500 text-sm";
+
+/// The expiry of a token valid for `days`, none when left empty.
+pub fn expiry(days: &str, now: u64) -> Result<Option<u64>, String> {
+    let days = days.trim();
+    if days.is_empty() {
+        return Ok(None);
+    }
+    match days.parse::<u64>() {
+        Ok(days) if days > 0 => Ok(Some(now + days * 86_400)),
+        _ => Err("Expiry must be a positive number of days".to_owned()),
+    }
+}
+
+fn role_name(role: Role) -> &'static str {
+    match role {
+        Role::Viewer => "viewer",
+        Role::Operator => "operator",
+        Role::Admin => "admin",
+    }
+}
+
+fn grants_label(grants: &[Grant]) -> String {
+    if grants.is_empty() {
+        return "none".to_owned();
+    }
+    grants
+        .iter()
+        .map(|g| format!("{} on {}", role_name(g.role), g.queues))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[component]
+pub fn SettingsPage() -> impl IntoView {
+    let config = use_board_config();
+    let can_manage = use_can(Permission::ManageTokens, Signal::stored(None));
+
+    view! {
+        <Title text="Settings" />
+        <div class="flex flex-col h-full w-full">
+            <div class="w-full bg-background-bright border-b border-gray-700 flex items-center h-[2.75rem]">
+                <span class="ml-2 mr-1 rounded p-1 bg-charcoal-700 text-text-bright p-2">
+                    {settings_icon()}
+                </span>
+                <h3 class="text-base font-bold m-2">Settings</h3>
+            </div>
+            <div class="flex-1 overflow-y-auto p-3 scrollbar-thin scrollbar-track-transparent scrollbar-thumb-charcoal-600">
+                {move || {
+                    if !config.with(|c| c.tokens) {
+                        view! {
+                            <p class="text-sm text-text-dimmed">
+                                "API tokens are disabled. Enable them with ApiBuilder::tokens."
+                            </p>
+                        }
+                            .into_any()
+                    } else if !can_manage.get() {
+                        view! {
+                            <p class="text-sm text-text-dimmed">
+                                "Only admins can manage API tokens."
+                            </p>
+                        }
+                            .into_any()
+                    } else {
+                        view! { <TokensSection /> }.into_any()
+                    }
+                }}
+            </div>
+        </div>
+    }
+}
+
+/// Mint, list and revoke API tokens.
+#[component]
+fn TokensSection() -> impl IntoView {
+    let read_only = {
+        let config = use_board_config();
+        move || config.with(|c| c.read_only)
+    };
+    let version = RwSignal::new(0_usize);
+    let tokens = LocalResource::new(move || {
+        version.track();
+        async move { ApiClient::get::<Vec<ApiToken>>("/admin/tokens").await }
+    });
+
+    let name = RwSignal::new(String::new());
+    let role = RwSignal::new("operator".to_owned());
+    let queues = RwSignal::new("*".to_owned());
+    let days = RwSignal::new(String::new());
+    let created = RwSignal::new(None::<CreatedToken>);
+    let error = RwSignal::new(None::<String>);
+
+    let on_submit = move |ev: leptos::ev::SubmitEvent| {
+        ev.prevent_default();
+        let expires_at = match expiry(&days.get_untracked(), Utc::now().timestamp() as u64) {
+            Ok(expires_at) => expires_at,
+            Err(e) => {
+                error.set(Some(e));
+                return;
+            }
+        };
+        let role = match role.get_untracked().as_str() {
+            "viewer" => Role::Viewer,
+            "admin" => Role::Admin,
+            _ => Role::Operator,
+        };
+        let request = NewToken {
+            name: name.get_untracked(),
+            grants: vec![Grant::new(role, queues.get_untracked())],
+            expires_at,
+        };
+        spawn_local(async move {
+            match ApiClient::post::<_, CreatedToken>("/admin/tokens", &request).await {
+                Ok(token) => {
+                    error.set(None);
+                    name.set(String::new());
+                    created.set(Some(token));
+                    version.update(|v| *v += 1);
+                }
+                Err(e) => error.set(Some(e)),
+            }
+        });
+    };
+
+    let revoke = move |id: String| {
+        spawn_local(async move {
+            match ApiClient::delete(&format!("/admin/tokens/{id}")).await {
+                Ok(()) => version.update(|v| *v += 1),
+                Err(e) => error.set(Some(e)),
+            }
+        });
+    };
+
+    view! {
+        <section class="flex flex-col gap-3 max-w-3xl">
+            <h4 class="text-sm font-medium text-text-bright">"API tokens"</h4>
+            <p class="text-xs text-text-dimmed">
+                "Tokens let CI jobs and scripts call the API as a bearer token. Only a hash is stored."
+            </p>
+            <Show when=move || !read_only()>
+                <form class="grid grid-cols-[2fr_1fr_1fr_1fr_auto] gap-2 items-end" on:submit=on_submit>
+                    <label class="flex flex-col gap-1 text-xs">
+                        "Name"
+                        <input
+                            class=INPUT_CLASS
+                            placeholder="ci-deploy"
+                            prop:value=name
+                            on:input=move |ev| name.set(event_target_value(&ev))
+                        />
+                    </label>
+                    <label class="flex flex-col gap-1 text-xs">
+                        "Role"
+                        <select
+                            class=INPUT_CLASS
+                            prop:value=role
+                            on:change=move |ev| role.set(event_target_value(&ev))
+                        >
+                            <option value="viewer">"viewer"</option>
+                            <option value="operator">"operator"</option>
+                            <option value="admin">"admin"</option>
+                        </select>
+                    </label>
+                    <label class="flex flex-col gap-1 text-xs">
+                        "Queues"
+                        <input
+                            class=INPUT_CLASS
+                            prop:value=queues
+                            on:input=move |ev| queues.set(event_target_value(&ev))
+                        />
+                    </label>
+                    <label class="flex flex-col gap-1 text-xs">
+                        "Expires in days"
+                        <input
+                            type="number"
+                            min="1"
+                            class=INPUT_CLASS
+                            placeholder="never"
+                            prop:value=days
+                            on:input=move |ev| days.set(event_target_value(&ev))
+                        />
+                    </label>
+                    <button
+                        type="submit"
+                        class="rounded-md bg-primary px-2.5 py-1 text-xs font-medium text-background-bright hover:bg-primary/80"
+                    >
+                        "Create"
+                    </button>
+                </form>
+            </Show>
+            {move || error.get().map(|e| view! { <span class="text-xs text-error">{e}</span> })}
+            {move || {
+                created
+                    .get()
+                    .map(|created| {
+                        view! {
+                            <div class="flex flex-col gap-1 border border-primary rounded-sm p-2 text-xs">
+                                <span class="text-text-bright">
+                                    {format!(
+                                        "Copy the token for `{}` now, it won't be shown again.",
+                                        created.token.name,
+                                    )}
+                                </span>
+                                <code class="font-mono text-sm select-all break-all">
+                                    {created.secret}
+                                </code>
+                            </div>
+                        }
+                    })
+            }}
+            <table class="w-full text-sm text-left">
+                <thead class="text-xs text-text-dimmed border-b border-grid-bright">
+                    <tr>
+                        <th class="py-1">"Name"</th>
+                        <th>"Roles"</th>
+                        <th>"Created by"</th>
+                        <th>"Created"</th>
+                        <th>"Expires"</th>
+                        <th></th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {move || {
+                        match tokens.get() {
+                            None => view! { <tr><td class="py-1 text-text-dimmed">"Loading..."</td></tr> }.into_any(),
+                            Some(Err(e)) => view! { <tr><td class="py-1 text-error">{e}</td></tr> }.into_any(),
+                            Some(Ok(tokens)) => {
+                                tokens
+                                    .into_iter()
+                                    .map(|token| {
+                                        let id = token.id.clone();
+                                        view! {
+                                            <tr class="border-b border-grid-bright">
+                                                <td class="py-1 text-text-bright">{token.name}</td>
+                                                <td>{grants_label(&token.grants)}</td>
+                                                <td>{token.created_by}</td>
+                                                <td>{relative_timestamp(token.created_at)}</td>
+                                                <td>
+                                                    {token
+                                                        .expires_at
+                                                        .map_or_else(|| "never".to_owned(), relative_timestamp)}
+                                                </td>
+                                                <td class="text-right">
+                                                    <Show when=move || !read_only()>
+                                                        <button
+                                                            class="text-xs text-error hover:underline"
+                                                            on:click={
+                                                                let id = id.clone();
+                                                                move |_| revoke(id.clone())
+                                                            }
+                                                        >
+                                                            "Revoke"
+                                                        </button>
+                                                    </Show>
+                                                </td>
+                                            </tr>
+                                        }
+                                    })
+                                    .collect_view()
+                                    .into_any()
+                            }
+                        }
+                    }}
+                </tbody>
+            </table>
+        </section>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expiry_in_days() {
+        assert_eq!(expiry("", 100), Ok(None));
+        assert_eq!(expiry(" 2 ", 100), Ok(Some(100 + 2 * 86_400)));
+        assert!(expiry("0", 100).is_err());
+        assert!(expiry("soon", 100).is_err());
+    }
+}
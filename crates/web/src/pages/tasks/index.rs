@@ -7,7 +7,7 @@ use leptos_router::{components::A, hooks::use_params_map};
 use leptos_struct_table::{DisplayStrategy, PaginationController, SortingMode, TableContent};
 
 use crate::{
-    components::{pagination::Paginator, sidebar::tasks_icon},
+    components::{backend_filter::BackendFilter, pagination::Paginator, sidebar::tasks_icon},
     pages::tasks::provider::TaskProvider,
 };
 
@@ -21,7 +21,8 @@ pub fn AllTasksPage() -> impl IntoView {
                 .unwrap_or_default()
         })
     };
-    let rows = move || TaskProvider::all(status());
+    let backend = RwSignal::new(String::new());
+    let rows = move || TaskProvider::all(status(), backend.into());
     let pagination_controller = PaginationController::default();
     view! {
         <Title text=move || format!("Tasks - {}", status()) />
@@ -33,7 +34,9 @@ pub fn AllTasksPage() -> impl IntoView {
                 <h3 class="text-base font-bold m-2 flex-grow">Tasks</h3>
                 <TaskNav />
             </div>
-            <div class="flex items-center gap-1 p-2 border-b border-gray-700"></div>
+            <div class="flex items-center gap-1 p-2 border-b border-gray-700">
+                <BackendFilter backend />
+            </div>
             <div class="flex-1 overflow-hidden overflow-y-auto scrollbar-thin scrollbar-track-transparent scrollbar-thumb-charcoal-600 hover:scrollbar-thumb-charcoal-500 w-full">
                 <table class="whitespace-nowrap transition-all duration-200 w-full">
                     {move || {
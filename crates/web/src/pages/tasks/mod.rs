@@ -38,6 +38,9 @@ pub struct Task {
     #[table(renderer = "ContextCellRenderer")]
     pub meta: Value,
 
+    /// The backend the task is stored in
+    pub backend: String,
+
     #[table(skip)]
     pub queue: String,
 }
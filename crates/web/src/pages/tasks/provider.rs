@@ -1,21 +1,36 @@
 use apalis_core::{backend::Statistic, task::status::Status};
+use leptos::prelude::{GetUntracked, Signal, Track};
 use leptos_struct_table::{ColumnSort, PaginatedTableDataProvider};
+use serde::Deserialize;
 use std::collections::VecDeque;
 
-use crate::{RawTask, api::ApiClient, pages::tasks::Task};
+use crate::{
+    RawTask, api::ApiClient, components::backend_filter::with_backend, pages::tasks::Task,
+};
+
+/// A listed task, tagged with its backend by `/tasks`.
+#[derive(Deserialize)]
+struct Listed {
+    #[serde(default)]
+    backend: String,
+    #[serde(flatten)]
+    task: RawTask,
+}
 
 pub struct TaskProvider {
     sorting: VecDeque<(usize, ColumnSort)>,
     queue: Option<String>,
     status: Option<Status>,
+    backend: Option<Signal<String>>,
 }
 
 impl TaskProvider {
-    pub fn all(status: Status) -> Self {
+    pub fn all(status: Status, backend: Signal<String>) -> Self {
         Self {
             sorting: VecDeque::new(),
             queue: None,
             status: Some(status),
+            backend: Some(backend),
         }
     }
     pub fn new(queue: String) -> Self {
@@ -23,6 +38,7 @@ impl TaskProvider {
             sorting: VecDeque::new(),
             queue: Some(queue),
             status: None,
+            backend: None,
         }
     }
 
@@ -31,6 +47,7 @@ impl TaskProvider {
             sorting: VecDeque::new(),
             queue: Some(queue),
             status: Some(status),
+            backend: None,
         }
     }
     fn url_sort_param_for_column(&self, column: usize) -> &'static str {
@@ -40,6 +57,7 @@ impl TaskProvider {
             2 => "status",
             3 => "run_at",
             4 => "ctx",
+            5 => "backend",
             _ => "",
         }
     }
@@ -65,7 +83,7 @@ impl TaskProvider {
             None => "".to_string(),
             Some(queue) => format!("/queues/{queue}"),
         };
-        format!(
+        let url = format!(
             "{prefix}/tasks?{sort}&page={}&page_size={}&queue={}&status={}",
             page_index + 1,
             Self::PAGE_ROW_COUNT,
@@ -74,7 +92,9 @@ impl TaskProvider {
                 .as_ref()
                 .map(|s| s.to_string())
                 .unwrap_or("".to_string()),
-        )
+        );
+        let backend = self.backend.map(|b| b.get_untracked()).unwrap_or_default();
+        with_backend(&url, &backend)
     }
 }
 
@@ -83,10 +103,10 @@ impl PaginatedTableDataProvider<Task, usize> for TaskProvider {
 
     async fn get_page(&self, page_index: usize) -> Result<Vec<Task>, String> {
         let url = self.get_url(page_index);
-        let resp: Vec<RawTask> = ApiClient::get(&url).await?;
+        let resp: Vec<Listed> = ApiClient::get(&url).await?;
         let tasks = resp
             .into_iter()
-            .map(|t| Task {
+            .map(|Listed { backend, task: t }| Task {
                 args: t.args,
                 task_id: t.parts.task_id.unwrap().to_string(),
                 attempt: t.parts.attempt.current(),
@@ -104,6 +124,7 @@ impl PaginatedTableDataProvider<Task, usize> for TaskProvider {
                     .unwrap_or("")
                     .to_string(),
                 meta: t.parts.ctx,
+                backend,
             })
             .collect();
         Ok(tasks)
@@ -129,4 +150,30 @@ impl PaginatedTableDataProvider<Task, usize> for TaskProvider {
     fn set_sorting(&mut self, sorting: &VecDeque<(usize, ColumnSort)>) {
         self.sorting = sorting.clone();
     }
+
+    fn track(&self) {
+        if let Some(b) = self.backend {
+            b.track()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use apalis_core::task::builder::TaskBuilder;
+
+    use super::*;
+
+    #[test]
+    fn test_listed_tasks_may_name_their_backend() {
+        let task: RawTask = TaskBuilder::new(serde_json::json!({ "to": "ops" })).build();
+        let mut json = serde_json::to_value(&task).unwrap();
+        let untagged: Listed = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(untagged.backend, "");
+
+        json["backend"] = "SqliteStorage".into();
+        let tagged: Listed = serde_json::from_value(json).unwrap();
+        assert_eq!(tagged.backend, "SqliteStorage");
+        assert_eq!(tagged.task.args["to"], "ops");
+    }
 }
@@ -2,12 +2,13 @@ use leptos::prelude::*;
 use leptos_meta::Title;
 use leptos_struct_table::*;
 
-use crate::components::sidebar::workers_icon;
+use crate::components::{backend_filter::BackendFilter, sidebar::workers_icon};
 use crate::pages::workers::provider::WorkerProvider;
 
 #[component]
 pub fn WorkersPage() -> impl IntoView {
-    let rows = WorkerProvider::all();
+    let backend = RwSignal::new(String::new());
+    let rows = WorkerProvider::all(backend.into());
 
     view! {
         <Title text="Workers" />
@@ -16,7 +17,10 @@ pub fn WorkersPage() -> impl IntoView {
                 <span class="ml-2 mr-1 rounded p-1 bg-charcoal-700 text-text-bright p-2">
                     {workers_icon()}
                 </span>
-                <h3 class="text-base font-bold m-2">Workers</h3>
+                <h3 class="text-base font-bold m-2 flex-grow">Workers</h3>
+                <span class="mr-2">
+                    <BackendFilter backend />
+                </span>
             </div>
             <div class="flex-1 overflow-hidden overflow-y-auto scrollbar-thin scrollbar-track-transparent scrollbar-thumb-charcoal-600 hover:scrollbar-thumb-charcoal-500 w-full">
                 <table class="whitespace-nowrap transition-all duration-200 w-full">
@@ -17,7 +17,7 @@ use crate::components::RelativeTimeRenderer;
 pub struct Worker {
     /// Unique identifier for the worker
     pub id: String,
-    /// Backend of the worker
+    /// The backend the worker was listed by
     pub backend: String,
     /// Timestamp when the worker was started
     #[table(renderer = "RelativeTimeRenderer")]
@@ -1,5 +1,4 @@
-use crate::{config::API_PATH, pages::workers::Worker};
-use gloo_net::http::Request;
+use crate::{api::ApiClient, components::backend_filter::with_backend, pages::workers::Worker};
 use leptos::prelude::{GetUntracked, Signal, Track};
 use leptos_struct_table::{ColumnSort, TableDataProvider};
 use std::{collections::VecDeque, ops::Range};
@@ -7,19 +6,22 @@ use std::{collections::VecDeque, ops::Range};
 pub struct WorkerProvider {
     sorting: VecDeque<(usize, ColumnSort)>,
     queue: Option<Signal<String>>,
+    backend: Option<Signal<String>>,
 }
 
 impl WorkerProvider {
-    pub fn all() -> Self {
+    pub fn all(backend: Signal<String>) -> Self {
         Self {
             sorting: VecDeque::new(),
             queue: None,
+            backend: Some(backend),
         }
     }
     pub fn new(queue: Signal<String>) -> Self {
         Self {
             sorting: VecDeque::new(),
             queue: Some(queue),
+            backend: None,
         }
     }
     fn url_sort_param_for_column(&self, column: usize) -> &'static str {
@@ -52,10 +54,11 @@ impl WorkerProvider {
         }
         match self.queue.map(|s| s.get_untracked()) {
             Some(ref q) if !q.is_empty() => {
-                format!("{API_PATH}/queues/{q}/workers?{sort}",)
+                format!("/queues/{q}/workers?{sort}")
             }
             _ => {
-                format!("{API_PATH}/workers?{sort}",)
+                let backend = self.backend.map(|b| b.get_untracked()).unwrap_or_default();
+                with_backend(&format!("/workers?{sort}"), &backend)
             }
         }
     }
@@ -66,13 +69,7 @@ impl TableDataProvider<Worker, usize> for WorkerProvider {
         let url = self.get_url();
         let Range { start, .. } = range;
 
-        let resp: Vec<Worker> = Request::get(&url)
-            .send()
-            .await
-            .map_err(|e| e.to_string())?
-            .json()
-            .await
-            .map_err(|e| e.to_string())?;
+        let resp: Vec<Worker> = ApiClient::get(&url).await?;
         if start > 0 {
             return Ok((vec![], start..start));
         }
@@ -83,15 +80,7 @@ impl TableDataProvider<Worker, usize> for WorkerProvider {
 
     async fn row_count(&self) -> Option<usize> {
         let url = self.get_url();
-        let resp: Vec<Worker> = Request::get(&url)
-            .send()
-            .await
-            .map_err(|e| e.to_string())
-            .ok()?
-            .json()
-            .await
-            .map_err(|e| e.to_string())
-            .ok()?;
+        let resp: Vec<Worker> = ApiClient::get(&url).await.ok()?;
         Some(resp.len())
     }
 
@@ -103,5 +92,8 @@ impl TableDataProvider<Worker, usize> for WorkerProvider {
         if let Some(q) = self.queue {
             q.track()
         }
+        if let Some(b) = self.backend {
+            b.track()
+        }
     }
 }
@@ -7,7 +7,7 @@ use apalis::{
     },
 };
 use apalis_board::axum::{
-    framework::{ApiBuilder, RegisterRoute},
+    framework::{ApiBuilder, QueueOptions, RegisterRoute},
     sse::{TracingBroadcaster, TracingSubscriber},
     ui::ServeUI,
 };
@@ -19,6 +19,7 @@ use lettre::{
     AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
     transport::smtp::authentication::Mechanism,
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use sqlx::PgPool;
 use tokio::signal::ctrl_c;
@@ -55,7 +56,7 @@ enum MessagePackError {
     Decode(#[from] rmp_serde::decode::Error),
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 pub struct Email {
     pub to: String,
     pub subject: String,
@@ -126,7 +127,16 @@ async fn main() {
 
     let http = async move {
         let api = ApiBuilder::new(Router::new())
-            .register(email_store.clone())
+            .register_with(
+                email_store.clone(),
+                QueueOptions::new().json_schema().validate(|email: &Email| {
+                    if email.to.contains('@') {
+                        Ok(())
+                    } else {
+                        Err(format!("`{}` is not an email address", email.to))
+                    }
+                }),
+            )
             .build();
         let router = Router::new()
             .nest("/api/v1", api)
@@ -0,0 +1,144 @@
+//! Connecting to the configured databases and serving their queues.
+
+use apalis_board::axum::{backend::BackendAdapter, framework::QueueOptions, registry::Registry};
+use apalis_core::backend::ListQueues;
+use apalis_postgres::{Config as QueueConfig, PgPool, PostgresStorage};
+use apalis_sqlite::{SqlitePool, SqliteStorage, SqlxError};
+use serde_json::Value;
+
+use crate::config::{BackendConfig, ConfigError, Kind};
+
+/// A connection to a configured database.
+#[derive(Debug)]
+enum Pool {
+    Sqlite(SqlitePool),
+    Postgres(PgPool),
+}
+
+/// A database whose queues are served under its name.
+///
+/// Queues are served with JSON arguments, the codec of the SQL backends.
+#[derive(Debug)]
+pub(crate) struct Backend {
+    name: String,
+    pool: Pool,
+}
+
+/// Why a backend couldn't be served.
+#[derive(Debug)]
+pub(crate) enum BackendError {
+    /// The backend is misconfigured.
+    Config(ConfigError),
+    /// The database couldn't be reached.
+    Sql(String, SqlxError),
+}
+
+impl std::fmt::Display for BackendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Config(e) => e.fmt(f),
+            Self::Sql(name, e) => write!(f, "Backend `{name}` failed: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for BackendError {}
+
+impl Backend {
+    /// Connect to the database of `config`.
+    pub(crate) async fn connect(config: &BackendConfig) -> Result<Self, BackendError> {
+        let name = config.name().map_err(BackendError::Config)?;
+        let pool = match config.kind().map_err(BackendError::Config)? {
+            Kind::Sqlite => SqlitePool::connect(&config.url).await.map(Pool::Sqlite),
+            Kind::Postgres => PgPool::connect(&config.url).await.map(Pool::Postgres),
+        }
+        .map_err(|e| BackendError::Sql(name.clone(), e))?;
+        Ok(Self { name, pool })
+    }
+
+    /// The name the board shows.
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Serve the queues of the database that aren't served yet, returning
+    /// their names.
+    ///
+    /// A queue already served by another backend is skipped, as queues are
+    /// looked up by name.
+    pub(crate) async fn discover(&self, registry: &Registry) -> Result<Vec<String>, BackendError> {
+        let queues = match &self.pool {
+            Pool::Sqlite(pool) => SqliteStorage::<Value, _, _>::new(pool).list_queues().await,
+            Pool::Postgres(pool) => PostgresStorage::<Value>::new(pool).list_queues().await,
+        }
+        .map_err(|e| BackendError::Sql(self.name.clone(), e))?;
+
+        let mut added = Vec::new();
+        for queue in queues {
+            if let Some(served) = registry.queue(&queue.name) {
+                if served.name() != self.name {
+                    tracing::warn!(
+                        "Queue `{}` of `{}` is already served by `{}`",
+                        queue.name,
+                        self.name,
+                        served.name()
+                    );
+                }
+                continue;
+            }
+            self.register(registry, &queue.name);
+            added.push(queue.name);
+        }
+        Ok(added)
+    }
+
+    fn register(&self, registry: &Registry, queue: &str) {
+        let options = QueueOptions::<Value>::new();
+        match &self.pool {
+            Pool::Sqlite(pool) => registry.register(
+                BackendAdapter::new(SqliteStorage::new_in_queue(pool, queue), options)
+                    .named(&self.name),
+            ),
+            Pool::Postgres(pool) => registry.register(
+                BackendAdapter::new(
+                    PostgresStorage::new_with_config(pool, &QueueConfig::new(queue)),
+                    options,
+                )
+                .named(&self.name),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use apalis_core::backend::TaskSink;
+    use apalis_sqlite::PoolOptions;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_new_queues_are_discovered() {
+        // A single connection, every connection to `:memory:` opens a new database
+        let pool = PoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        SqliteStorage::setup(&pool).await.unwrap();
+        let backend = Backend {
+            name: "tasks".to_owned(),
+            pool: Pool::Sqlite(pool.clone()),
+        };
+        let registry = Registry::default();
+        assert!(backend.discover(&registry).await.unwrap().is_empty());
+
+        let mut emails = SqliteStorage::<String, _, _>::new_in_queue(&pool, "emails");
+        emails.push("ada@example.com".to_owned()).await.unwrap();
+        assert_eq!(backend.discover(&registry).await.unwrap(), ["emails"]);
+        assert!(backend.discover(&registry).await.unwrap().is_empty());
+
+        let queue = registry.queue("emails").unwrap();
+        assert_eq!(queue.name(), "tasks");
+    }
+}
@@ -0,0 +1,294 @@
+//! The configuration of the server, read from a TOML file and the command line.
+
+use std::{collections::HashSet, fmt, net::SocketAddr, path::PathBuf, str::FromStr};
+
+use clap::Parser;
+use serde::Deserialize;
+
+/// Serve the board for the queues of one or more apalis backends.
+#[derive(Debug, Default, Parser)]
+#[command(name = "apalis-board", version)]
+pub(crate) struct Cli {
+    /// A TOML file to read the configuration from.
+    #[arg(short, long, value_name = "FILE")]
+    pub(crate) config: Option<PathBuf>,
+
+    /// A backend to serve, e.g. `sqlite://tasks.db` or `orders=postgres://localhost/orders`.
+    ///
+    /// May be repeated, the backends are added to those of the configuration file.
+    #[arg(short, long = "backend", value_name = "[NAME=]URL")]
+    pub(crate) backends: Vec<BackendConfig>,
+
+    /// The address to listen on, `127.0.0.1:8000` by default.
+    #[arg(short, long, value_name = "ADDR")]
+    pub(crate) listen: Option<SocketAddr>,
+
+    /// Where to mount the API, `/api/v1` by default.
+    #[arg(long, value_name = "PATH")]
+    pub(crate) api_path: Option<String>,
+
+    /// The URL the board is reached at behind a proxy, `/` by default.
+    #[arg(long, value_name = "URL")]
+    pub(crate) public_url: Option<String>,
+
+    /// How often to look for new queues, 30 seconds by default, 0 to never.
+    #[arg(long, value_name = "SECONDS")]
+    pub(crate) discover_every: Option<u64>,
+
+    /// Only let the board read, not push or change tasks.
+    #[arg(long)]
+    pub(crate) read_only: bool,
+
+    /// Which logs to print and stream to the board, e.g. `info,sqlx=warn`.
+    #[arg(long, value_name = "FILTER", default_value = "info")]
+    pub(crate) log: String,
+}
+
+/// The configuration of the server.
+///
+/// ```toml
+/// listen = "0.0.0.0:8000"
+/// discover_every = 60
+///
+/// [[backend]]
+/// url = "sqlite://tasks.db"
+///
+/// [[backend]]
+/// name = "orders"
+/// url = "postgres://board@localhost/orders"
+/// ```
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Config {
+    /// The address to listen on.
+    pub(crate) listen: SocketAddr,
+    /// Where the API is mounted.
+    pub(crate) api_path: String,
+    /// The URL the board is reached at.
+    pub(crate) public_url: String,
+    /// How often to look for new queues, in seconds, 0 to never.
+    pub(crate) discover_every: u64,
+    /// Whether the board may only read.
+    pub(crate) read_only: bool,
+    /// The backends to serve.
+    #[serde(rename = "backend")]
+    pub(crate) backends: Vec<BackendConfig>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            listen: SocketAddr::from(([127, 0, 0, 1], 8000)),
+            api_path: "/api/v1".to_owned(),
+            public_url: "/".to_owned(),
+            discover_every: 30,
+            read_only: false,
+            backends: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Read the configuration file named by `cli`, if any, and apply its flags.
+    pub(crate) fn load(cli: &Cli) -> Result<Self, ConfigError> {
+        let config = match &cli.config {
+            Some(path) => {
+                let text = std::fs::read_to_string(path)
+                    .map_err(|e| ConfigError::Read(path.clone(), e))?;
+                Self::parse(&text)?
+            }
+            None => Self::default(),
+        };
+        config.merge(cli).validate()
+    }
+
+    /// Parse a TOML configuration.
+    pub(crate) fn parse(text: &str) -> Result<Self, ConfigError> {
+        toml::from_str(text).map_err(ConfigError::Parse)
+    }
+
+    /// Override the configuration with the flags given on the command line.
+    fn merge(mut self, cli: &Cli) -> Self {
+        if let Some(listen) = cli.listen {
+            self.listen = listen;
+        }
+        if let Some(path) = &cli.api_path {
+            self.api_path.clone_from(path);
+        }
+        if let Some(url) = &cli.public_url {
+            self.public_url.clone_from(url);
+        }
+        if let Some(every) = cli.discover_every {
+            self.discover_every = every;
+        }
+        self.read_only |= cli.read_only;
+        self.backends.extend(cli.backends.iter().cloned());
+        self
+    }
+
+    /// Check there are backends to serve and that they can be told apart.
+    fn validate(self) -> Result<Self, ConfigError> {
+        if self.backends.is_empty() {
+            return Err(ConfigError::NoBackend);
+        }
+        let mut names = HashSet::new();
+        for backend in &self.backends {
+            backend.kind()?;
+            if !names.insert(backend.name()?) {
+                return Err(ConfigError::DuplicateName(backend.name()?));
+            }
+        }
+        Ok(self)
+    }
+}
+
+/// A backend to serve.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct BackendConfig {
+    /// The name the board shows, the storage type by default.
+    #[serde(default)]
+    pub(crate) name: Option<String>,
+    /// The URL of the database, `sqlite://` or `postgres://`.
+    pub(crate) url: String,
+}
+
+/// The kinds of databases the server connects to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Kind {
+    Sqlite,
+    Postgres,
+}
+
+impl BackendConfig {
+    /// The kind of database, from the scheme of the URL.
+    pub(crate) fn kind(&self) -> Result<Kind, ConfigError> {
+        match self.url.split_once(':').map(|(scheme, _)| scheme) {
+            Some("sqlite") => Ok(Kind::Sqlite),
+            Some("postgres" | "postgresql") => Ok(Kind::Postgres),
+            _ => Err(ConfigError::UnsupportedUrl(self.url.clone())),
+        }
+    }
+
+    /// The name the board shows.
+    pub(crate) fn name(&self) -> Result<String, ConfigError> {
+        if let Some(name) = &self.name {
+            return Ok(name.clone());
+        }
+        Ok(match self.kind()? {
+            Kind::Sqlite => "SqliteStorage",
+            Kind::Postgres => "PostgresStorage",
+        }
+        .to_owned())
+    }
+}
+
+impl FromStr for BackendConfig {
+    type Err = String;
+
+    /// Parse `URL` or `NAME=URL`, a `=` in the URL itself doesn't name it.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let scheme = s.find("://").ok_or_else(|| format!("`{s}` is not a URL"))?;
+        Ok(match s[..scheme].split_once('=') {
+            Some((name, _)) => Self {
+                name: Some(name.to_owned()),
+                url: s[name.len() + 1..].to_owned(),
+            },
+            None => Self {
+                name: None,
+                url: s.to_owned(),
+            },
+        })
+    }
+}
+
+/// Why the configuration couldn't be loaded.
+#[derive(Debug)]
+pub(crate) enum ConfigError {
+    /// The configuration file couldn't be read.
+    Read(PathBuf, std::io::Error),
+    /// The configuration file isn't valid.
+    Parse(toml::de::Error),
+    /// No backend was configured.
+    NoBackend,
+    /// The URL of a backend names no supported database.
+    UnsupportedUrl(String),
+    /// Two backends have the same name.
+    DuplicateName(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read(path, e) => write!(f, "Could not read `{}`: {e}", path.display()),
+            Self::Parse(e) => write!(f, "Invalid configuration: {e}"),
+            Self::NoBackend => write!(f, "No backend to serve, pass one with `--backend`"),
+            Self::UnsupportedUrl(url) => {
+                write!(f, "`{url}` is not a `sqlite://` or `postgres://` URL")
+            }
+            Self::DuplicateName(name) => write!(
+                f,
+                "Several backends are named `{name}`, give them distinct names"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flags_override_the_file() {
+        let config = Config::parse(
+            r#"
+            listen = "0.0.0.0:9000"
+            api_path = "/board/api"
+
+            [[backend]]
+            url = "sqlite://tasks.db?mode=rwc"
+            "#,
+        )
+        .unwrap();
+        let cli = Cli::try_parse_from([
+            "apalis-board",
+            "--listen",
+            "127.0.0.1:8080",
+            "--backend",
+            "orders=postgres://board@localhost/orders",
+        ])
+        .unwrap();
+
+        let config = config.merge(&cli).validate().unwrap();
+        assert_eq!(config.listen, SocketAddr::from(([127, 0, 0, 1], 8080)));
+        assert_eq!(config.api_path, "/board/api");
+        assert_eq!(config.discover_every, 30);
+        assert_eq!(config.backends[0].name().unwrap(), "SqliteStorage");
+        assert_eq!(config.backends[0].url, "sqlite://tasks.db?mode=rwc");
+        assert_eq!(config.backends[1].name().unwrap(), "orders");
+        assert_eq!(config.backends[1].kind().unwrap(), Kind::Postgres);
+    }
+
+    #[test]
+    fn test_backends_must_be_told_apart() {
+        let cli =
+            Cli::try_parse_from(["apalis-board", "-b", "sqlite://a.db", "-b", "sqlite://b.db"])
+                .unwrap();
+        assert!(matches!(
+            Config::load(&cli),
+            Err(ConfigError::DuplicateName(name)) if name == "SqliteStorage"
+        ));
+
+        let cli = Cli::try_parse_from(["apalis-board", "-b", "redis://localhost"]).unwrap();
+        assert!(matches!(
+            Config::load(&cli),
+            Err(ConfigError::UnsupportedUrl(_))
+        ));
+        assert!(matches!(
+            Config::load(&Cli::default()),
+            Err(ConfigError::NoBackend)
+        ));
+    }
+}
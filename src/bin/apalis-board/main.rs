@@ -0,0 +1,121 @@
+//! A standalone board for the queues of one or more apalis backends.
+//!
+//! ```sh
+//! apalis-board --backend sqlite://tasks.db --backend postgres://localhost/app
+//! apalis-board --config board.toml
+//! ```
+//!
+//! Every queue of the databases is discovered and served with the API and the
+//! frontend, new queues are picked up while the server runs.
+
+use std::{net::SocketAddr, process::ExitCode, time::Duration};
+
+use apalis_board::axum::{
+    framework::ApiBuilder,
+    registry::Registry,
+    sse::{TracingBroadcaster, TracingSubscriber},
+    ui::ServeUI,
+};
+use axum::{Extension, Router};
+use clap::Parser;
+use tracing_subscriber::{EnvFilter, Layer, layer::SubscriberExt, util::SubscriberInitExt};
+
+use crate::{
+    backend::Backend,
+    config::{Cli, Config},
+};
+
+mod backend;
+mod config;
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(Cli::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load(&cli)?;
+
+    let broadcaster = TracingBroadcaster::create();
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer().with_filter(EnvFilter::try_new(&cli.log)?))
+        .with(
+            TracingSubscriber::new(&broadcaster)
+                .layer()
+                .with_filter(EnvFilter::try_new(&cli.log)?),
+        )
+        .try_init()?;
+
+    let mut backends = Vec::with_capacity(config.backends.len());
+    for backend in &config.backends {
+        backends.push(Backend::connect(backend).await?);
+    }
+
+    let mut api = ApiBuilder::new(Router::new());
+    if config.read_only {
+        api = api.read_only();
+    }
+    let registry = api.registry();
+    discover(&backends, &registry).await?;
+    if config.discover_every > 0 {
+        let every = Duration::from_secs(config.discover_every);
+        tokio::spawn(rediscover(backends, registry, every));
+    }
+
+    let router = Router::new()
+        .nest(&config.api_path, api.build())
+        .fallback_service(
+            ServeUI::new()
+                .api_path(&config.api_path)
+                .public_url(&config.public_url),
+        )
+        .layer(Extension(broadcaster));
+
+    let listener = tokio::net::TcpListener::bind(config.listen).await?;
+    tracing::info!("Serving the board on http://{}", listener.local_addr()?);
+    // The peer address is what the audit log records for each change
+    axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async {
+        let _ = tokio::signal::ctrl_c().await;
+    })
+    .await?;
+    Ok(())
+}
+
+/// Serve the queues of every backend, failing if one can't be listed.
+async fn discover(backends: &[Backend], registry: &Registry) -> Result<(), backend::BackendError> {
+    for backend in backends {
+        let queues = backend.discover(registry).await?;
+        tracing::info!("Serving {} queues of `{}`", queues.len(), backend.name());
+    }
+    Ok(())
+}
+
+/// Look for new queues every `every`, logging the backends that fail.
+async fn rediscover(backends: Vec<Backend>, registry: Registry, every: Duration) {
+    let mut interval = tokio::time::interval(every);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        for backend in &backends {
+            match backend.discover(&registry).await {
+                Ok(queues) => {
+                    for queue in queues {
+                        tracing::info!("Serving new queue `{queue}` of `{}`", backend.name());
+                    }
+                }
+                Err(e) => tracing::warn!("{e}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,2 @@
+#[cfg(feature = "tower")]
+pub use apalis_board_api::*;